@@ -0,0 +1,270 @@
+//! `--config`, a TOML file with the options not worth typing on every run
+//!
+//! ```toml
+//! [defaults]
+//! concurrency = 5
+//! delay = 500
+//! proxy = "socks5://127.0.0.1:1080"
+//! output_dir = "/media/sdcard/novels"
+//!
+//! [sites.czbooks]
+//! concurrency = 2
+//! delay = 1500
+//!
+//! [sites."https://tw.uukanshu.com/"]
+//! delay_ms = 200
+//! user_agent = "Mozilla/5.0 ..."
+//! retry_max = 8
+//!
+//! [sites.piaotia]
+//! encoding = "gbk"
+//! ```
+//!
+//! without `--config`, `get_novel.toml` in `~/.config/get_novel/` or next to the executable
+
+use crate::noveler::NovelError;
+use encoding_rs::Encoding;
+use serde::{de, Deserialize, Deserializer};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::{env, fs};
+use url::Url;
+
+const CONFIG_FILE: &str = "get_novel.toml";
+
+#[derive(Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct Config {
+    pub(crate) defaults: Defaults,
+    /// keyed by `site_key` or by a url prefix, the longest matching prefix wins over `site_key`
+    pub(crate) sites: HashMap<String, SiteConfig>,
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct Defaults {
+    pub(crate) concurrency: Option<usize>,
+    /// milliseconds before each chapter fetch
+    #[serde(alias = "delay_ms")]
+    pub(crate) delay: Option<u64>,
+    pub(crate) proxy: Option<String>,
+    pub(crate) user_agent: Option<String>,
+    /// retries of a failed chapter fetch
+    pub(crate) retry_max: Option<u32>,
+    pub(crate) output_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct SiteConfig {
+    pub(crate) concurrency: Option<usize>,
+    /// milliseconds before each chapter fetch
+    #[serde(alias = "delay_ms")]
+    pub(crate) delay: Option<u64>,
+    pub(crate) user_agent: Option<String>,
+    /// retries of a failed chapter fetch
+    pub(crate) retry_max: Option<u32>,
+    /// decode the pages of the site with this, a label like `gbk` or `big5`
+    #[serde(deserialize_with = "deserialize_encoding")]
+    pub(crate) encoding: Option<&'static Encoding>,
+}
+
+fn deserialize_encoding<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<&'static Encoding>, D::Error> {
+    let label = String::deserialize(deserializer)?;
+    Encoding::for_label(label.as_bytes())
+        .map(Some)
+        .ok_or_else(|| de::Error::custom(format!("unknown encoding `{label}`")))
+}
+
+impl Config {
+    pub(crate) fn load(path: &Path) -> Result<Self, NovelError> {
+        Ok(toml::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    /// the first of `default_paths` which exists
+    pub(crate) fn find() -> Option<PathBuf> {
+        default_paths().into_iter().find(|path| path.is_file())
+    }
+
+    /// the table of the site of `url`, `[defaults]` for what it leaves out
+    pub(crate) fn site(&self, url: &str) -> SiteConfig {
+        let by_prefix = self
+            .sites
+            .iter()
+            .filter(|(key, _)| key.contains("://") && url.starts_with(key.as_str()))
+            .max_by_key(|(key, _)| key.len())
+            .map(|(_, site)| site);
+        let site = by_prefix
+            .or_else(|| self.sites.get(site_key(&Url::parse(url).ok()?)?))
+            .cloned()
+            .unwrap_or_default();
+        SiteConfig {
+            concurrency: site.concurrency.or(self.defaults.concurrency),
+            delay: site.delay.or(self.defaults.delay),
+            user_agent: site.user_agent.or_else(|| self.defaults.user_agent.clone()),
+            retry_max: site.retry_max.or(self.defaults.retry_max),
+            encoding: site.encoding,
+        }
+    }
+}
+
+/// `$XDG_CONFIG_HOME/get_novel/` or `~/.config/get_novel/`, then the dir of the executable
+fn default_paths() -> Vec<PathBuf> {
+    let config_home = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")));
+    let exe_dir = env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf));
+    [config_home.map(|dir| dir.join("get_novel")), exe_dir]
+        .into_iter()
+        .flatten()
+        .map(|dir| dir.join(CONFIG_FILE))
+        .collect()
+}
+
+/// the label before the top-level domain, `czbooks` for `czbooks.net` and `uukanshu` for
+/// `tw.uukanshu.com`, so the mirrors of a site share one table
+pub(crate) fn site_key(url: &Url) -> Option<&str> {
+    url.host_str()?.rsplit('.').nth(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    static CONFIG: &str = r#"
+[defaults]
+concurrency = 5
+delay = 500
+proxy = "socks5://127.0.0.1:1080"
+output_dir = "/media/sdcard/novels"
+
+[sites.czbooks]
+concurrency = 2
+delay = 1500
+
+[sites.uukanshu]
+delay = 200
+
+[sites."https://tw.uukanshu.com/"]
+delay_ms = 300
+user_agent = "tw"
+retry_max = 8
+
+[sites.piaotia]
+encoding = "gb2312"
+"#;
+
+    #[test]
+    fn test_site_key() {
+        let key = |url: &str| site_key(&Url::parse(url).unwrap()).map(str::to_string);
+        assert_eq!(
+            key("https://czbooks.net/n/uilla7"),
+            Some("czbooks".to_string())
+        );
+        assert_eq!(
+            key("https://tw.uukanshu.com/b/1/"),
+            Some("uukanshu".to_string())
+        );
+        assert_eq!(
+            key("https://www.biquge.info/1_1/"),
+            Some("biquge".to_string())
+        );
+        assert_eq!(key("http://localhost:8080/"), None);
+    }
+
+    #[test]
+    fn test_config_site() {
+        let config: Config = toml::from_str(CONFIG).unwrap();
+        assert_eq!(
+            config.defaults.proxy.as_deref(),
+            Some("socks5://127.0.0.1:1080")
+        );
+        assert_eq!(
+            config.defaults.output_dir,
+            Some(PathBuf::from("/media/sdcard/novels"))
+        );
+        assert_eq!(
+            config.site("https://czbooks.net/n/uilla7"),
+            SiteConfig {
+                concurrency: Some(2),
+                delay: Some(1500),
+                ..SiteConfig::default()
+            }
+        );
+        // the site table leaves concurrency to `[defaults]`
+        assert_eq!(
+            config.site("https://www.uukanshu.com/b/1/"),
+            SiteConfig {
+                concurrency: Some(5),
+                delay: Some(200),
+                ..SiteConfig::default()
+            }
+        );
+        // the url prefix wins over the site key of the mirror
+        assert_eq!(
+            config.site("https://tw.uukanshu.com/b/1/"),
+            SiteConfig {
+                concurrency: Some(5),
+                delay: Some(300),
+                user_agent: Some("tw".to_string()),
+                retry_max: Some(8),
+                encoding: None,
+            }
+        );
+        assert_eq!(
+            config.site("https://www.piaotia.com/html/14/14881/"),
+            SiteConfig {
+                concurrency: Some(5),
+                delay: Some(500),
+                encoding: Some(encoding_rs::GBK),
+                ..SiteConfig::default()
+            }
+        );
+        assert_eq!(
+            config.site("https://www.biquge.info/1_1/"),
+            SiteConfig {
+                concurrency: Some(5),
+                delay: Some(500),
+                ..SiteConfig::default()
+            }
+        );
+        assert_eq!(
+            Config::default().site("https://czbooks.net/n/uilla7"),
+            SiteConfig::default()
+        );
+    }
+
+    #[test]
+    fn test_config_load() {
+        let dir = TempDir::new("config_test_load").unwrap();
+        let path = dir.path().join("get_novel.toml");
+        fs::write(&path, CONFIG).unwrap();
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.sites.len(), 4);
+
+        fs::write(&path, "[defaults]\nconcurency = 5\n").unwrap();
+        assert!(matches!(Config::load(&path), Err(NovelError::TomlError(_))));
+        fs::write(&path, "[sites.piaotia]\nencoding = \"gbk2\"\n").unwrap();
+        assert!(matches!(Config::load(&path), Err(NovelError::TomlError(_))));
+        assert!(Config::load(&dir.path().join("missing.toml")).is_err());
+
+        dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_default_paths() {
+        let paths = default_paths();
+        assert!(paths.iter().all(|path| path.ends_with(CONFIG_FILE)));
+        let exe_dir = env::current_exe().unwrap().parent().unwrap().to_path_buf();
+        assert_eq!(paths.last(), Some(&exe_dir.join(CONFIG_FILE)));
+        if env::var_os("HOME").is_some() {
+            assert!(paths[0].ends_with(Path::new("get_novel").join(CONFIG_FILE)));
+        }
+    }
+}
@@ -1,33 +1,76 @@
-use reqwest::{Client, IntoUrl};
-use std::collections::HashSet;
+use crate::robots;
+use chardetng::EncodingDetector;
+use indicatif::{ProgressBar, ProgressStyle};
+use rand::Rng;
+use regex::Regex;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{Client, IntoUrl, Proxy, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Display;
-use std::io::Write;
+use std::hash::{Hash, Hasher};
+use std::io::{Read, Write};
+use std::num::NonZeroUsize;
+use std::ops::RangeInclusive;
 use std::panic;
-use std::sync::Arc;
-use std::time::Duration;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant, SystemTime};
 use std::{
     fmt, fs, io,
     path::{Path, PathBuf},
 };
 use thiserror::Error;
-use tokio::sync::{mpsc, Semaphore};
+use tokio::sync::{mpsc, Mutex, OwnedSemaphorePermit, Semaphore};
 use tokio::task::JoinSet;
 use url::Url;
 use visdom::types::Elements;
 
+mod biquge;
 mod czbooks;
+mod du123;
+mod dxmwx;
 mod hjwzw;
+mod jjwxc_api;
 mod novel543;
 mod piaotia;
+mod piaotian_net;
 mod qbtr;
+mod qidian;
+mod qimao;
+mod readm;
+mod shuhai;
+mod tadu;
 mod uukanshu;
+mod wfxs;
+mod wnmtl;
+mod xbiquge_so;
+mod yunqi;
+mod zongheng;
 
+pub(crate) use biquge::Biquge;
 pub(crate) use czbooks::Czbooks;
+pub(crate) use du123::Du123;
+pub(crate) use dxmwx::Dxmwx;
 pub(crate) use hjwzw::Hjwzw;
+pub(crate) use jjwxc_api::JjwxcApi;
 pub(crate) use novel543::Novel543;
 pub(crate) use piaotia::Piaotia;
+pub(crate) use piaotian_net::PiaotianNet;
 pub(crate) use qbtr::Qbtr;
+pub(crate) use qidian::Qidian;
+pub(crate) use qimao::Qimao;
+pub(crate) use readm::Readm;
+pub(crate) use shuhai::Shuhai;
+pub(crate) use tadu::Tadu;
 pub(crate) use uukanshu::UUkanshu;
+pub(crate) use wfxs::Wfxs;
+pub(crate) use wnmtl::Wnmtl;
+pub(crate) use xbiquge_so::XbiqugeSo;
+pub(crate) use yunqi::Yunqi;
+pub(crate) use zongheng::Zongheng;
 
 #[derive(Error, Debug)]
 pub(crate) enum NovelError {
@@ -47,20 +90,72 @@ pub(crate) enum NovelError {
     AhoCorasickError(#[from] aho_corasick::BuildError),
     #[error("Regex fail {0}")]
     RegexError(#[from] regex::Error),
+    #[error("serde_json fail {0}")]
+    SerdeJsonError(#[from] serde_json::Error),
+    #[error("toml fail {0}")]
+    TomlError(#[from] toml::de::Error),
+    #[error("Skipping '{0}' (does not match filter)")]
+    Skipped(String),
+    #[error("header value fail {0}")]
+    InvalidHeaderValue(#[from] reqwest::header::InvalidHeaderValue),
+    #[error("{0} is not supported")]
+    UnsupportedSite(String),
+    #[error("{0} is a VIP chapter, the session cookie has no access to it")]
+    VipChapter(String),
+    /// HTTP 429 with the wait of its `Retry-After`
+    #[error("too many requests, retry after {0:?}")]
+    TooManyRequests(Option<Duration>),
+    #[error("{0} is disallowed by robots.txt")]
+    DisallowedByRobotsTxt(String),
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub(crate) struct Book {
     name: String,
     author: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    synopsis: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cover_url: Option<Url>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    /// as the contents page shows it, not counted from the chapters
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    word_count: Option<usize>,
 }
 
 impl fmt::Display for Book {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}_{}", self.author, self.name)
+        // also the name of the book dir
+        let name = format!("{}_{}", self.author, self.name);
+        write!(f, "{}", sanitize_path_component(&name))
     }
 }
 
+/// the first number in `text`, times the `萬` or `億` after it, e.g. `744.69萬字` or `字数：7446890`
+pub(crate) fn parse_word_count(text: &str) -> Option<usize> {
+    static WORD_COUNT: OnceLock<Regex> = OnceLock::new();
+    let re = WORD_COUNT.get_or_init(|| {
+        Regex::new(r"(\d[\d,]*)(?:\.(\d+))?\s*([萬万億亿])?").expect("word count pattern")
+    });
+
+    let caps = re.captures(text)?;
+    let unit: usize = match caps.get(3).map(|unit| unit.as_str()) {
+        Some("萬" | "万") => 10_000,
+        Some("億" | "亿") => 100_000_000,
+        _ => 1,
+    };
+    let whole: usize = caps[1].replace(',', "").parse().ok()?;
+    let mut count = whole.checked_mul(unit)?;
+    // the fraction only counts with a unit, digits past the unit are dropped
+    let mut scale = unit;
+    for digit in caps.get(2).map_or("", |fraction| fraction.as_str()).chars() {
+        scale /= 10;
+        count = count.checked_add(digit.to_digit(10)? as usize * scale)?;
+    }
+    Some(count)
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub(crate) struct Chapter {
     order: String,
@@ -72,6 +167,126 @@ impl Chapter {
     pub(crate) fn content(&self) -> String {
         format!("{}\n\n{}", self.title, self.text)
     }
+
+    /// characters of the text, the title and whitespace left out
+    pub(crate) fn word_count(&self) -> usize {
+        count_words(&self.text)
+    }
+}
+
+/// every char but whitespace, CJK and ascii alike
+fn count_words(text: &str) -> usize {
+    text.chars().filter(|c| !c.is_whitespace()).count()
+}
+
+/// full-width ascii (U+FF01–U+FF5E) and the ideographic space to ascii, except the chars in `keep`
+fn normalize_fullwidth(text: &str, keep: &[char]) -> String {
+    text.chars()
+        .map(|c| if keep.contains(&c) { c } else { halfwidth(c) })
+        .collect()
+}
+
+fn halfwidth(c: char) -> char {
+    match c {
+        '\u{3000}' => ' ',
+        '\u{ff01}'..='\u{ff5e}' => char::from_u32(c as u32 - 0xfee0).unwrap_or(c),
+        _ => c,
+    }
+}
+
+/// drop the lines containing any of `patterns`, plain substrings instead of regexes
+pub(crate) fn strip_navigation_text(text: &str, patterns: &[&str]) -> String {
+    text.lines()
+        .filter(|line| !patterns.iter().any(|pattern| line.contains(pattern)))
+        .collect::<Vec<&str>>()
+        .join("\n")
+}
+
+type TextStep = Box<dyn Fn(&str) -> String + Send + Sync>;
+
+/// cleans the chapter text, the steps run in the order they are added
+#[derive(Default)]
+pub(crate) struct TextProcessor {
+    steps: Vec<TextStep>,
+}
+
+impl fmt::Debug for TextProcessor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TextProcessor")
+            .field("steps", &self.steps.len())
+            .finish()
+    }
+}
+
+impl TextProcessor {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// a step the builder does not cover
+    pub(crate) fn add_step(
+        mut self,
+        step: impl Fn(&str) -> String + Send + Sync + 'static,
+    ) -> Self {
+        self.steps.push(Box::new(step));
+        self
+    }
+
+    pub(crate) fn add_regex_replacement(
+        self,
+        pattern: &str,
+        replacement: &str,
+    ) -> Result<Self, NovelError> {
+        let re = Regex::new(pattern)?;
+        let replacement = replacement.to_string();
+        Ok(self.add_step(move |text| re.replace_all(text, replacement.as_str()).to_string()))
+    }
+
+    /// one line per piece split by `separators`, each trimmed
+    pub(crate) fn add_line_split_and_trim(self, separators: &[char]) -> Self {
+        let separators = separators.to_vec();
+        self.add_step(move |text| {
+            text.split(separators.as_slice())
+                .map(str::trim)
+                .collect::<Vec<&str>>()
+                .join("\n")
+        })
+    }
+
+    pub(crate) fn add_skip_first_n_lines(self, n: usize) -> Self {
+        self.add_step(move |text| text.lines().skip(n).collect::<Vec<&str>>().join("\n"))
+    }
+
+    /// `normalize_fullwidth` with `keep`
+    pub(crate) fn add_fullwidth_normalization(self, keep: &[char]) -> Self {
+        let keep = keep.to_vec();
+        self.add_step(move |text| normalize_fullwidth(text, &keep))
+    }
+
+    /// `strip_navigation_text` with `patterns`
+    pub(crate) fn add_strip_navigation_text(self, patterns: &[&str]) -> Self {
+        let patterns: Vec<String> = patterns.iter().map(ToString::to_string).collect();
+        self.add_step(move |text| {
+            let patterns: Vec<&str> = patterns.iter().map(String::as_str).collect();
+            strip_navigation_text(text, &patterns)
+        })
+    }
+
+    /// drop the lines with only whitespace
+    pub(crate) fn add_filter_empty_lines(self) -> Self {
+        self.add_step(|text| {
+            text.lines()
+                .filter(|s| !s.trim().is_empty())
+                .collect::<Vec<&str>>()
+                .join("\n")
+        })
+    }
+
+    pub(crate) fn process(&self, text: &str) -> String {
+        self.steps
+            .iter()
+            .fold(text.to_string(), |text, step| step(&text))
+    }
 }
 
 pub trait Noveler: Display + Sync + Send + 'static {
@@ -79,28 +294,112 @@ pub trait Noveler: Display + Sync + Send + 'static {
         None
     }
 
+    /// for mirrors which switch between encodings, `need_encoding` is used when the guess is unsure
+    fn auto_detect_encoding(&self) -> bool {
+        false
+    }
+
+    /// `encoding` is the one `--config` sets for the site, it wins over `need_encoding`
     fn process_url(
         &self,
         client: Client,
         order: &str,
         url: Url,
+        encoding: Option<&'static encoding_rs::Encoding>,
     ) -> impl std::future::Future<Output = Result<(Chapter, Option<Url>), NovelError>> + Send {
-        async {
-            let document = get_html_and_fix_encoding(client, url, self.need_encoding()).await?;
+        async move {
+            let encoding = page_encoding(self, encoding);
+            let document = get_html_and_fix_encoding(client, url, encoding).await?;
             let document = visdom::Vis::load(document)?;
 
             let mut chapter: Chapter = self.get_chapter(&document, order)?;
+            if let Some(limit) = self.get_chapter_word_limit() {
+                if chapter.text.chars().count() > limit {
+                    tracing::warn!(order, limit, "chapter text exceeds word limit");
+                    return Err(NovelError::NotFound(WORD_LIMIT_EXCEEDED.to_string()));
+                }
+            }
             chapter = self.process_chapter(chapter);
 
-            let next_page = self.get_next_page(&document)?;
+            let next_page = if self.is_last_chapter(&document) {
+                None
+            } else {
+                self.get_next_page(&document)?
+            };
 
             Ok((chapter, next_page))
         }
     }
 
+    /// how many chapters are fetched at the same time unless `DownloadOptions` says otherwise
+    fn default_concurrency(&self) -> ConcurrencyPolicy {
+        ConcurrencyPolicy::Fixed(10)
+    }
+
+    /// chapters longer than this are skipped, they are usually broken pages or whole books
+    fn get_chapter_word_limit(&self) -> Option<usize> {
+        None
+    }
+
     fn get_book_info(&self, document: &Elements) -> Result<Book, NovelError>;
+
+    fn get_synopsis(&self, _document: &Elements) -> Option<String> {
+        None
+    }
+
+    /// the cover image on the contents page, downloaded into `cover.<ext>` of the book dir
+    fn get_cover_url(&self, _document: &Elements) -> Result<Option<Url>, NovelError> {
+        Ok(None)
+    }
+
+    fn get_tags(&self, _document: &Elements) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// the total word count on the contents page, `parse_word_count` reads the usual formats
+    fn get_book_word_count(&self, _document: &Elements) -> Option<usize> {
+        None
+    }
+
+    /// `get_book_info` with the synopsis, cover, tags and word count filled in
+    fn get_book(&self, document: &Elements) -> Result<Book, NovelError> {
+        Ok(Book {
+            synopsis: self.get_synopsis(document),
+            cover_url: self.get_cover_url(document)?,
+            tags: self.get_tags(document),
+            word_count: self.get_book_word_count(document),
+            ..self.get_book_info(document)?
+        })
+    }
     fn get_chapter_urls_sorted(&self, document: &Elements) -> Result<Vec<Url>, NovelError>;
 
+    /// newest chapter first, sites which list the chapters so can parse them here instead
+    fn get_chapter_urls_sorted_reverse(&self, document: &Elements) -> Result<Vec<Url>, NovelError> {
+        let mut urls = self.get_chapter_urls_sorted(document)?;
+        urls.reverse();
+        Ok(urls)
+    }
+
+    /// the site of the novel, `None` accepts chapters from anywhere
+    fn base_url(&self) -> Option<&Url> {
+        None
+    }
+
+    /// filter out the navigation links in the chapter list, e.g. the home page
+    fn chapter_url_is_valid(&self, url: &Url) -> bool {
+        self.base_url()
+            .is_none_or(|base| url.host_str() == base.host_str() && url.path() != "/")
+    }
+
+    /// for sites whose chapter list needs more requests than the contents page, e.g. a json api
+    fn fetch_chapter_urls_sorted(
+        &self,
+        _client: Client,
+        document: &Elements,
+    ) -> impl std::future::Future<Output = Result<Vec<Url>, NovelError>> {
+        async { self.get_chapter_urls_sorted(document) }
+    }
+
     fn append_urls_with_orders(&self, urls: Vec<Url>) -> Vec<(String, Url)> {
         urls.into_iter()
             .enumerate()
@@ -108,54 +407,468 @@ pub trait Noveler: Display + Sync + Send + 'static {
             .collect()
     }
 
-    fn get_chapter(&self, document: &Elements, order: &str) -> Result<Chapter, NovelError>;
+    fn chapter_title_selector(&self) -> &'static str {
+        r"h1"
+    }
+
+    fn chapter_content_selector(&self) -> &'static str {
+        r"div#content"
+    }
+
+    /// the text of the selectors, sites which need more than that override it
+    fn get_chapter(&self, document: &Elements, order: &str) -> Result<Chapter, NovelError> {
+        let title = document
+            .find(self.chapter_title_selector())
+            .text()
+            .trim()
+            .to_string();
+        let text: String = document.find(self.chapter_content_selector()).text();
+
+        let order = order.to_string();
+        Ok(Chapter { order, title, text })
+    }
     fn get_next_page(&self, document: &Elements) -> Result<Option<Url>, NovelError>;
-    fn process_chapter(&self, chapter: Chapter) -> Chapter;
+
+    /// the page is the last one of its chapter, `get_next_page` is not asked then
+    fn is_last_chapter(&self, _document: &Elements) -> bool {
+        false
+    }
+
+    /// the title as it is, sites with noise in their titles override it
+    fn process_chapter_title(&self, title: &str) -> String {
+        title.to_string()
+    }
+    fn process_chapter_text(&self, text: &str) -> String;
+    fn process_chapter(&self, chapter: Chapter) -> Chapter {
+        Chapter {
+            title: self.process_chapter_title(&chapter.title),
+            text: self.process_chapter_text(&chapter.text),
+            ..chapter
+        }
+    }
 }
 
-fn file_name(order: &str) -> String {
-    format!("{order}.txt")
+const FAILED_FILE: &str = "failed.json";
+const STATE_FILE: &str = "state.json";
+const BOOK_INFO_FILE: &str = "book_info.json";
+const PAGES_FILE: &str = "pages.jsonl";
+const METADATA_FILE: &str = "metadata.json";
+const STATS_FILE: &str = "stats.json";
+/// chapters listed as the longest and the shortest in `stats.json`
+const STATS_EXTREMES: usize = 5;
+const COVER_FILE_STEM: &str = "cover";
+/// extension of a file still being written
+const PART_EXTENSION: &str = "part";
+const WORD_LIMIT_EXCEEDED: &str = "Chapter text exceeds word limit";
+
+/// (order, url, attempt), attempt starts from 1
+type UrlTask = (String, Url, u32);
+
+/// numeric part of the order first, then the depth of the `_n` next-page chain
+fn order_key(order: &str) -> (u64, usize) {
+    let number = order
+        .split('_')
+        .next()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(u64::MAX);
+    (number, order.matches("_n").count())
 }
 
-fn process_url_contents(
-    noveler: &Arc<impl Noveler>,
-    document: &Elements,
+/// sort the urls by their order, the next pages right after their chapter
+fn reorder_chapters(mut urls: Vec<(String, Url)>) -> Vec<(String, Url)> {
+    urls.sort_by_key(|(order, _)| order_key(order));
+    urls
+}
+
+/// `00001.txt`, or `00001_第一章老地方.txt` with the title, its whitespace left out
+fn file_name(order: &str, title: Option<&str>) -> String {
+    let mut title: String = title
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+    if title.is_empty() {
+        return sanitize_path_component(&format!("{order}.txt"));
+    }
+    // a long title is cut, not the extension
+    truncate_bytes(
+        &mut title,
+        MAX_PATH_COMPONENT_BYTES.saturating_sub(order.len() + "_.txt".len()),
+    );
+    sanitize_path_component(&format!("{order}_{title}.txt"))
+}
+
+/// the order a chapter file is named after, with or without the title
+///
+/// `_n` right after the number is a next page, so a title of only `n` is read as one
+fn chapter_order(path: &Path) -> Option<String> {
+    let stem = path.file_stem()?.to_str()?;
+    let mut parts = stem.split('_');
+    let number = parts
+        .next()
+        .filter(|number| !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit()))?;
+    let mut order = number.to_string();
+    for part in parts.take_while(|part| *part == "n") {
+        order.push('_');
+        order.push_str(part);
+    }
+    Some(order)
+}
+
+/// the chapter files in `dir` by their order, none if `dir` is not there yet
+fn chapter_files_by_order(dir: &Path) -> HashMap<String, PathBuf> {
+    chapter_files(dir)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|path| Some((chapter_order(&path)?, path)))
+        .collect()
+}
+
+/// a desktop browser, the default of reqwest is blocked by many sites
+const DEFAULT_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 \
+    (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36";
+
+/// most file systems allow no more bytes in a file name
+const MAX_PATH_COMPONENT_BYTES: usize = 255;
+
+/// a file or dir name every platform accepts, illegal characters become `_`
+///
+/// windows also forbids `<>:"\|?*`, control characters, trailing dots or spaces,
+/// and device names like `NUL`
+fn sanitize_path_component(s: &str) -> String {
+    let windows = cfg!(target_os = "windows");
+    let mut name: String = s
+        .chars()
+        .map(|c| match c {
+            '/' | '\0' => '_',
+            '<' | '>' | ':' | '"' | '\\' | '|' | '?' | '*' if windows => '_',
+            c if windows && c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+
+    if windows {
+        name.truncate(name.trim_end_matches(['.', ' ']).len());
+        let stem = name.split('.').next().unwrap_or_default();
+        if is_windows_device_name(stem) {
+            name.insert(0, '_');
+        }
+    }
+
+    truncate_bytes(&mut name, MAX_PATH_COMPONENT_BYTES);
+    name
+}
+
+/// cut `s` to at most `max` bytes without splitting a char
+fn truncate_bytes(s: &mut String, max: usize) {
+    if s.len() > max {
+        let mut end = max;
+        while !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        s.truncate(end);
+    }
+}
+
+fn is_windows_device_name(stem: &str) -> bool {
+    let stem = stem.trim_end().to_ascii_uppercase();
+    match stem.as_str() {
+        "CON" | "PRN" | "AUX" | "NUL" => true,
+        _ => {
+            (stem.starts_with("COM") || stem.starts_with("LPT"))
+                && stem.len() == 4
+                && matches!(stem.as_bytes()[3], b'1'..=b'9')
+        }
+    }
+}
+
+/// `00001.txt.sha256` next to `00001.txt`
+fn checksum_path(path: &Path) -> PathBuf {
+    let mut checksum = path.as_os_str().to_owned();
+    checksum.push(".sha256");
+    PathBuf::from(checksum)
+}
+
+fn sha256_hex(content: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(content))
+}
+
+/// the chapter file exists and matches its `.sha256` sidecar
+fn chapter_file_is_valid(path: &Path) -> bool {
+    let (Ok(content), Ok(checksum)) = (fs::read(path), fs::read_to_string(checksum_path(path)))
+    else {
+        return false;
+    };
+    checksum.trim() == sha256_hex(&content)
+}
+
+/// chapter files in `dir` whose sidecar is missing or does not match, nothing is downloaded
+pub(crate) fn verify_chapters(dir: &Path) -> Result<Vec<PathBuf>, NovelError> {
+    Ok(chapter_files(dir)?
+        .into_iter()
+        .filter(|path| !chapter_file_is_valid(path))
+        .collect())
+}
+
+/// chapter files with the same text, e.g. one chapter listed under two ids
+///
+/// the text below the title is hashed, the titles of the copies usually differ
+pub(crate) fn duplicate_chapters(dir: &Path) -> Result<Vec<Vec<PathBuf>>, NovelError> {
+    let mut groups: HashMap<String, Vec<PathBuf>> = HashMap::new();
+    for path in chapter_files(dir)? {
+        let content = fs::read_to_string(&path)?;
+        // `Chapter::content` is the title and the text
+        let text = content.split_once("\n\n").map_or("", |(_, text)| text);
+        if text.trim().is_empty() {
+            continue;
+        }
+        groups
+            .entry(sha256_hex(text.as_bytes()))
+            .or_default()
+            .push(path);
+    }
+
+    let mut duplicates: Vec<Vec<PathBuf>> = groups
+        .into_values()
+        .filter(|paths| paths.len() > 1)
+        .collect();
+    duplicates.sort_unstable();
+    Ok(duplicates)
+}
+
+fn collect_chapter_urls(
+    urls: Vec<(String, Url)>,
+    pages: Vec<(String, Url)>,
     dir: &Path,
-    tx: mpsc::Sender<(String, Url)>,
-) -> Result<i32, NovelError> {
-    let urls = noveler.get_chapter_urls_sorted(document)?;
-    let mut urls = noveler.append_urls_with_orders(urls);
+    options: &DownloadOptions,
+) -> Vec<(String, Url)> {
+    let mut urls = slice_urls(urls, options.start_index, options.end_index);
+    urls.retain(|(order, _)| !chapter_is_skipped(order, &options.skip_chapters));
+    if options.force {
+        // the next pages are found again with their chapters
+        return urls;
+    }
+
+    // continue the next page chains of the chapters in range
+    let orders: HashSet<String> = urls.iter().map(|(order, _)| order.clone()).collect();
+    urls.extend(pages.into_iter().filter(|(order, _)| {
+        order
+            .split('_')
+            .next()
+            .is_some_and(|chapter| orders.contains(chapter))
+    }));
     urls = remove_url_with_exist_file(urls, dir);
 
+    reorder_chapters(urls)
+}
+
+/// the chapters failed in the last run with `retry_failed`, otherwise from the contents,
+/// without the saved ones unless `force`
+async fn chapter_urls_to_download(
+    state: &DownloadState,
+    dir: &Path,
+    options: &DownloadOptions,
+    failed: &FailedManifest,
+    pages: &PageLog,
+) -> Result<Vec<(String, Url)>, NovelError> {
+    let last_failed = failed.take().await?;
+    if options.retry_failed {
+        return Ok(last_failed
+            .into_iter()
+            .map(|chapter| (chapter.order, chapter.url))
+            .collect());
+    }
+
+    Ok(collect_chapter_urls(
+        state.chapters.clone(),
+        pages.read().await?,
+        dir,
+        options,
+    ))
+}
+
+/// send HEAD requests to all chapter urls and collect their `Content-Length`
+///
+/// urls which fail or do not report a length are left out
+async fn prefetch_all_chapter_headers(
+    urls: &[(String, Url)],
+    client: Client,
+    limit: usize,
+) -> HashMap<Url, u64> {
+    let semaphore = Arc::new(Semaphore::new(limit));
+    let mut join_set = JoinSet::new();
+    for (_, url) in urls {
+        let url = url.clone();
+        let client = client.clone();
+        let semaphore = semaphore.clone();
+        join_set.spawn(async move {
+            let _permit = semaphore.acquire_owned().await.ok()?;
+            let resp = client.head(url.clone()).send().await.ok()?;
+            let length = resp
+                .headers()
+                .get(reqwest::header::CONTENT_LENGTH)?
+                .to_str()
+                .ok()?
+                .parse::<u64>()
+                .ok()?;
+            Some((url, length))
+        });
+    }
+
+    let mut sizes = HashMap::new();
+    while let Some(result) = join_set.join_next().await {
+        if let Ok(Some((url, length))) = result {
+            sizes.insert(url, length);
+        }
+    }
+
+    sizes
+}
+
+/// log the estimated size of `urls`, the sizes are returned only if every chapter reported one
+async fn estimate_size(
+    urls: &[(String, Url)],
+    client: Client,
+    limit: usize,
+) -> Option<HashMap<Url, u64>> {
+    let sizes = prefetch_all_chapter_headers(urls, client, limit).await;
+    tracing::info!(
+        bytes = sizes.values().sum::<u64>(),
+        reported = sizes.len(),
+        chapters = urls.len(),
+        "estimated size"
+    );
+    urls.iter()
+        .all(|(_, url)| sizes.contains_key(url))
+        .then_some(sizes)
+}
+
+fn process_url_contents(urls: Vec<(String, Url)>, tx: mpsc::Sender<UrlTask>) -> i32 {
     let tasks = i32::try_from(urls.len()).expect("usize to i32 ok");
     tokio::spawn(async move {
-        for url in urls {
-            if let Err(err) = tx.send(url).await {
-                eprintln!("Failed to send url: {err}");
+        for (order, url) in urls {
+            if let Err(err) = tx.send((order, url, 1)).await {
+                tracing::error!(error = %err, "failed to send url");
             }
         }
     });
 
-    Ok(tasks)
+    tasks
+}
+
+/// `url` was queued before by another order, logged, otherwise it is claimed by `order`
+fn is_claimed(seen: &mut HashMap<Url, String>, order: &str, url: &Url) -> bool {
+    if let Some(original) = seen.get(url) {
+        tracing::warn!("URL {url} already downloaded as order {original}, skipping {order}");
+        return true;
+    }
+    seen.insert(url.clone(), order.to_string());
+    false
+}
+
+/// what the chapter tasks of one download share
+#[derive(Debug, Clone)]
+struct DownloadContext {
+    client: Client,
+    limiter: Arc<ConcurrencyLimiter>,
+    tx: mpsc::Sender<UrlTask>,
+    /// the book dir
+    dir: PathBuf,
+    pages: Arc<PageLog>,
+    failed: Arc<FailedManifest>,
+    dedup: Option<Arc<ContentHashes>>,
+    /// chapters written by this run
+    saved_chapters: Arc<AtomicU64>,
+    /// `Chapter::word_count` of the chapters written by this run
+    saved_words: Arc<AtomicU64>,
+    /// chapters with fewer chars are logged, they may be a blocked page
+    min_chars: usize,
+    /// fetch the short chapters again until the attempts run out
+    retry_short: bool,
+    /// name the chapter files `00001_<title>.txt`
+    title_in_filename: bool,
+    progress: ProgressBar,
+    /// the prefetched `Content-Length` of each chapter when the bar counts bytes
+    chapter_sizes: Option<Arc<HashMap<Url, u64>>>,
+    /// `DownloadOptions::encoding`
+    encoding: Option<&'static encoding_rs::Encoding>,
+}
+
+impl DownloadContext {
+    /// one chapter, or its prefetched size when the bar counts bytes, next pages count nothing
+    fn progress_step(&self, url: &Url) -> u64 {
+        self.chapter_sizes
+            .as_ref()
+            .map_or(1, |sizes| sizes.get(url).copied().unwrap_or_default())
+    }
+}
+
+/// cleaned text below `min_chars`, a near-empty body or a "请稍后" placeholder
+fn text_is_short(text: &str, min_chars: usize) -> bool {
+    count_words(text) < min_chars
 }
 
+/// chapters whose text is in `ctx.dedup` already are not written, the next page is still followed
 async fn process_save_task(
     chapter: Chapter,
+    url: &Url,
     next_page: Option<Url>,
-    dir: &Path,
-    tx: mpsc::Sender<(String, Url)>,
+    ctx: &DownloadContext,
 ) -> Result<i32, NovelError> {
-    tokio::fs::write(dir.join(file_name(&chapter.order)), chapter.content()).await?;
+    // record the next page first, a resumed run can not find it once the chapter is saved
+    if let Some(next_page_url) = &next_page {
+        ctx.pages
+            .push(&format!("{}_n", chapter.order), next_page_url)
+            .await?;
+    }
+    if text_is_short(&chapter.text, ctx.min_chars) {
+        ctx.progress.suspend(|| {
+            tracing::warn!(
+                order = %chapter.order,
+                bytes = chapter.text.len(),
+                "chapter text is suspiciously short"
+            );
+        });
+    }
+    let duplicate = match &ctx.dedup {
+        Some(dedup) => !dedup.insert(&chapter.text).await,
+        None => false,
+    };
+    if duplicate {
+        ctx.progress
+            .suspend(|| tracing::info!(order = %chapter.order, "skip duplicate chapter"));
+    } else {
+        let content = chapter.content();
+        let checksum = sha256_hex(content.as_bytes());
+        let title = ctx.title_in_filename.then_some(chapter.title.as_str());
+        let path = ctx.dir.join(file_name(&chapter.order, title));
+        write_atomic(&path, content).await?;
+        // written last, a chapter without it is downloaded again
+        write_atomic(&checksum_path(&path), checksum + "\n").await?;
+        // the copy named the other way by an earlier run would be combined twice
+        let other_title = (!ctx.title_in_filename).then_some(chapter.title.as_str());
+        let other = ctx.dir.join(file_name(&chapter.order, other_title));
+        if other != path {
+            remove_file_if_exists(&other).await?;
+            remove_file_if_exists(&checksum_path(&other)).await?;
+        }
+        ctx.saved_chapters.fetch_add(1, Ordering::Relaxed);
+        ctx.saved_words
+            .fetch_add(chapter.word_count() as u64, Ordering::Relaxed);
+    }
 
-    println!("{:>10} => {:<8}", "Done", chapter.order);
+    tracing::debug!(order = %chapter.order, "done");
+    ctx.progress.inc(ctx.progress_step(url));
 
     let mut tasks_done = -1;
     if let Some(next_page_url) = next_page {
         tasks_done += 1;
+        let tx = ctx.tx.clone();
+        let progress = ctx.progress.clone();
         tokio::spawn(async move {
-            let url = (chapter.order + "_n", next_page_url);
+            let url = (chapter.order + "_n", next_page_url, 1);
             if let Err(err) = tx.send(url).await {
-                eprintln!("Failed to send url: {err}");
+                progress.suspend(|| tracing::error!(error = %err, "failed to send url"));
             }
         });
     }
@@ -163,426 +876,4545 @@ async fn process_save_task(
     Ok(tasks_done)
 }
 
-pub(crate) async fn download_novel(
-    noveler: Arc<impl Noveler>,
-    url_contents: &str,
-    dir: &Path,
-    limit: usize,
-) -> Result<PathBuf, NovelError> {
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(60 * 3))
-        .build()?;
-
-    let document =
-        get_html_and_fix_encoding(client.clone(), url_contents, noveler.need_encoding()).await?;
-    // fs::write("test.html", document.html()).unwrap();
-    let document = visdom::Vis::load(document)?;
+async fn remove_file_if_exists(path: &Path) -> io::Result<()> {
+    match tokio::fs::remove_file(path).await {
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
 
-    let book = noveler.get_book_info(&document)?;
+/// re-queue the chapter with exponential backoff if the error is transient,
+/// otherwise give up on it once `max_attempts` is reached
+///
+/// chapters over the word limit and VIP chapters are given up at once
+async fn process_retry_task(
+    err: NovelError,
+    (order, url, attempt): UrlTask,
+    retry: RetryPolicy,
+    ctx: &DownloadContext,
+) -> Result<i32, NovelError> {
+    // a missing or broken chapter goes to `failed.json`, the others still download
+    if !is_retryable(&err) || attempt >= retry.max_attempts {
+        ctx.progress.suspend(|| {
+            tracing::error!(order = %order, url = %url, attempt, error = %err, "failed");
+        });
+        ctx.failed
+            .push(FailedChapter {
+                order,
+                url,
+                last_error: err.to_string(),
+            })
+            .await?;
+        return Ok(-1);
+    }
 
-    let dir = dir
-        .join("temp")
-        .join(noveler.to_string())
-        .join(book.to_string());
-    tokio::fs::create_dir_all(dir.as_path()).await?;
+    // the site says how long to wait, the backoff is only a guess
+    let wait = match &err {
+        NovelError::TooManyRequests(retry_after) => retry_after.unwrap_or(DEFAULT_RETRY_AFTER),
+        _ => retry.backoff(attempt),
+    };
+    tracing::debug!(order = %order, url = %url, attempt, error = %err, ?wait, "retrying");
+    requeue_task((order, url, attempt), wait, ctx).await
+}
 
-    let semaphore = Arc::new(Semaphore::new(limit)); // Adjust the concurrency limit as needed
-    let (tx, mut rx) = mpsc::channel::<(String, Url)>(10);
+/// fetch the chapter again after `wait`
+async fn requeue_task(
+    (order, url, attempt): UrlTask,
+    wait: Duration,
+    ctx: &DownloadContext,
+) -> Result<i32, NovelError> {
+    tokio::time::sleep(wait).await;
+    if let Err(err) = ctx.tx.send((order, url, attempt + 1)).await {
+        ctx.progress
+            .suspend(|| tracing::error!(error = %err, "failed to send url"));
+    }
 
-    let mut set = HashSet::new();
-    let mut tasks = process_url_contents(&noveler, &document, &dir, tx.clone())?;
-    let mut join_set: JoinSet<Result<i32, NovelError>> = JoinSet::new();
-    while tasks > 0 {
-        tokio::select! {
-            Some((order, url)) = rx.recv() => {
-                if set.contains(&url) {
-                    join_set.spawn(async move {
-                        Ok(-1)
-                    });
-                    continue;
-                }
-                set.insert(url.clone());
-
-                println!("{:>10} => {order:<8}: {url}", "Insert");
-
-                join_set.spawn({
-                    let tx = tx.clone();
-                    let noveler = noveler.clone();
-                    let dir = dir.clone();
-                    let client = client.clone();
-                    let permit = semaphore.clone().acquire_owned().await.expect("acquire semaphore permit");
-
-                    async move {
-                        println!("{:>10} => {order:<8}: {url}", "Process");
-                        let (chapter, next_page) = match noveler.process_url(client, &order, url.clone()).await {
-                            Ok(result) => result,
-                            Err(NovelError::ReqwestError(e)) => {
-                                if e.is_timeout() {
-                                    println!("{:>10} => {order:<8}: {url}", "TOutRedo");
-                                    if let Err(err) = tx.send((order, url)).await {
-                                        eprintln!("Failed to send url: {err}");
-                                    }
-                                    return Ok(0);
-                                }
-
-                                return Err(e.into());
-                            }
-                            Err(e) => {
-                                return Err(e);
-                            },
-                        };
-
-                        // Release the semaphore permit
-                        drop(permit);
-                        process_save_task(chapter, next_page, &dir, tx).await
-                    }
-                });
-            }
-            Some(result) = join_set.join_next() => {
-                match result {
-                    Ok(result) => {
-                        tasks += result?;
-                        println!("{:<10} => {tasks:05}", "Tasks");
-                    }
-                    Err(join_error) => {
-                        eprintln!("Async task failed: {join_error:?}");
-                        if join_error.is_panic() {
-                            eprintln!("Async task panicked!");
-                        }
-                    }
-                }
-            }
-        };
+    Ok(0)
+}
+
+fn is_retryable(err: &NovelError) -> bool {
+    match err {
+        NovelError::TooManyRequests(_) => true,
+        NovelError::ReqwestError(e) => {
+            e.is_timeout()
+                || e.is_connect()
+                || e.is_request()
+                || e.is_body()
+                || e.status().is_some_and(|status| status.is_server_error())
+        }
+        _ => false,
     }
+}
 
-    Ok(dir)
+/// the bar counts bytes if `prefetch_sizes` found the size of every chapter, otherwise chapters
+async fn chapter_progress(
+    urls: &[(String, Url)],
+    client: Client,
+    limit: usize,
+    options: &DownloadOptions,
+) -> (ProgressBar, Option<Arc<HashMap<Url, u64>>>) {
+    let sizes = if options.prefetch_sizes {
+        estimate_size(urls, client, limit).await
+    } else {
+        None
+    };
+    match sizes {
+        Some(sizes) => (
+            progress_bar(sizes.values().sum(), true, options.hide_progress),
+            Some(Arc::new(sizes)),
+        ),
+        None => (
+            progress_bar(
+                u64::try_from(urls.len()).unwrap_or_default(),
+                false,
+                options.hide_progress,
+            ),
+            None,
+        ),
+    }
 }
 
-pub(crate) fn combine_txt(dir: &Path) -> Result<(), NovelError> {
-    let mut save_path = dir.to_path_buf();
-    save_path.set_extension("txt");
+/// log lines are written around the bar with `ProgressBar::suspend`
+///
+/// `len` counts chapters, or bytes with `bytes`
+fn progress_bar(len: u64, bytes: bool, hidden: bool) -> ProgressBar {
+    if hidden {
+        return ProgressBar::hidden();
+    }
 
-    let mut output = fs::File::create(save_path)?;
+    let template = if bytes {
+        "[{elapsed_precise}] {wide_bar} {bytes}/{total_bytes} ({bytes_per_sec}, ETA {eta})"
+    } else {
+        "[{elapsed_precise}] {wide_bar} {pos}/{len} ({per_sec}, ETA {eta})"
+    };
+    ProgressBar::new(len)
+        .with_style(ProgressStyle::with_template(template).expect("progress template ok"))
+}
 
-    let entries: Vec<fs::DirEntry> = dir.read_dir()?.collect::<Result<_, std::io::Error>>()?;
-    let mut paths: Vec<PathBuf> = entries.into_iter().map(|entry| entry.path()).collect();
-    paths.sort_unstable();
-    for path in paths {
-        let mut input = fs::File::open(&path)?;
-        io::copy(&mut input, &mut output)?;
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct ChapterMeta {
+    order: String,
+    title: String,
+    source_url: Url,
+    /// characters of the text below the title, whitespace left out
+    #[serde(default)]
+    word_count: usize,
+}
 
-        // Add a line break after copying each file
-        write!(&mut output, "\n\n")?;
+/// what has been downloaded, kept as `metadata.json` in the book dir for other tools
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Metadata {
+    #[serde(flatten)]
+    book: Book,
+    source_url: String,
+    /// seconds since the unix epoch
+    downloaded_at: u64,
+    chapter_count: usize,
+    /// characters in all saved chapters
+    #[serde(default)]
+    word_count: usize,
+    chapters: Vec<ChapterMeta>,
+}
 
-        if let Some(file_name) = path.file_name() {
-            println!("Appended content of file: {file_name:?}");
+impl Metadata {
+    /// the chapters saved in `dir`, the title is the first line of the file
+    async fn collect(
+        state: &DownloadState,
+        pages: &PageLog,
+        dir: &Path,
+    ) -> Result<Self, NovelError> {
+        let mut chapters = Vec::new();
+        let saved = chapter_files_by_order(dir);
+        for (order, source_url) in state.chapters.iter().cloned().chain(pages.read().await?) {
+            let Some(path) = saved.get(&order) else {
+                continue;
+            };
+            let Ok(content) = tokio::fs::read_to_string(path).await else {
+                continue;
+            };
+            let (title, text) = content.split_once('\n').unwrap_or((&content, ""));
+            let title = title.to_string();
+            let word_count = count_words(text);
+            chapters.push(ChapterMeta {
+                order,
+                title,
+                source_url,
+                word_count,
+            });
         }
+        chapters.sort_by_key(|chapter| order_key(&chapter.order));
+
+        let downloaded_at = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+        Ok(Self {
+            book: state.book.clone(),
+            source_url: state.url_contents.clone(),
+            downloaded_at,
+            chapter_count: chapters.len(),
+            word_count: chapters.iter().map(|chapter| chapter.word_count).sum(),
+            chapters,
+        })
     }
-
-    println!("done");
-    Ok(())
 }
 
-async fn get_html_and_fix_encoding<T: IntoUrl>(
-    client: Client,
-    url: T,
-    need_encoding: Option<&'static encoding_rs::Encoding>,
-) -> Result<String, NovelError> {
-    let resp = client.get(url).send().await?;
-
-    match need_encoding {
-        None => Ok(resp.text().await?),
-        Some(encoding) => {
-            // Extract raw body bytes
-            let body_bytes = resp.bytes().await?;
+/// the characters saved by the last run and the longest and shortest chapters of the book,
+/// kept as `stats.json` in the book dir
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct WordStats {
+    /// chapters written by the last run
+    saved_chapters: u64,
+    /// characters in the chapters written by the last run
+    saved_word_count: u64,
+    chapter_count: usize,
+    word_count: usize,
+    /// most characters first
+    longest: Vec<ChapterMeta>,
+    /// fewest characters first
+    shortest: Vec<ChapterMeta>,
+}
 
-            // Decode the response body to UTF-8 using the encoding
-            let (decoded, _, _) = encoding.decode(&body_bytes);
+impl WordStats {
+    fn new(metadata: &Metadata, ctx: &DownloadContext) -> Self {
+        let mut chapters = metadata.chapters.clone();
+        // the earlier chapter first among the same counts
+        chapters.sort_by_key(|chapter| chapter.word_count);
+        let shortest = chapters.iter().take(STATS_EXTREMES).cloned().collect();
+        chapters.sort_by_key(|chapter| std::cmp::Reverse(chapter.word_count));
+        let longest = chapters.into_iter().take(STATS_EXTREMES).collect();
+
+        Self {
+            saved_chapters: ctx.saved_chapters.load(Ordering::Relaxed),
+            saved_word_count: ctx.saved_words.load(Ordering::Relaxed),
+            chapter_count: metadata.chapter_count,
+            word_count: metadata.word_count,
+            longest,
+            shortest,
+        }
+    }
 
-            // Parse the decoded HTML back into a scraper::Html
-            Ok(decoded.into_owned())
+    fn log(&self) {
+        tracing::info!(
+            "saved {} chapters, {} chars; the book has {} chapters, {} chars",
+            self.saved_chapters,
+            self.saved_word_count,
+            self.chapter_count,
+            self.word_count
+        );
+        for (name, chapters) in [("longest", &self.longest), ("shortest", &self.shortest)] {
+            for chapter in chapters {
+                tracing::info!(
+                    "{name:>8} {:>8} {:>7} {}",
+                    chapter.order,
+                    chapter.word_count,
+                    chapter.title
+                );
+            }
         }
     }
 }
 
-fn remove_url_with_exist_file(urls: Vec<(String, Url)>, dir: &Path) -> Vec<(String, Url)> {
-    urls.into_iter()
-        .filter(|(order, _)| !dir.join(file_name(order)).is_file())
-        .collect()
+/// how the last run went
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub(crate) struct DownloadStats {
+    /// chapters and next pages queued at the start, the next pages found later are not counted
+    pub(crate) queued: usize,
+    /// chapters given up after the retries
+    pub(crate) failed: usize,
+    pub(crate) elapsed_secs: f64,
+    /// stopped by Ctrl-C, the chapters not started are left for the next run
+    pub(crate) interrupted: bool,
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use chardetng::EncodingDetector;
-    use regex::Regex;
-    use std::sync::atomic::{AtomicI32, Ordering};
-    use tempdir::TempDir;
+/// `Metadata` with `DownloadStats`, written to `DownloadOptions::output_json`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct DownloadReport {
+    #[serde(flatten)]
+    metadata: Metadata,
+    stats: DownloadStats,
+}
 
-    async fn guess_coding<T: IntoUrl>(url: T) -> (&'static encoding_rs::Encoding, bool) {
-        let resp = reqwest::get(url).await.unwrap();
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct ChapterLink {
+    /// the link text in the contents page
+    title: Option<String>,
+    url: Url,
+}
 
-        // Extract raw body bytes
-        let body_bytes = resp.bytes().await.unwrap();
+/// the book and its chapters as listed in the contents page, nothing is downloaded
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct BookSummary {
+    #[serde(flatten)]
+    book: Book,
+    url_contents: String,
+    chapter_count: usize,
+    first_chapter: Option<ChapterLink>,
+    last_chapter: Option<ChapterLink>,
+}
 
-        // Use chardetng to detect encoding
-        let mut detector = EncodingDetector::new();
-        detector.feed(&body_bytes, true);
-        detector.guess_assess(None, true)
+impl fmt::Display for BookSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "name: {}", self.book.name)?;
+        writeln!(f, "author: {}", self.book.author)?;
+        writeln!(f, "chapters: {}", self.chapter_count)?;
+        if let Some(word_count) = self.book.word_count {
+            writeln!(f, "words: {word_count}")?;
+        }
+        for (label, chapter) in [("first", &self.first_chapter), ("last", &self.last_chapter)] {
+            if let Some(ChapterLink { title, url }) = chapter {
+                writeln!(f, "{label}: {} {url}", title.as_deref().unwrap_or("-"))?;
+            }
+        }
+        if let Some(cover_url) = &self.book.cover_url {
+            writeln!(f, "cover: {cover_url}")?;
+        }
+        Ok(())
     }
+}
 
-    #[ignore = "only for detecting coding"]
-    #[tokio::test]
+/// read the contents page only, for checking the url before downloading
+pub(crate) async fn fetch_book_summary(
+    noveler: &impl Noveler,
+    url_contents: &str,
+    client: Client,
+    encoding: Option<&'static encoding_rs::Encoding>,
+) -> Result<BookSummary, NovelError> {
+    let encoding = page_encoding(noveler, encoding);
+    let document = get_html_and_fix_encoding(client.clone(), url_contents, encoding).await?;
+    let document = visdom::Vis::load(document)?;
+
+    let book = noveler.get_book(&document)?;
+    let urls: Vec<Url> = noveler
+        .fetch_chapter_urls_sorted(client, &document)
+        .await?
+        .into_iter()
+        .filter(|url| noveler.chapter_url_is_valid(url))
+        .collect();
+
+    let base = Url::parse(url_contents)?;
+    let link = |url: &Url| ChapterLink {
+        title: link_text(&document, &base, url),
+        url: url.clone(),
+    };
+    Ok(BookSummary {
+        book,
+        url_contents: url_contents.to_string(),
+        chapter_count: urls.len(),
+        first_chapter: urls.first().map(link),
+        last_chapter: urls.last().map(link),
+    })
+}
+
+/// the chapters of the contents page with their orders, as `download_novel` would fetch them
+pub(crate) async fn fetch_chapter_list(
+    noveler: &impl Noveler,
+    url_contents: &str,
+    client: Client,
+    encoding: Option<&'static encoding_rs::Encoding>,
+) -> Result<Vec<(String, Url)>, NovelError> {
+    Ok(
+        DownloadState::fetch(noveler, client, url_contents, encoding)
+            .await?
+            .chapters,
+    )
+}
+
+/// `--dry-run`, the chapters `download_novel` would fetch with `options`, without fetching them
+/// or writing anything
+pub(crate) async fn chapters_to_download(
+    noveler: &Arc<impl Noveler>,
+    url_contents: &str,
+    dir: &Path,
+    options: &DownloadOptions,
+    client: Client,
+) -> Result<Vec<(String, Url)>, NovelError> {
+    let dir = dir.join("temp").join(noveler.to_string());
+    let state = DownloadState::load_or_fetch(noveler, client, &dir, url_contents, options).await?;
+    check_novel_filter(options.novel_filter.as_ref(), &state.book)?;
+
+    let dir = dir.join(state.book.to_string());
+    let pages = PageLog::new(dir.join(PAGES_FILE)).read().await?;
+    Ok(collect_chapter_urls(state.chapters, pages, &dir, options))
+}
+
+/// the text of the first link to `url` in the page
+fn link_text(document: &Elements, base: &Url, url: &Url) -> Option<String> {
+    document
+        .find("a[href]")
+        .into_iter()
+        .find(|a| {
+            a.get_attribute("href")
+                .and_then(|href| base.join(&href.to_string()).ok())
+                .as_ref()
+                == Some(url)
+        })
+        .map(|a| a.text().trim().to_string())
+}
+
+/// the contents of a book, kept as `state.json` in the book dir
+/// so an interrupted download can resume without fetching the contents again
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct DownloadState {
+    url_contents: String,
+    book: Book,
+    /// every chapter in the contents, before any range or existing file is left out
+    chapters: Vec<(String, Url)>,
+}
+
+impl DownloadState {
+    async fn fetch(
+        noveler: &impl Noveler,
+        client: Client,
+        url_contents: &str,
+        encoding: Option<&'static encoding_rs::Encoding>,
+    ) -> Result<Self, NovelError> {
+        let encoding = page_encoding(noveler, encoding);
+        let document = get_html_and_fix_encoding(client.clone(), url_contents, encoding).await?;
+        // fs::write("test.html", document.html()).unwrap();
+        let document = visdom::Vis::load(document)?;
+
+        let book = noveler.get_book(&document)?;
+        let urls = noveler
+            .fetch_chapter_urls_sorted(client, &document)
+            .await?
+            .into_iter()
+            .filter(|url| noveler.chapter_url_is_valid(url))
+            .collect();
+        let chapters = noveler.append_urls_with_orders(urls);
+
+        Ok(Self {
+            url_contents: url_contents.to_string(),
+            book,
+            chapters,
+        })
+    }
+
+    /// the state saved by an earlier run unless `no_resume`, otherwise from the contents page
+    async fn load_or_fetch(
+        noveler: &Arc<impl Noveler>,
+        client: Client,
+        dir: &Path,
+        url_contents: &str,
+        options: &DownloadOptions,
+    ) -> Result<Self, NovelError> {
+        if !options.no_resume {
+            if let Some(state) = Self::find(dir, url_contents)? {
+                return Ok(state);
+            }
+        }
+        Self::fetch(noveler.as_ref(), client, url_contents, options.encoding).await
+    }
+
+    /// look for the state of `url_contents` in the book dirs under `dir`
+    fn find(dir: &Path, url_contents: &str) -> Result<Option<Self>, NovelError> {
+        let entries = match dir.read_dir() {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+
+        for entry in entries {
+            let Ok(json) = fs::read_to_string(entry?.path().join(STATE_FILE)) else {
+                continue;
+            };
+            if let Ok(state) = serde_json::from_str::<Self>(&json) {
+                if state.url_contents == url_contents {
+                    return Ok(Some(state));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    async fn save(&self, dir: &Path) -> Result<(), NovelError> {
+        write_json_atomic(&dir.join(STATE_FILE), self).await
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct FailedChapter {
+    order: String,
+    url: Url,
+    last_error: String,
+}
+
+/// chapters which exhausted their retries, kept as `failed.json` in the book dir
+#[derive(Debug)]
+struct FailedManifest {
+    path: PathBuf,
+    chapters: Mutex<Vec<FailedChapter>>,
+}
+
+impl FailedManifest {
+    fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            chapters: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// read the manifest left by the last run and start a new one
+    async fn take(&self) -> Result<Vec<FailedChapter>, NovelError> {
+        let chapters = match tokio::fs::read_to_string(&self.path).await {
+            Ok(json) => serde_json::from_str(&json)?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err.into()),
+        };
+        tokio::fs::remove_file(&self.path).await?;
+
+        Ok(chapters)
+    }
+
+    /// chapters failed in this run
+    async fn len(&self) -> usize {
+        self.chapters.lock().await.len()
+    }
+
+    async fn push(&self, chapter: FailedChapter) -> Result<(), NovelError> {
+        let mut chapters = self.chapters.lock().await;
+        chapters.push(chapter);
+        write_json_atomic(&self.path, &*chapters).await
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct NextPage {
+    order: String,
+    url: Url,
+}
+
+/// next pages found so far, one json per line in `pages.jsonl` of the book dir
+#[derive(Debug)]
+struct PageLog {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl PageLog {
+    fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// pages recorded by the earlier runs, a line cut off by an interrupt is ignored
+    async fn read(&self) -> Result<Vec<(String, Url)>, NovelError> {
+        let lines = match tokio::fs::read_to_string(&self.path).await {
+            Ok(lines) => lines,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut orders = HashSet::new();
+        Ok(lines
+            .lines()
+            .filter_map(|line| serde_json::from_str::<NextPage>(line).ok())
+            .filter(|page| orders.insert(page.order.clone()))
+            .map(|page| (page.order, page.url))
+            .collect())
+    }
+
+    async fn push(&self, order: &str, url: &Url) -> Result<(), NovelError> {
+        use tokio::io::AsyncWriteExt;
+
+        let page = NextPage {
+            order: order.to_string(),
+            url: url.clone(),
+        };
+        let mut line = serde_json::to_string(&page)?;
+        line.push('\n');
+
+        let _guard = self.lock.lock().await;
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await?;
+        file.write_all(line.as_bytes()).await?;
+        Ok(())
+    }
+}
+
+/// hashes of the chapter texts saved so far, to skip mirrors of the same chapter
+#[derive(Debug, Default)]
+struct ContentHashes {
+    hashes: Mutex<HashSet<u64>>,
+}
+
+impl ContentHashes {
+    /// start from the chapters saved by the earlier runs
+    fn load(dir: &Path) -> Result<Self, NovelError> {
+        let mut hashes = HashSet::new();
+        for path in chapter_files(dir)? {
+            let content = fs::read_to_string(path)?;
+            // `Chapter::content` is the title and the text
+            let text = content.split_once("\n\n").map_or("", |(_, text)| text);
+            hashes.insert(text_hash(text));
+        }
+        Ok(Self {
+            hashes: Mutex::new(hashes),
+        })
+    }
+
+    /// false if the same text was saved before
+    async fn insert(&self, text: &str) -> bool {
+        self.hashes.lock().await.insert(text_hash(text))
+    }
+}
+
+/// `None` without `dedup`, empty with `force` as the saved chapters are replaced
+fn content_hashes(
+    dir: &Path,
+    options: &DownloadOptions,
+) -> Result<Option<Arc<ContentHashes>>, NovelError> {
+    if !options.dedup {
+        return Ok(None);
+    }
+    if options.force {
+        return Ok(Some(Arc::default()));
+    }
+    ContentHashes::load(dir).map(|hashes| Some(Arc::new(hashes)))
+}
+
+fn text_hash(text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ConcurrencyPolicy {
+    Fixed(usize),
+    /// start from `initial`, one more after `ADAPTIVE_FAST_STREAK` fast fetches in a row,
+    /// one less on a request error or a fetch slower than twice `ADAPTIVE_TARGET_LATENCY`
+    Adaptive {
+        initial: usize,
+        max: usize,
+    },
+}
+
+const ADAPTIVE_TARGET_LATENCY: Duration = Duration::from_secs(2);
+const ADAPTIVE_FAST_STREAK: usize = 10;
+
+impl ConcurrencyPolicy {
+    fn initial(self) -> usize {
+        match self {
+            Self::Fixed(limit) | Self::Adaptive { initial: limit, .. } => limit.max(1),
+        }
+    }
+}
+
+/// a semaphore whose permits follow the `ConcurrencyPolicy`
+#[derive(Debug)]
+struct ConcurrencyLimiter {
+    policy: ConcurrencyPolicy,
+    semaphore: Arc<Semaphore>,
+    /// (current limit, fast fetches in a row)
+    state: std::sync::Mutex<(usize, usize)>,
+}
+
+impl ConcurrencyLimiter {
+    fn new(policy: ConcurrencyPolicy) -> Self {
+        Self {
+            policy,
+            semaphore: Arc::new(Semaphore::new(policy.initial())),
+            state: std::sync::Mutex::new((policy.initial(), 0)),
+        }
+    }
+
+    async fn acquire(&self) -> OwnedSemaphorePermit {
+        self.semaphore
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("acquire semaphore permit")
+    }
+
+    /// give the permit back and adjust the limit by how the fetch went
+    fn release(&self, permit: OwnedSemaphorePermit, latency: Duration, request_failed: bool) {
+        let ConcurrencyPolicy::Adaptive { max, .. } = self.policy else {
+            return;
+        };
+
+        let mut state = self.state.lock().expect("lock concurrency state");
+        let (limit, fast_streak) = &mut *state;
+        if request_failed || latency > ADAPTIVE_TARGET_LATENCY * 2 {
+            *fast_streak = 0;
+            if *limit > 1 {
+                *limit -= 1;
+                permit.forget();
+                tracing::debug!(limit = *limit, "concurrency down");
+            }
+        } else if latency <= ADAPTIVE_TARGET_LATENCY {
+            *fast_streak += 1;
+            if *fast_streak >= ADAPTIVE_FAST_STREAK && *limit < max {
+                *fast_streak = 0;
+                *limit += 1;
+                self.semaphore.add_permits(1);
+                tracing::debug!(limit = *limit, "concurrency up");
+            }
+        } else {
+            *fast_streak = 0;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RetryPolicy {
+    /// total attempts for one chapter, including the first one
+    pub(crate) max_attempts: u32,
+    /// wait before the first retry, doubled for each following retry
+    pub(crate) base_delay: Duration,
+}
+
+/// the wait after a 429 without a `Retry-After`
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(5);
+
+impl RetryPolicy {
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.base_delay
+            .saturating_mul(2_u32.saturating_pow(attempt.saturating_sub(1)))
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        // 5 retries
+        Self {
+            max_attempts: 6,
+            base_delay: Duration::from_secs(1),
+        }
+    }
+}
+
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Clone)]
+pub(crate) struct DownloadOptions {
+    /// chapters fetched at the same time, `None` for `Noveler::default_concurrency`
+    pub(crate) concurrency: Option<ConcurrencyPolicy>,
+    /// wait before each chapter fetch
+    pub(crate) delay: Duration,
+    /// randomly vary `delay` by up to this percentage (0-100)
+    pub(crate) delay_jitter: u8,
+    /// first chapter to download, 1-based and inclusive
+    pub(crate) start_index: Option<usize>,
+    /// last chapter to download, 1-based and inclusive
+    pub(crate) end_index: Option<usize>,
+    /// estimate the total size with HEAD requests before downloading, the bar then counts bytes
+    pub(crate) prefetch_sizes: bool,
+    /// only download the book when `Book` matches
+    pub(crate) novel_filter: Option<Regex>,
+    /// decode the pages with this instead of `Noveler::need_encoding`
+    pub(crate) encoding: Option<&'static encoding_rs::Encoding>,
+    /// how failed chapter fetches are retried
+    pub(crate) retry: RetryPolicy,
+    /// only download the chapters listed in `failed.json` by the last run
+    pub(crate) retry_failed: bool,
+    /// no progress bar, e.g. when every step is logged
+    pub(crate) hide_progress: bool,
+    /// fetch the contents again even if `state.json` exists
+    pub(crate) no_resume: bool,
+    /// skip chapters with the same text as one saved before
+    pub(crate) dedup: bool,
+    /// download every chapter again, replacing the saved files
+    pub(crate) force: bool,
+    /// chapters not to download, 1-based like `start_index`
+    pub(crate) skip_chapters: Vec<RangeInclusive<usize>>,
+    /// warn about chapters with fewer chars than this, 0 to disable
+    pub(crate) min_chars: usize,
+    /// fetch the chapters below `min_chars` again, they may be a blocked page
+    pub(crate) retry_short: bool,
+    /// also write `metadata.json` with the stats of the run here
+    pub(crate) output_json: Option<PathBuf>,
+    /// name the chapter files `00001_<title>.txt` instead of `00001.txt`
+    pub(crate) chapter_title_in_filename: bool,
+    /// skip what the site's `robots.txt` disallows, refusing a disallowed contents page
+    pub(crate) respect_robots_txt: bool,
+}
+
+impl Default for DownloadOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: None,
+            delay: Duration::ZERO,
+            delay_jitter: 0,
+            start_index: None,
+            end_index: None,
+            prefetch_sizes: false,
+            encoding: None,
+            novel_filter: None,
+            retry: RetryPolicy::default(),
+            retry_failed: false,
+            hide_progress: false,
+            no_resume: false,
+            dedup: false,
+            force: false,
+            skip_chapters: Vec::new(),
+            min_chars: 0,
+            retry_short: false,
+            output_json: None,
+            chapter_title_in_filename: false,
+            respect_robots_txt: false,
+        }
+    }
+}
+
+/// `delay * (1 ± jitter% * rand)`
+fn jitter_delay(delay: Duration, jitter: u8) -> Duration {
+    if jitter == 0 {
+        return delay;
+    }
+
+    let ratio = f64::from(jitter.min(100)) / 100.0;
+    delay.mul_f64(1.0 + rand::thread_rng().gen_range(-ratio..=ratio))
+}
+
+/// `client` fetches the contents and every chapter, see `build_client`
+///
+/// on Ctrl-C no more chapters are started, the ones in progress get `shutdown_timeout` to finish,
+/// the chapter dir comes back with `DownloadStats::interrupted` set
+pub(crate) async fn download_novel(
+    noveler: Arc<impl Noveler>,
+    url_contents: &str,
+    dir: &Path,
+    options: &DownloadOptions,
+    client: Client,
+    shutdown_timeout: Duration,
+) -> Result<(PathBuf, DownloadStats), NovelError> {
+    let ctrl_c = async {
+        if tokio::signal::ctrl_c().await.is_err() {
+            // no signal handler, never interrupted
+            std::future::pending::<()>().await;
+        }
+    };
+    download_novel_until(
+        noveler,
+        url_contents,
+        dir,
+        options,
+        client,
+        ctrl_c,
+        shutdown_timeout,
+    )
+    .await
+}
+
+/// fetch one chapter after `delay`, then save it or queue it again
+async fn process_url_task(
+    noveler: Arc<impl Noveler>,
+    (order, url, attempt): UrlTask,
+    permit: OwnedSemaphorePermit,
+    delay: Duration,
+    retry: RetryPolicy,
+    ctx: DownloadContext,
+) -> Result<i32, NovelError> {
+    tokio::time::sleep(delay).await;
+    tracing::debug!(order = %order, url = %url, "processing");
+    let started = Instant::now();
+    let result = noveler
+        .process_url(ctx.client.clone(), &order, url.clone(), ctx.encoding)
+        .await;
+    // Release the semaphore permit
+    ctx.limiter.release(
+        permit,
+        started.elapsed(),
+        matches!(
+            result,
+            Err(NovelError::ReqwestError(_) | NovelError::TooManyRequests(_))
+        ),
+    );
+    let (chapter, next_page) = match result {
+        Ok(result) => result,
+        Err(err) => {
+            return process_retry_task(err, (order, url, attempt), retry, &ctx).await;
+        }
+    };
+    // a genuinely short chapter is saved after the last attempt
+    if ctx.retry_short
+        && attempt < retry.max_attempts
+        && text_is_short(&chapter.text, ctx.min_chars)
+    {
+        tracing::debug!(order = %order, url = %url, attempt, "retrying short chapter");
+        return requeue_task((order, url, attempt), retry.backoff(attempt), &ctx).await;
+    }
+
+    process_save_task(chapter, &url, next_page, &ctx).await
+}
+
+async fn download_novel_until(
+    noveler: Arc<impl Noveler>,
+    url_contents: &str,
+    dir: &Path,
+    options: &DownloadOptions,
+    client: Client,
+    shutdown: impl std::future::Future<Output = ()>,
+    shutdown_timeout: Duration,
+) -> Result<(PathBuf, DownloadStats), NovelError> {
+    let &DownloadOptions {
+        concurrency,
+        delay,
+        delay_jitter,
+        retry,
+        ..
+    } = options;
+
+    let started = Instant::now();
+    let (state, dir) =
+        prepare_book_dir(&noveler, client.clone(), url_contents, dir, options).await?;
+
+    let failed = Arc::new(FailedManifest::new(dir.join(FAILED_FILE)));
+    let pages = Arc::new(PageLog::new(dir.join(PAGES_FILE)));
+    let mut urls = chapter_urls_to_download(&state, &dir, options, &failed, &pages).await?;
+    if options.respect_robots_txt {
+        urls = robots::allowed_chapters(&client, urls).await?;
+    }
+    let dedup = content_hashes(&dir, options)?;
+
+    let concurrency = concurrency.unwrap_or_else(|| noveler.default_concurrency());
+    let (progress, chapter_sizes) =
+        chapter_progress(&urls, client.clone(), concurrency.initial(), options).await;
+
+    let queued = urls.len();
+    let (tx, mut rx) = mpsc::channel::<UrlTask>(10);
+    let mut tasks = process_url_contents(urls, tx.clone());
+    let ctx = DownloadContext {
+        client,
+        limiter: Arc::new(ConcurrencyLimiter::new(concurrency)),
+        tx,
+        dir,
+        pages,
+        failed,
+        dedup,
+        saved_chapters: Arc::default(),
+        saved_words: Arc::default(),
+        min_chars: options.min_chars,
+        retry_short: options.retry_short,
+        title_in_filename: options.chapter_title_in_filename,
+        progress,
+        chapter_sizes,
+        encoding: options.encoding,
+    };
+
+    // the order which first claimed each url, a next page can point at a chapter already queued
+    let mut seen: HashMap<Url, String> = HashMap::new();
+    let mut join_set: JoinSet<Result<i32, NovelError>> = JoinSet::new();
+    tokio::pin!(shutdown);
+    let mut interrupted = false;
+    while tasks > 0 {
+        tokio::select! {
+            () = &mut shutdown, if !interrupted => {
+                interrupted = true;
+                break;
+            }
+            Some((order, url, attempt)) = rx.recv() => {
+                if attempt == 1 && ctx.progress.suspend(|| is_claimed(&mut seen, &order, &url)) {
+                    join_set.spawn(async { Ok(-1) });
+                    continue;
+                }
+
+                tracing::debug!(order = %order, url = %url, "insert");
+
+                let permit = ctx.limiter.acquire().await;
+                join_set.spawn(process_url_task(
+                    noveler.clone(),
+                    (order, url, attempt),
+                    permit,
+                    jitter_delay(delay, delay_jitter),
+                    retry,
+                    ctx.clone(),
+                ));
+            }
+            Some(result) = join_set.join_next() => {
+                match result {
+                    Ok(result) => {
+                        tasks += result?;
+                        if ctx.chapter_sizes.is_none() {
+                            ctx.progress.set_length(ctx.progress.position() + u64::try_from(tasks).unwrap_or_default());
+                        }
+                        tracing::debug!(tasks, "tasks left");
+                    }
+                    Err(join_error) => {
+                        ctx.progress.suspend(|| {
+                            tracing::error!(error = ?join_error, panic = join_error.is_panic(), "async task failed");
+                        });
+                    }
+                }
+            }
+        };
+    }
+    if interrupted {
+        rx.close();
+        drain_tasks(join_set, shutdown_timeout, &ctx.progress).await;
+    }
+    ctx.progress.finish();
+
+    let stats = DownloadStats {
+        queued,
+        failed: ctx.failed.len().await,
+        elapsed_secs: started.elapsed().as_secs_f64(),
+        interrupted,
+    };
+    save_metadata(&state, &ctx, stats.clone(), options.output_json.as_deref()).await?;
+
+    Ok((ctx.dir, stats))
+}
+
+/// `metadata.json` and `stats.json` in the book dir, and with `stats` to `output_json` when asked for
+async fn save_metadata(
+    state: &DownloadState,
+    ctx: &DownloadContext,
+    stats: DownloadStats,
+    output_json: Option<&Path>,
+) -> Result<(), NovelError> {
+    let metadata = Metadata::collect(state, &ctx.pages, &ctx.dir).await?;
+    write_json_atomic(&ctx.dir.join(METADATA_FILE), &metadata).await?;
+    let word_stats = WordStats::new(&metadata, ctx);
+    write_json_atomic(&ctx.dir.join(STATS_FILE), &word_stats).await?;
+    word_stats.log();
+    if let Some(path) = output_json {
+        write_json_atomic(path, &DownloadReport { metadata, stats }).await?;
+    }
+    Ok(())
+}
+
+/// chapter files in `dir`, sorted by their order, then by file name
+///
+/// the title in a file name could put `00001_n.txt` before `00001_第一章.txt` otherwise
+pub(crate) fn chapter_files(dir: &Path) -> Result<Vec<PathBuf>, NovelError> {
+    let entries: Vec<fs::DirEntry> = dir.read_dir()?.collect::<Result<_, std::io::Error>>()?;
+    let mut paths: Vec<PathBuf> = entries
+        .into_iter()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+        .collect();
+    paths.sort_by_cached_key(|path| {
+        let order = chapter_order(path).map(|order| order_key(&order));
+        (order, path.clone())
+    });
+    Ok(paths)
+}
+
+/// the line break of the combined txt, the chapter files always use `\n`
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LineEnding {
+    /// `\n`，Linux 與 macOS
+    Lf,
+    /// `\r\n`，Windows 記事本
+    Crlf,
+    /// `\r`，舊版 Mac OS
+    Cr,
+}
+
+impl LineEnding {
+    fn as_bytes(self) -> &'static [u8] {
+        match self {
+            Self::Lf => b"\n",
+            Self::Crlf => b"\r\n",
+            Self::Cr => b"\r",
+        }
+    }
+
+    /// `text` with each `\n` replaced
+    fn write(self, output: &mut impl Write, text: &[u8]) -> io::Result<()> {
+        if self == Self::Lf {
+            return output.write_all(text);
+        }
+        for (i, line) in text.split(|&b| b == b'\n').enumerate() {
+            if i > 0 {
+                output.write_all(self.as_bytes())?;
+            }
+            output.write_all(line)?;
+        }
+        Ok(())
+    }
+}
+
+/// `buf` is reused across the chapters, one allocation for the whole book
+fn append_chapter(
+    output: &mut impl Write,
+    path: &Path,
+    buf: &mut Vec<u8>,
+    line_ending: LineEnding,
+) -> Result<(), NovelError> {
+    buf.clear();
+    fs::File::open(path)?.read_to_end(buf)?;
+    line_ending.write(output, buf)?;
+
+    // Add a line break after copying each file
+    line_ending.write(output, b"\n\n")?;
+
+    tracing::debug!(file = ?path.file_name(), "appended");
+    Ok(())
+}
+
+pub(crate) fn combine_txt(dir: &Path, line_ending: LineEnding) -> Result<(), NovelError> {
+    let mut save_path = dir.to_path_buf();
+    save_path.set_extension("txt");
+
+    let mut output = io::BufWriter::new(fs::File::create(&save_path)?);
+    let mut buf = Vec::new();
+    for path in chapter_files(dir)? {
+        append_chapter(&mut output, &path, &mut buf, line_ending)?;
+    }
+    output.flush()?;
+
+    tracing::info!(path = %save_path.display(), "combined");
+    Ok(())
+}
+
+/// same as `combine_txt`, but a new `<book>.volNN.txt` every `chapters_per_volume` chapters
+///
+/// the next pages of a chapter, `_n`, stay in its volume
+pub(crate) fn combine_txt_volumes(
+    dir: &Path,
+    chapters_per_volume: NonZeroUsize,
+    line_ending: LineEnding,
+) -> Result<Vec<PathBuf>, NovelError> {
+    // the files of one chapter are next to each other once sorted
+    let mut chapters: Vec<Vec<PathBuf>> = Vec::new();
+    let mut last_chapter = None;
+    for path in chapter_files(dir)? {
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        let chapter = stem.split('_').next().unwrap_or_default().to_string();
+        match chapters.last_mut() {
+            Some(files) if last_chapter.as_ref() == Some(&chapter) => files.push(path),
+            _ => chapters.push(vec![path]),
+        }
+        last_chapter = Some(chapter);
+    }
+
+    let mut save_paths = Vec::new();
+    for (index, volume) in chapters.chunks(chapters_per_volume.get()).enumerate() {
+        let mut save_path = dir.to_path_buf();
+        save_path.set_extension(format!("vol{:02}.txt", index + 1));
+
+        let mut output = io::BufWriter::new(fs::File::create(&save_path)?);
+        let mut buf = Vec::new();
+        for path in volume.iter().flatten() {
+            append_chapter(&mut output, path, &mut buf, line_ending)?;
+        }
+        output.flush()?;
+
+        tracing::info!(path = %save_path.display(), "combined");
+        save_paths.push(save_path);
+    }
+    Ok(save_paths)
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// the book from `metadata.json`, or `book_info.json` when the download did not finish
+fn read_book(dir: &Path) -> Result<Option<Book>, NovelError> {
+    for file in [METADATA_FILE, BOOK_INFO_FILE] {
+        match fs::read_to_string(dir.join(file)) {
+            Ok(json) => return Ok(Some(serde_json::from_str(&json)?)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err.into()),
+        }
+    }
+    Ok(None)
+}
+
+/// same as `combine_txt`, but writes one html page with a chapter index on top
+pub(crate) fn combine_html(dir: &Path) -> Result<(), NovelError> {
+    let mut save_path = dir.to_path_buf();
+    save_path.set_extension("html");
+
+    let book = read_book(dir)?;
+
+    // (id, title, paragraphs)
+    let mut chapters = Vec::new();
+    for path in chapter_files(dir)? {
+        let stem = path
+            .file_stem()
+            .ok_or(NovelError::NotFound("file stem".to_string()))?
+            .to_string_lossy();
+        let content = fs::read_to_string(&path)?;
+        let mut lines = content.lines().map(str::trim).filter(|s| !s.is_empty());
+        let title = lines.next().unwrap_or_default().to_string();
+        let paragraphs: Vec<String> = lines.map(escape_html).collect();
+        chapters.push((format!("ch{stem}"), escape_html(&title), paragraphs));
+    }
+
+    let mut output = io::BufWriter::new(fs::File::create(&save_path)?);
+    writeln!(output, "<!DOCTYPE html>")?;
+    writeln!(output, r#"<html lang="zh-Hant">"#)?;
+    writeln!(output, "<head>")?;
+    writeln!(output, r#"<meta charset="utf-8">"#)?;
+    if let Some(book) = &book {
+        writeln!(output, "<title>{}</title>", escape_html(&book.name))?;
+        writeln!(
+            output,
+            r#"<meta name="author" content="{}">"#,
+            escape_html(&book.author)
+        )?;
+    } else {
+        let name = dir.file_name().unwrap_or_default().to_string_lossy();
+        writeln!(output, "<title>{}</title>", escape_html(&name))?;
+    }
+    writeln!(output, "</head>")?;
+    writeln!(output, "<body>")?;
+
+    writeln!(output, "<nav>")?;
+    writeln!(output, "<ol>")?;
+    for (id, title, _) in &chapters {
+        writeln!(output, r##"<li><a href="#{id}">{title}</a></li>"##)?;
+    }
+    writeln!(output, "</ol>")?;
+    writeln!(output, "</nav>")?;
+
+    for (id, title, paragraphs) in &chapters {
+        writeln!(output, r#"<article id="{id}">"#)?;
+        writeln!(output, "<h2>{title}</h2>")?;
+        for paragraph in paragraphs {
+            writeln!(output, "<p>{paragraph}</p>")?;
+        }
+        writeln!(output, "</article>")?;
+    }
+
+    writeln!(output, "</body>")?;
+    writeln!(output, "</html>")?;
+    output.flush()?;
+
+    tracing::info!(path = %save_path.display(), "combined");
+    Ok(())
+}
+
+/// same as `combine_txt`, but writes markdown, each chapter title as a `##` heading
+///
+/// the book name and author go into a yaml front matter
+pub(crate) fn combine_markdown(dir: &Path) -> Result<PathBuf, NovelError> {
+    let mut save_path = dir.to_path_buf();
+    save_path.set_extension("md");
+
+    let mut output = io::BufWriter::new(fs::File::create(&save_path)?);
+    if let Some(book) = read_book(dir)? {
+        // json strings are valid yaml scalars, quotes and colons are escaped
+        writeln!(output, "---")?;
+        writeln!(output, "title: {}", serde_json::to_string(&book.name)?)?;
+        writeln!(output, "author: {}", serde_json::to_string(&book.author)?)?;
+        writeln!(output, "---")?;
+        writeln!(output)?;
+    }
+
+    for path in chapter_files(dir)? {
+        let content = fs::read_to_string(&path)?;
+        let mut lines = content.lines().map(str::trim).filter(|s| !s.is_empty());
+        writeln!(output, "## {}", lines.next().unwrap_or_default())?;
+        for paragraph in lines {
+            writeln!(output)?;
+            writeln!(output, "{paragraph}")?;
+        }
+        writeln!(output)?;
+
+        tracing::debug!(file = ?path.file_name(), "appended");
+    }
+    output.flush()?;
+
+    tracing::info!(path = %save_path.display(), "combined");
+    Ok(save_path)
+}
+
+/// remove the book dir `temp/<site>/<book>`, then `temp/<site>` and `temp` once they are empty
+///
+/// the saved chapters are gone, so the next download starts over
+pub(crate) fn cleanup_temp_dir(dir: &Path) -> Result<(), NovelError> {
+    fs::remove_dir_all(dir)?;
+
+    for parent in dir.ancestors().skip(1).take(2) {
+        if parent.read_dir()?.next().is_some() {
+            break;
+        }
+        fs::remove_dir(parent)?;
+    }
+
+    Ok(())
+}
+
+/// the `name=value` pairs of a Netscape cookie file which are sent to `host`
+///
+/// the format of browser exports and `curl -c`, seven tab separated fields:
+/// domain, include subdomains, path, secure, expiry, name and value
+pub(crate) fn cookies_for_host(cookie_file: &str, host: &str) -> Vec<(String, String)> {
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    cookie_file
+        .lines()
+        .filter_map(|line| {
+            // curl marks http-only cookies this way, other `#` lines are comments
+            let line = line.strip_prefix("#HttpOnly_").unwrap_or(line);
+            if line.starts_with('#') {
+                return None;
+            }
+            let fields: Vec<&str> = line.trim_end_matches(['\r', '\n']).split('\t').collect();
+            let &[domain, subdomains, _path, _secure, expiry, name, value] = fields.as_slice()
+            else {
+                return None;
+            };
+
+            let domain = domain.trim_start_matches('.');
+            let matched = host == domain
+                || (subdomains.eq_ignore_ascii_case("TRUE")
+                    && host.ends_with(&format!(".{domain}")));
+            // 0 for a session cookie
+            let expired = expiry.parse::<u64>().is_ok_and(|at| at != 0 && at < now);
+            (matched && !expired).then(|| (name.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+/// `proxy` accepts both http and socks5 schemes, with optional `user:pass@`,
+/// `headers` are sent with every request, e.g. the cookie of a solved challenge,
+/// `user_agent` is `DEFAULT_USER_AGENT` when `None`, a `User-Agent` in `headers` wins over both
+pub(crate) fn build_client(
+    proxy: Option<Proxy>,
+    headers: Option<HeaderMap>,
+    user_agent: Option<String>,
+) -> Result<Client, NovelError> {
+    let mut builder = reqwest::Client::builder()
+        .timeout(Duration::from_mins(3))
+        .user_agent(user_agent.unwrap_or_else(|| DEFAULT_USER_AGENT.to_string()));
+    if let Some(proxy) = proxy {
+        builder = builder.proxy(proxy);
+    }
+    if let Some(headers) = headers {
+        builder = builder.default_headers(headers);
+    }
+
+    Ok(builder.build()?)
+}
+
+/// how the body of a page is decoded
+#[derive(Debug, Clone, Copy)]
+enum PageEncoding {
+    /// the charset of the response, utf-8 by default
+    Response,
+    Declared(&'static encoding_rs::Encoding),
+    /// guess with `chardetng`, falling back to the declared encoding
+    AutoDetect(Option<&'static encoding_rs::Encoding>),
+}
+
+/// `encoding` from `--config` first, then what the site declares
+fn page_encoding<N: Noveler + ?Sized>(
+    noveler: &N,
+    encoding: Option<&'static encoding_rs::Encoding>,
+) -> PageEncoding {
+    if let Some(encoding) = encoding {
+        return PageEncoding::Declared(encoding);
+    }
+    match (noveler.auto_detect_encoding(), noveler.need_encoding()) {
+        (true, declared) => PageEncoding::AutoDetect(declared),
+        (false, Some(encoding)) => PageEncoding::Declared(encoding),
+        (false, None) => PageEncoding::Response,
+    }
+}
+
+/// the declared encoding wins when `chardetng` is not confident about its guess
+///
+/// a guess outside of the encodings used by chinese sites is a sample too short to tell
+fn detect_encoding(
+    body: &[u8],
+    declared: Option<&'static encoding_rs::Encoding>,
+) -> &'static encoding_rs::Encoding {
+    let mut detector = EncodingDetector::new();
+    detector.feed(body, true);
+    let (guess, confident) = detector.guess_assess(None, true);
+    let chinese = [
+        encoding_rs::UTF_8,
+        encoding_rs::GBK,
+        encoding_rs::GB18030,
+        encoding_rs::BIG5,
+    ]
+    .contains(&guess);
+
+    match declared {
+        Some(declared) if !confident || !chinese => declared,
+        _ => guess,
+    }
+}
+
+/// wait for the chapters in progress, the ones still running after `timeout` are aborted
+///
+/// an aborted chapter has no checksum and is downloaded again by the next run
+async fn drain_tasks(
+    mut join_set: JoinSet<Result<i32, NovelError>>,
+    timeout: Duration,
+    progress: &ProgressBar,
+) {
+    progress.suspend(|| {
+        tracing::warn!(
+            tasks = join_set.len(),
+            "interrupted, finishing the chapters in progress"
+        );
+    });
+    let drain = async {
+        while let Some(result) = join_set.join_next().await {
+            if let Ok(Err(err)) = result {
+                progress.suspend(|| tracing::error!(error = %err, "chapter failed"));
+            }
+        }
+    };
+    if tokio::time::timeout(timeout, drain).await.is_err() {
+        progress.suspend(|| tracing::warn!(tasks = join_set.len(), "shutdown timeout, aborting"));
+        join_set.abort_all();
+    }
+}
+
+/// the state of the book and its dir under `dir/temp/<site>`, with the book files saved
+async fn prepare_book_dir(
+    noveler: &Arc<impl Noveler>,
+    client: Client,
+    url_contents: &str,
+    dir: &Path,
+    options: &DownloadOptions,
+) -> Result<(DownloadState, PathBuf), NovelError> {
+    if options.respect_robots_txt {
+        robots::check_contents(&client, url_contents).await?;
+    }
+    let dir = dir.join("temp").join(noveler.to_string());
+    let state =
+        DownloadState::load_or_fetch(noveler, client.clone(), &dir, url_contents, options).await?;
+    check_novel_filter(options.novel_filter.as_ref(), &state.book)?;
+
+    let dir = dir.join(state.book.to_string());
+    tokio::fs::create_dir_all(dir.as_path()).await?;
+    remove_part_files(&dir).await?;
+    save_book_files(&state, client, &dir, options.force).await?;
+    Ok((state, dir))
+}
+
+/// delete the `.part` files left by an interrupted run, their chapters are downloaded again
+async fn remove_part_files(dir: &Path) -> Result<(), NovelError> {
+    let mut entries = tokio::fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if path.extension().is_some_and(|ext| ext == PART_EXTENSION) {
+            tracing::debug!(path = %path.display(), "remove part file");
+            tokio::fs::remove_file(path).await?;
+        }
+    }
+    Ok(())
+}
+
+/// `state.json`, `book_info.json` and the cover in the book dir
+///
+/// a `book_info.json` already there is kept unless `force`, it may have been edited by hand
+async fn save_book_files(
+    state: &DownloadState,
+    client: Client,
+    dir: &Path,
+    force: bool,
+) -> Result<(), NovelError> {
+    state.save(dir).await?;
+    let book_info = dir.join(BOOK_INFO_FILE);
+    if force || !book_info.exists() {
+        write_json_atomic(&book_info, &state.book).await?;
+    }
+    if let Some(cover_url) = &state.book.cover_url {
+        // a missing cover should not stop the chapters from downloading
+        if let Err(err) = download_cover(client, cover_url.clone(), dir).await {
+            tracing::warn!(url = %cover_url, error = %err, "download cover fail");
+        }
+    }
+    Ok(())
+}
+
+/// save the cover as `cover.<ext>`, skipped when one is already there
+async fn download_cover(client: Client, url: Url, dir: &Path) -> Result<PathBuf, NovelError> {
+    if let Some(path) = find_cover(dir)? {
+        return Ok(path);
+    }
+
+    let resp = client.get(url.clone()).send().await?.error_for_status()?;
+    let content_type = resp
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let bytes = resp.bytes().await?;
+
+    let path = dir.join(format!(
+        "{COVER_FILE_STEM}.{}",
+        cover_extension(&url, content_type.as_deref())
+    ));
+    // a cut off image would be found by `find_cover` and never fetched again
+    write_atomic(&path, bytes).await?;
+    Ok(path)
+}
+
+fn find_cover(dir: &Path) -> Result<Option<PathBuf>, NovelError> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() && path.file_stem().is_some_and(|stem| stem == COVER_FILE_STEM) {
+            return Ok(Some(path));
+        }
+    }
+    Ok(None)
+}
+
+/// the `Content-Type` of the image, then the extension of the url, `jpg` by default
+fn cover_extension(url: &Url, content_type: Option<&str>) -> &'static str {
+    let mime = content_type
+        .and_then(|content_type| content_type.split(';').next())
+        .map(|mime| mime.trim().to_ascii_lowercase());
+    match mime.as_deref() {
+        Some("image/png") => return "png",
+        Some("image/gif") => return "gif",
+        Some("image/webp") => return "webp",
+        Some("image/jpeg" | "image/jpg") => return "jpg",
+        _ => {}
+    }
+
+    let ext = Path::new(url.path())
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase);
+    match ext.as_deref() {
+        Some("png") => "png",
+        Some("gif") => "gif",
+        Some("webp") => "webp",
+        _ => "jpg",
+    }
+}
+
+async fn get_html_and_fix_encoding<T: IntoUrl>(
+    client: Client,
+    url: T,
+    encoding: PageEncoding,
+) -> Result<String, NovelError> {
+    decode_response(fetch_response(client, url).await?, encoding).await
+}
+
+/// the successful response of `url`, a 429 is `TooManyRequests` so the status is not lost
+async fn fetch_response<T: IntoUrl>(client: Client, url: T) -> Result<Response, NovelError> {
+    let resp = client.get(url).send().await?;
+    if resp.status() == StatusCode::TOO_MANY_REQUESTS {
+        return Err(NovelError::TooManyRequests(retry_after(resp.headers())));
+    }
+    Ok(resp.error_for_status()?)
+}
+
+/// `Retry-After` in seconds or as an HTTP-date, a date in the past is no wait
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(
+        date.duration_since(SystemTime::now())
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+async fn decode_response(resp: Response, encoding: PageEncoding) -> Result<String, NovelError> {
+    let content_type = resp
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+
+    // Extract raw body bytes
+    let body_bytes = resp.bytes().await?;
+
+    Ok(decode_html(&body_bytes, content_type.as_deref(), encoding))
+}
+
+/// the charset of the `Content-Type` header, then the `<meta>` in the first kilobyte,
+/// and only then the encoding of the site
+fn decode_html(body: &[u8], content_type: Option<&str>, encoding: PageEncoding) -> String {
+    let encoding = content_type
+        .and_then(charset_from_content_type)
+        .or_else(|| charset_from_meta(body))
+        .unwrap_or_else(|| match encoding {
+            PageEncoding::Response => encoding_rs::UTF_8,
+            PageEncoding::Declared(encoding) => encoding,
+            PageEncoding::AutoDetect(declared) => detect_encoding(body, declared),
+        });
+
+    // Decode the response body to UTF-8 using the encoding
+    let (decoded, _, _) = encoding.decode(body);
+    decoded.into_owned()
+}
+
+fn charset_from_content_type(content_type: &str) -> Option<&'static encoding_rs::Encoding> {
+    content_type.split(';').skip(1).find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        if !name.trim().eq_ignore_ascii_case("charset") {
+            return None;
+        }
+        encoding_rs::Encoding::for_label(value.trim().trim_matches(['"', '\'']).as_bytes())
+    })
+}
+
+/// both `<meta charset="...">` and `<meta http-equiv="Content-Type" content="...; charset=...">`
+fn charset_from_meta(body: &[u8]) -> Option<&'static encoding_rs::Encoding> {
+    static META_CHARSET: OnceLock<Regex> = OnceLock::new();
+    let re = META_CHARSET.get_or_init(|| {
+        Regex::new(r#"(?i)<meta[^>]*charset\s*=\s*["']?([\w:.-]+)"#).expect("meta charset pattern")
+    });
+
+    let head = String::from_utf8_lossy(&body[..body.len().min(1024)]);
+    let charset = re.captures(&head)?.get(1)?.as_str();
+    encoding_rs::Encoding::for_label(charset.as_bytes())
+}
+
+/// write to `<path>.part` first, then rename it over `path`
+///
+/// an interrupt leaves the old file or none, never a part of the new one, and a failed write
+/// removes its `.part` file
+async fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> Result<(), NovelError> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".");
+    tmp.push(PART_EXTENSION);
+    let tmp = PathBuf::from(tmp);
+
+    let written = match tokio::fs::write(&tmp, contents).await {
+        Ok(()) => tokio::fs::rename(&tmp, path).await,
+        Err(err) => Err(err),
+    };
+    if written.is_err() {
+        remove_file_if_exists(&tmp).await?;
+    }
+    Ok(written?)
+}
+
+async fn write_json_atomic<T: Serialize + ?Sized>(
+    path: &Path,
+    value: &T,
+) -> Result<(), NovelError> {
+    write_atomic(path, serde_json::to_vec_pretty(value)?).await
+}
+
+fn check_novel_filter(novel_filter: Option<&Regex>, book: &Book) -> Result<(), NovelError> {
+    match novel_filter {
+        Some(re) if !re.is_match(&book.to_string()) => Err(NovelError::Skipped(book.to_string())),
+        _ => Ok(()),
+    }
+}
+
+/// keep chapters from `start_index` to `end_index` (1-based, inclusive)
+///
+/// indices outside `1..=urls.len()` are clamped into it
+fn slice_urls(
+    urls: Vec<(String, Url)>,
+    start_index: Option<usize>,
+    end_index: Option<usize>,
+) -> Vec<(String, Url)> {
+    let len = urls.len();
+    if len == 0 {
+        return urls;
+    }
+
+    let clamp = |index: usize, name: &str| {
+        let clamped = index.clamp(1, len);
+        if clamped != index {
+            tracing::warn!(index, clamped, chapters = len, "--{name} out of range");
+        }
+        clamped
+    };
+    let start = start_index.map_or(1, |index| clamp(index, "from")) - 1;
+    let end = end_index.map_or(len, |index| clamp(index, "to"));
+
+    urls.into_iter()
+        .skip(start)
+        .take(end.saturating_sub(start))
+        .collect()
+}
+
+/// `order` is the 1-based number of the chapter in the contents
+fn chapter_is_skipped(order: &str, skip: &[RangeInclusive<usize>]) -> bool {
+    order
+        .parse::<usize>()
+        .is_ok_and(|index| skip.iter().any(|range| range.contains(&index)))
+}
+
+/// with or without the title in the file name, a chapter saved either way is not fetched again
+fn remove_url_with_exist_file(urls: Vec<(String, Url)>, dir: &Path) -> Vec<(String, Url)> {
+    let saved = chapter_files_by_order(dir);
+    urls.into_iter()
+        .filter(|(order, _)| {
+            !saved
+                .get(order)
+                .is_some_and(|path| chapter_file_is_valid(path))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use regex::Regex;
+    use std::sync::atomic::AtomicI32;
+    use tempdir::TempDir;
+
+    async fn guess_coding<T: IntoUrl>(url: T) -> (&'static encoding_rs::Encoding, bool) {
+        let resp = reqwest::get(url).await.unwrap();
+
+        // Extract raw body bytes
+        let body_bytes = resp.bytes().await.unwrap();
+
+        // Use chardetng to detect encoding
+        let mut detector = EncodingDetector::new();
+        detector.feed(&body_bytes, true);
+        detector.guess_assess(None, true)
+    }
+
+    #[ignore = "only for detecting coding"]
+    #[tokio::test]
     async fn test_guess_coding() {
         dbg!(guess_coding("https://www.qbtr.cc/tongren/3655.html").await);
     }
 
-    #[ignore = "only for check coding"]
+    #[test]
+    fn test_detect_encoding() {
+        let text =
+            "天色漸暗，他在沙漠中看到了一朵血紅色的花，花瓣在風中輕輕搖曳，彷彿在訴說著什麼。";
+        let (big5, _, _) = encoding_rs::BIG5.encode(text);
+        assert_eq!(
+            detect_encoding(&big5, Some(encoding_rs::GBK)),
+            encoding_rs::BIG5
+        );
+
+        let text =
+            "天色渐暗，他在沙漠中看到了一朵血红色的花，花瓣在风中轻轻摇曳，仿佛在诉说着什么。";
+        let (gbk, _, _) = encoding_rs::GBK.encode(text);
+        assert_eq!(detect_encoding(&gbk, None), encoding_rs::GBK);
+
+        // too short to be sure
+        let (gbk, _, _) = encoding_rs::GBK.encode("中");
+        assert_eq!(
+            detect_encoding(&gbk, Some(encoding_rs::GBK)),
+            encoding_rs::GBK
+        );
+        assert_ne!(detect_encoding(&gbk, None), encoding_rs::GBK);
+    }
+
+    #[test]
+    fn test_page_encoding() {
+        let fake = FakeNoveler::new("http://novel.invalid".to_string());
+        assert!(matches!(page_encoding(&fake, None), PageEncoding::Response));
+        // `--config` wins over the site
+        assert!(matches!(
+            page_encoding(&fake, Some(encoding_rs::BIG5)),
+            PageEncoding::Declared(encoding) if encoding == encoding_rs::BIG5
+        ));
+    }
+
+    #[test]
+    fn test_decode_html_charset() {
+        static SHUHAI: &[u8] = include_bytes!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/shuhai/chapter.html"
+        ));
+        static QIDIAN: &[u8] = include_bytes!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/qidian/chapter.html"
+        ));
+
+        // gbk from <meta http-equiv>, even if the site is not known to be gbk
+        let html = decode_html(SHUHAI, Some("text/html"), PageEncoding::Response);
+        assert!(html.contains("第一章 初雪"));
+
+        // utf-8 from <meta charset>, even if the site is declared as gbk
+        let html = decode_html(QIDIAN, None, PageEncoding::Declared(encoding_rs::GBK));
+        assert!(html.contains("第一章 表世界"));
+
+        // the header wins over a wrong <meta>
+        let body = r#"<html><head><meta charset="gbk"></head><body>第一章</body></html>"#;
+        let html = decode_html(
+            body.as_bytes(),
+            Some(r#"text/html; charset="UTF-8""#),
+            PageEncoding::Declared(encoding_rs::GBK),
+        );
+        assert!(html.contains("第一章"));
+        let html = decode_html(body.as_bytes(), None, PageEncoding::Response);
+        assert!(!html.contains("第一章"));
+
+        // nothing declared
+        let (body, _, _) = encoding_rs::GBK.encode("<html><body>第一章</body></html>");
+        let html = decode_html(&body, None, PageEncoding::Declared(encoding_rs::GBK));
+        assert!(html.contains("第一章"));
+    }
+
+    #[test]
+    fn test_charset_from_content_type() {
+        assert_eq!(
+            charset_from_content_type("text/html; charset=gb2312"),
+            Some(encoding_rs::GBK)
+        );
+        assert_eq!(
+            charset_from_content_type("text/html;Charset='big5'"),
+            Some(encoding_rs::BIG5)
+        );
+        assert_eq!(charset_from_content_type("text/html"), None);
+        assert_eq!(
+            charset_from_content_type("text/html; charset=unknown"),
+            None
+        );
+    }
+
+    #[ignore = "only for check coding"]
+    #[tokio::test]
+    async fn test_check_coding() {
+        let client = reqwest::Client::new();
+        let document = get_html_and_fix_encoding(
+            client,
+            "https://www.qbtr.cc/tongren/3655.html",
+            PageEncoding::Declared(encoding_rs::GBK),
+        )
+        .await
+        .unwrap();
+        dbg!(document);
+    }
+
+    /// answer every GET with an empty page
+    async fn mock_any_get(server: &mut mockito::ServerGuard) -> mockito::Mock {
+        server
+            .mock("GET", mockito::Matcher::Any)
+            .create_async()
+            .await
+    }
+
+    struct FakeNoveler {
+        re: Regex,
+        host: String,
+        num: AtomicI32,
+        word_limit: Option<usize>,
+        /// the next pages are the urls of the chapters instead of `/next_page/{num}`
+        next_page_is_chapter: bool,
+    }
+
+    impl FakeNoveler {
+        fn new(host: String) -> Self {
+            Self {
+                re: Regex::new(r"text").expect("pattern"),
+                host,
+                num: AtomicI32::new(1),
+                word_limit: None,
+                next_page_is_chapter: false,
+            }
+        }
+    }
+
+    /// the output of a `tracing` subscriber, for checking what is logged
+    #[derive(Clone, Default)]
+    struct LogBuffer(Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl LogBuffer {
+        fn contents(&self) -> String {
+            String::from_utf8_lossy(&self.0.lock().unwrap()).to_string()
+        }
+    }
+
+    impl io::Write for LogBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Display for FakeNoveler {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "FakeNoveler")
+        }
+    }
+
+    impl Noveler for FakeNoveler {
+        fn get_chapter_word_limit(&self) -> Option<usize> {
+            self.word_limit
+        }
+
+        fn get_book_info(&self, _document: &Elements) -> Result<Book, NovelError> {
+            let name = "name".to_string();
+            let author = "author".to_string();
+            Ok(Book {
+                name,
+                author,
+                ..Book::default()
+            })
+        }
+
+        fn get_chapter_urls_sorted(&self, _document: &Elements) -> Result<Vec<Url>, NovelError> {
+            Ok((1..)
+                .take(10)
+                .map(|n| Url::parse(&format!("{}/{}", &self.host, n)).unwrap())
+                .collect())
+        }
+
+        fn get_chapter(&self, _document: &Elements, order: &str) -> Result<Chapter, NovelError> {
+            let title = format!("title_{order}");
+            let text = format!("text_{order}");
+            let order = order.to_string();
+            Ok(Chapter { order, title, text })
+        }
+
+        fn get_next_page(&self, _document: &Elements) -> Result<Option<Url>, NovelError> {
+            let num = self.num.fetch_add(1, Ordering::SeqCst);
+
+            if num > 10 {
+                Ok(None)
+            } else if self.next_page_is_chapter {
+                Ok(Some(Url::parse(&format!("{}/{num}", &self.host))?))
+            } else {
+                let url = Url::parse(&format!("{}/next_page/{num}", &self.host))?;
+                Ok(Some(url))
+            }
+        }
+
+        fn process_chapter_text(&self, text: &str) -> String {
+            self.re.replace_all(text, "text_process").to_string()
+        }
+    }
+
+    #[test]
+    fn test_process_chapter() {
+        let novel = FakeNoveler::new(String::new());
+        let chapter = novel.process_chapter(Chapter {
+            order: "00001".to_string(),
+            title: "title text".to_string(),
+            text: "text_00001".to_string(),
+        });
+        assert_eq!(chapter.order, "00001");
+        // only the text goes through the processor by default
+        assert_eq!(chapter.title, "title text");
+        assert_eq!(chapter.text, "text_process_00001");
+    }
+
+    #[tokio::test]
+    async fn test_process_url_contents() {
+        // Request a new server from the pool
+        let server = mockito::Server::new_async().await;
+
+        // Use one of these addresses to configure your client
+        let url = server.url();
+
+        let fake = Arc::new(FakeNoveler::new(url));
+        let dir = TempDir::new("noveler_test_process_url_contents").unwrap();
+        let path = dir.path();
+        let (tx, mut rx) = mpsc::channel::<UrlTask>(5);
+
+        let contents: &str = include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/hjwzw/contents.html"
+        ));
+        let document = visdom::Vis::load(contents).unwrap();
+
+        let client = reqwest::Client::new();
+        let urls = fake
+            .fetch_chapter_urls_sorted(client, &document)
+            .await
+            .unwrap();
+        let urls = collect_chapter_urls(
+            fake.append_urls_with_orders(urls),
+            Vec::new(),
+            path,
+            &DownloadOptions::default(),
+        );
+        let result = process_url_contents(urls.clone(), tx);
+        assert_eq!(result, 10);
+
+        // every chapter is queued in order for its first attempt
+        let mut queued = Vec::new();
+        while let Some(task) = rx.recv().await {
+            queued.push(task);
+        }
+        let expected: Vec<UrlTask> = urls
+            .into_iter()
+            .map(|(order, url)| (order, url, 1))
+            .collect();
+        assert_eq!(queued, expected);
+        assert_eq!(queued[0].0, "00001");
+    }
+
+    #[test]
+    fn test_text_processor_steps() {
+        let processor = TextProcessor::new()
+            .add_regex_replacement(r"(?m)^.*example\.com.*$", "")
+            .unwrap();
+        assert_eq!(processor.process("a\nad example.com\nb"), "a\n\nb");
+
+        let processor = TextProcessor::new().add_line_split_and_trim(&['\n', '\u{3000}']);
+        assert_eq!(processor.process(" a \u{3000}b\n\n c"), "a\nb\n\nc");
+
+        let processor = TextProcessor::new().add_filter_empty_lines();
+        assert_eq!(processor.process("a\n \n\nb\n"), "a\nb");
+
+        let processor = TextProcessor::new().add_skip_first_n_lines(2);
+        assert_eq!(processor.process("title\nauthor\na\nb"), "a\nb");
+        assert_eq!(processor.process("title"), "");
+
+        assert!(TextProcessor::new().add_regex_replacement("(", "").is_err());
+        assert_eq!(TextProcessor::new().process(" a "), " a ");
+    }
+
+    #[test]
+    fn test_normalize_fullwidth() {
+        assert_eq!(normalize_fullwidth("ｗｗｗ.ｃｏｍ", &[]), "www.com");
+        assert_eq!(normalize_fullwidth("\u{3000}", &[]), " ");
+        assert_eq!(normalize_fullwidth("！～ＡＺ０９", &[]), "!~AZ09");
+        assert_eq!(normalize_fullwidth("ａ，\u{3000}", &['，']), "a， ");
+        // outside the full-width ascii block
+        assert_eq!(normalize_fullwidth("中文。、「」", &[]), "中文。、「」");
+
+        let processor = TextProcessor::new().add_fullwidth_normalization(&['，', '\u{3000}']);
+        assert_eq!(
+            processor.process("ｕｕｋａｎｓｈｕ．ｃｏｍ，\u{3000}ＦＷ！"),
+            "uukanshu.com，\u{3000}FW!"
+        );
+    }
+
+    #[test]
+    fn test_strip_navigation_text() {
+        let text = "上一章 | 目录 | 下一章\nfirst\n加入书签\nsecond (not a.*regex)";
+        assert_eq!(
+            strip_navigation_text(text, &["上一章", "加入书签"]),
+            "first\nsecond (not a.*regex)"
+        );
+        // the patterns are literal
+        assert_eq!(
+            strip_navigation_text(text, &["a.*"]),
+            text.replace("\nsecond (not a.*regex)", "")
+        );
+        assert_eq!(strip_navigation_text(text, &[]), text);
+
+        let processor = TextProcessor::new().add_strip_navigation_text(&["下一章"]);
+        assert_eq!(processor.process("a\n下一章\nb"), "a\nb");
+    }
+
+    #[test]
+    fn test_text_processor_pipeline() {
+        // the steps run in the order they are added
+        let processor = TextProcessor::new()
+            .add_regex_replacement(r"(?m)^.*(?:example\.com|廣告).*$", "")
+            .unwrap()
+            .add_line_split_and_trim(&['\n', '\u{a0}', '\u{3000}', '\r'])
+            .add_filter_empty_lines()
+            .add_skip_first_n_lines(1)
+            .add_step(|text| text.replace('。', "."));
+        let text = "title\r\n\u{3000}\u{3000}first。\u{a0}second。\n廣告 example.com\n\n  last。  ";
+        assert_eq!(processor.process(text), "first.\nsecond.\nlast.");
+    }
+
+    #[test]
+    fn test_collect_chapter_urls_skip() {
+        let url = |path: &str| Url::parse(&format!("https://example.com/{path}")).unwrap();
+        let urls: Vec<(String, Url)> = (1..=10)
+            .map(|n| (format!("{n:05}"), url(&n.to_string())))
+            .collect();
+        let pages = vec![
+            ("00002_n".to_string(), url("2_2")),
+            ("00003_n".to_string(), url("3_2")),
+        ];
+        let dir = TempDir::new("noveler_test_collect_chapter_urls_skip").unwrap();
+
+        let options = DownloadOptions {
+            end_index: Some(6),
+            skip_chapters: vec![1..=1, 3..=5],
+            ..DownloadOptions::default()
+        };
+        let orders: Vec<String> = collect_chapter_urls(urls, pages, dir.path(), &options)
+            .into_iter()
+            .map(|(order, _)| order)
+            .collect();
+        // the next pages of a skipped chapter are skipped too, the others follow their chapter
+        assert_eq!(orders, ["00002", "00002_n", "00006"]);
+
+        assert!(chapter_is_skipped("00010", &[8..=12]));
+        assert!(!chapter_is_skipped("00010_n", &[8..=12]));
+        assert!(!chapter_is_skipped("00010", &[]));
+
+        dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_slice_urls() {
+        let urls: Vec<(String, Url)> = (1..=10)
+            .map(|n| {
+                (
+                    format!("{n:05}"),
+                    Url::parse(&format!("https://example.com/{n}")).unwrap(),
+                )
+            })
+            .collect();
+
+        let orders = |urls: Vec<(String, Url)>| -> Vec<String> {
+            urls.into_iter().map(|(order, _)| order).collect()
+        };
+
+        assert_eq!(
+            orders(slice_urls(urls.clone(), Some(3), Some(5))),
+            ["00003", "00004", "00005"]
+        );
+        assert_eq!(
+            orders(slice_urls(urls.clone(), Some(9), None)),
+            ["00009", "00010"]
+        );
+        assert_eq!(
+            orders(slice_urls(urls.clone(), None, Some(2))),
+            ["00001", "00002"]
+        );
+        assert_eq!(orders(slice_urls(urls.clone(), None, None)).len(), 10);
+        assert!(slice_urls(urls.clone(), Some(6), Some(5)).is_empty());
+
+        // out of range clamps to the first and the last chapter
+        assert_eq!(
+            orders(slice_urls(urls.clone(), Some(0), Some(1))),
+            ["00001"]
+        );
+        assert_eq!(
+            orders(slice_urls(urls.clone(), Some(8), Some(100))),
+            ["00008", "00009", "00010"]
+        );
+        assert_eq!(orders(slice_urls(urls, Some(20), None)), ["00010"]);
+        assert!(slice_urls(Vec::new(), Some(3), Some(5)).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_prefetch_all_chapter_headers() {
+        let mut server = mockito::Server::new_async().await;
+        let url = server.url();
+
+        let mock_1 = server
+            .mock("HEAD", "/1")
+            .with_header("content-length", "100")
+            .create_async()
+            .await;
+        let mock_2 = server
+            .mock("HEAD", "/2")
+            .with_header("content-length", "250")
+            .create_async()
+            .await;
+        let mock_3 = server
+            .mock("HEAD", "/3")
+            .with_status(404)
+            .create_async()
+            .await;
+
+        let urls: Vec<(String, Url)> = (1..=3)
+            .map(|n| {
+                (
+                    format!("{n:05}"),
+                    Url::parse(&format!("{url}/{n}")).unwrap(),
+                )
+            })
+            .collect();
+
+        let client = reqwest::Client::new();
+        let sizes = prefetch_all_chapter_headers(&urls, client.clone(), 2).await;
+
+        mock_1.assert_async().await;
+        mock_2.assert_async().await;
+        mock_3.assert_async().await;
+        assert_eq!(sizes.get(&urls[0].1), Some(&100));
+        assert_eq!(sizes.get(&urls[1].1), Some(&250));
+        assert_eq!(sizes.values().sum::<u64>(), 350);
+
+        // one chapter without a length, the bar falls back to chapters
+        assert_eq!(estimate_size(&urls, client.clone(), 2).await, None);
+        let sizes = estimate_size(&urls[..2], client, 2).await.unwrap();
+        assert_eq!(sizes.values().sum::<u64>(), 350);
+    }
+
+    #[tokio::test]
+    async fn test_build_client_with_proxy() {
+        let mut server = mockito::Server::new_async().await;
+        let proxy = server
+            .mock("GET", mockito::Matcher::Any)
+            .with_body("proxied")
+            .create_async()
+            .await;
+
+        let client = build_client(Some(Proxy::all(server.url()).unwrap()), None, None).unwrap();
+        let text =
+            get_html_and_fix_encoding(client, "http://novel.invalid/", PageEncoding::Response)
+                .await
+                .unwrap();
+
+        proxy.assert_async().await;
+        assert_eq!(text, "proxied");
+    }
+
+    #[tokio::test]
+    async fn test_build_client_user_agent() {
+        let mut server = mockito::Server::new_async().await;
+        let default = server
+            .mock("GET", "/default")
+            .match_header("user-agent", DEFAULT_USER_AGENT)
+            .create_async()
+            .await;
+        let custom = server
+            .mock("GET", "/custom")
+            .match_header("user-agent", "get_novel/0.1")
+            .create_async()
+            .await;
+
+        let client = build_client(None, None, None).unwrap();
+        let response = client
+            .get(format!("{}/default", server.url()))
+            .send()
+            .await
+            .unwrap();
+        assert!(response.status().is_success());
+
+        let client = build_client(None, None, Some("get_novel/0.1".to_string())).unwrap();
+        let response = client
+            .get(format!("{}/custom", server.url()))
+            .send()
+            .await
+            .unwrap();
+        assert!(response.status().is_success());
+
+        default.assert_async().await;
+        custom.assert_async().await;
+    }
+
+    #[test]
+    fn test_cookies_for_host() {
+        let cookie_file = [
+            "# Netscape HTTP Cookie File",
+            "",
+            ".qidian.com\tTRUE\t/\tTRUE\t0\tcf_clearance\tabc123",
+            "#HttpOnly_.qidian.com\tTRUE\t/\tTRUE\t0\t__cf_bm\txyz",
+            "book.qidian.com\tFALSE\t/\tFALSE\t0\tsession\ts1",
+            "www.qidian.com\tFALSE\t/\tFALSE\t0\tother\to1",
+            ".qidian.com\tTRUE\t/\tFALSE\t1\texpired\te1",
+            ".czbooks.net\tTRUE\t/\tFALSE\t0\tczbooks\tc1",
+            "not a cookie line",
+        ]
+        .join("\n");
+
+        let names = |host: &str| -> Vec<String> {
+            cookies_for_host(&cookie_file, host)
+                .into_iter()
+                .map(|(name, value)| format!("{name}={value}"))
+                .collect()
+        };
+        assert_eq!(
+            names("book.qidian.com"),
+            ["cf_clearance=abc123", "__cf_bm=xyz", "session=s1"]
+        );
+        assert_eq!(names("qidian.com"), ["cf_clearance=abc123", "__cf_bm=xyz"]);
+        assert_eq!(names("czbooks.net"), ["czbooks=c1"]);
+        assert!(names("notqidian.com").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_build_client_with_headers() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/")
+            .match_header("cookie", "cf_clearance=abc123")
+            .with_body("cleared")
+            .create_async()
+            .await;
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            reqwest::header::COOKIE,
+            "cf_clearance=abc123".parse().unwrap(),
+        );
+        let client = build_client(None, Some(headers), None).unwrap();
+        let text = get_html_and_fix_encoding(client, server.url(), PageEncoding::Response)
+            .await
+            .unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(text, "cleared");
+
+        // without the cookie the mock does not match
+        let client = build_client(None, None, None).unwrap();
+        let response = client.get(server.url()).send().await.unwrap();
+        assert_eq!(response.status(), 501);
+    }
+
+    #[test]
+    fn test_cover_extension() {
+        let url = Url::parse("https://img.example.com/cover/1.PNG").unwrap();
+        assert_eq!(cover_extension(&url, None), "png");
+        assert_eq!(cover_extension(&url, Some("image/webp; q=1")), "webp");
+        let url = Url::parse("https://img.example.com/cover?id=1").unwrap();
+        assert_eq!(cover_extension(&url, None), "jpg");
+        assert_eq!(
+            cover_extension(&url, Some("application/octet-stream")),
+            "jpg"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_download_cover() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/cover")
+            .with_header("content-type", "image/png")
+            .with_body("png bytes")
+            .expect(1)
+            .create_async()
+            .await;
+
+        let dir = TempDir::new("noveler_test_download_cover").unwrap();
+        // left by an interrupted run, not taken for the cover
+        fs::write(dir.path().join("cover.png.part"), "png").unwrap();
+        let url = Url::parse(&format!("{}/cover", server.url())).unwrap();
+        let path = download_cover(Client::new(), url.clone(), dir.path())
+            .await
+            .unwrap();
+        assert_eq!(path, dir.path().join("cover.png"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "png bytes");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+
+        // already downloaded
+        let path = download_cover(Client::new(), url, dir.path())
+            .await
+            .unwrap();
+        assert_eq!(path, dir.path().join("cover.png"));
+        mock.assert_async().await;
+    }
+
+    #[test]
+    fn test_chapter_url_is_valid() {
+        let novel = Biquge::new("https://www.biquge.co/0_410/").unwrap();
+        assert!(
+            novel.chapter_url_is_valid(&Url::parse("https://www.biquge.co/0_410/1.html").unwrap())
+        );
+        assert!(!novel.chapter_url_is_valid(&Url::parse("https://www.biquge.co/").unwrap()));
+        assert!(!novel.chapter_url_is_valid(&Url::parse("https://www.example.com/1.html").unwrap()));
+
+        let novel = Qidian::new("https://book.qidian.com/info/1035420986/").unwrap();
+        assert!(novel.chapter_url_is_valid(
+            &Url::parse("https://www.qidian.com/chapter/1035420986/694135582/").unwrap()
+        ));
+        assert!(!novel.chapter_url_is_valid(&Url::parse("https://www.qidian.com/").unwrap()));
+
+        let novel = Czbooks::new().unwrap();
+        assert!(novel.chapter_url_is_valid(&Url::parse("https://czbooks.net/").unwrap()));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_book_summary() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = mock_any_get(&mut server).await;
+        let url = server.url();
+
+        let summary = fetch_book_summary(&FakeNoveler::new(url.clone()), &url, Client::new(), None)
+            .await
+            .unwrap();
+        assert_eq!(summary.chapter_count, 10);
+        assert_eq!(
+            summary.last_chapter.as_ref().unwrap().url,
+            Url::parse(&format!("{url}/10")).unwrap()
+        );
+        assert_eq!(
+            summary.to_string(),
+            format!(
+                "name: name\nauthor: author\nchapters: 10\nfirst: - {url}/1\nlast: - {url}/10\n"
+            )
+        );
+
+        let json: serde_json::Value = serde_json::to_value(&summary).unwrap();
+        assert_eq!(json["name"], "name");
+        assert_eq!(json["author"], "author");
+        assert_eq!(json["url_contents"], url.as_str());
+        assert_eq!(json["chapter_count"], 10);
+        assert_eq!(json["first_chapter"]["url"], format!("{url}/1"));
+        assert_eq!(json["first_chapter"]["title"], serde_json::Value::Null);
+        assert_eq!(json["cover_url"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn test_parse_word_count() {
+        assert_eq!(parse_word_count("字数：7446890"), Some(7_446_890));
+        assert_eq!(parse_word_count("字數：1,234,567"), Some(1_234_567));
+        assert_eq!(parse_word_count("744.69萬字"), Some(7_446_900));
+        assert_eq!(parse_word_count("共 120 万字"), Some(1_200_000));
+        assert_eq!(parse_word_count("1.5億字"), Some(150_000_000));
+        assert_eq!(parse_word_count("3.5 字"), Some(3));
+        assert_eq!(parse_word_count("1.234567萬"), Some(12_345));
+        assert_eq!(parse_word_count("字数："), None);
+        assert_eq!(parse_word_count("99999999999999999999萬"), None);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_chapter_list() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = mock_any_get(&mut server).await;
+        let url = server.url();
+
+        let chapters =
+            fetch_chapter_list(&FakeNoveler::new(url.clone()), &url, Client::new(), None)
+                .await
+                .unwrap();
+        assert_eq!(chapters.len(), 10);
+        for (i, (order, chapter_url)) in chapters.iter().enumerate() {
+            assert_eq!(order, &format!("{:05}", i + 1));
+            assert_eq!(chapter_url.as_str(), format!("{url}/{}", i + 1));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chapters_to_download() {
+        let mut server = mockito::Server::new_async().await;
+        let contents = server.mock("GET", "/").create_async().await;
+        // no chapter page is fetched
+        let chapters = server
+            .mock("GET", mockito::Matcher::Any)
+            .expect(0)
+            .create_async()
+            .await;
+        let url = server.url();
+
+        let dir = TempDir::new("noveler_test_chapters_to_download").unwrap();
+        let path = dir.path();
+        let noveler = Arc::new(FakeNoveler::new(url.clone()));
+        let options = DownloadOptions {
+            skip_chapters: vec![2..=3],
+            ..DownloadOptions::default()
+        };
+        let urls = chapters_to_download(&noveler, &url, path, &options, Client::new())
+            .await
+            .unwrap();
+        contents.assert_async().await;
+        chapters.assert_async().await;
+        // nothing written, not even the book dir
+        assert_eq!(fs::read_dir(path).unwrap().count(), 0);
+        let orders: Vec<&str> = urls.iter().map(|(order, _)| order.as_str()).collect();
+        assert_eq!(orders.len(), 8);
+        assert!(!orders.contains(&"00002"));
+
+        // only the missing chapters are left
+        let chapter_dir = path.join("temp/FakeNoveler/author_name");
+        fs::create_dir_all(&chapter_dir).unwrap();
+        let chapter = chapter_dir.join("00001.txt");
+        fs::write(&chapter, "text").unwrap();
+        fs::write(checksum_path(&chapter), sha256_hex(b"text")).unwrap();
+        let urls = chapters_to_download(&noveler, &url, path, &options, Client::new())
+            .await
+            .unwrap();
+        assert_eq!(urls.len(), 7);
+        assert_eq!(urls[0].0, "00004");
+
+        dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_sanitize_path_component() {
+        assert_eq!(sanitize_path_component("小說：《特殊》"), "小說：《特殊》");
+        assert_eq!(sanitize_path_component("00001_n.txt"), "00001_n.txt");
+
+        let long = "說".repeat(100);
+        let sanitized = sanitize_path_component(&long);
+        assert!(sanitized.len() <= MAX_PATH_COMPONENT_BYTES);
+        assert!(long.starts_with(&sanitized));
+
+        if cfg!(target_os = "windows") {
+            assert_eq!(sanitize_path_component("NUL"), "_NUL");
+            assert_eq!(sanitize_path_component("com1.txt"), "_com1.txt");
+            assert_eq!(
+                sanitize_path_component("a<b>:\"c/d\\e|f?*"),
+                "a_b___c_d_e_f__"
+            );
+            assert_eq!(sanitize_path_component("name. "), "name");
+        } else {
+            assert_eq!(sanitize_path_component("NUL"), "NUL");
+            assert_eq!(
+                sanitize_path_component("a<b>:\"c/d\\e|f?*"),
+                "a<b>:\"c_d\\e|f?*"
+            );
+        }
+
+        let book = Book {
+            name: "上/下".to_string(),
+            author: "作者".to_string(),
+            ..Book::default()
+        };
+        assert_eq!(book.to_string(), "作者_上_下");
+    }
+
+    #[test]
+    fn test_reorder_chapters() {
+        let url = Url::parse("https://example.com").unwrap();
+        let urls = ["00010", "00002_n", "00001", "100000", "00002"]
+            .into_iter()
+            .map(|order| (order.to_string(), url.clone()))
+            .collect();
+
+        let orders: Vec<String> = reorder_chapters(urls)
+            .into_iter()
+            .map(|(order, _)| order)
+            .collect();
+        assert_eq!(orders, ["00001", "00002", "00002_n", "00010", "100000"]);
+    }
+
+    #[test]
+    fn test_reorder_chapters_numeric() {
+        let url = Url::parse("https://example.com").unwrap();
+        let urls = ["10", "9", "9_n", "1"]
+            .into_iter()
+            .map(|order| (order.to_string(), url.clone()))
+            .collect();
+
+        // "10" after "9", not after "1" as a string sort puts it
+        let orders: Vec<String> = reorder_chapters(urls)
+            .into_iter()
+            .map(|(order, _)| order)
+            .collect();
+        assert_eq!(orders, ["1", "9", "9_n", "10"]);
+    }
+
+    fn test_context(dir: &Path) -> (DownloadContext, mpsc::Receiver<UrlTask>) {
+        let (tx, rx) = mpsc::channel::<UrlTask>(5);
+        let ctx = DownloadContext {
+            client: Client::new(),
+            limiter: Arc::new(ConcurrencyLimiter::new(ConcurrencyPolicy::Fixed(1))),
+            tx,
+            dir: dir.to_path_buf(),
+            pages: Arc::new(PageLog::new(dir.join(PAGES_FILE))),
+            failed: Arc::new(FailedManifest::new(dir.join(FAILED_FILE))),
+            dedup: None,
+            saved_chapters: Arc::default(),
+            saved_words: Arc::default(),
+            min_chars: 0,
+            retry_short: false,
+            title_in_filename: false,
+            progress: ProgressBar::hidden(),
+            chapter_sizes: None,
+            encoding: None,
+        };
+        (ctx, rx)
+    }
+
+    #[tokio::test]
+    async fn test_process_save_task() {
+        let dir = TempDir::new("noveler_test_process_save_task").unwrap();
+        let path = dir.path();
+
+        let (ctx, _rx) = test_context(path);
+
+        let chapter = Chapter {
+            order: "order".to_string(),
+            title: "title".to_string(),
+            text: "text".to_string(),
+        };
+        let url = Url::parse("https://example.com/1").unwrap();
+        process_save_task(chapter.clone(), &url, None, &ctx)
+            .await
+            .unwrap();
+        assert_eq!(ctx.progress.position(), 1);
+
+        let file_path = path.join(file_name(&chapter.order, None));
+        dbg!(&file_path);
+        assert!(file_path.is_file());
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), "title\n\ntext");
+        assert_eq!(
+            fs::read_to_string(checksum_path(&file_path)).unwrap(),
+            format!("{}\n", sha256_hex(b"title\n\ntext"))
+        );
+        assert!(chapter_file_is_valid(&file_path));
+        assert!(ctx.pages.read().await.unwrap().is_empty());
+        // renamed into place, no temporary file is left
+        assert_eq!(fs::read_dir(path).unwrap().count(), 2);
+
+        // the bar counts the prefetched bytes, a next page has none
+        let ctx = DownloadContext {
+            chapter_sizes: Some(Arc::new(HashMap::from([(url.clone(), 100)]))),
+            progress: ProgressBar::hidden(),
+            ..ctx
+        };
+        process_save_task(chapter.clone(), &url, None, &ctx)
+            .await
+            .unwrap();
+        let next_page = url.join("1_2").unwrap();
+        process_save_task(chapter, &next_page, None, &ctx)
+            .await
+            .unwrap();
+        assert_eq!(ctx.progress.position(), 100);
+    }
+
+    #[tokio::test]
+    async fn test_process_save_task_dedup() {
+        let dir = TempDir::new("noveler_test_process_save_task_dedup").unwrap();
+        let path = dir.path();
+        fs::write(path.join("00001.txt"), "title\n\nsaved before").unwrap();
+
+        let (ctx, _rx) = test_context(path);
+        let ctx = DownloadContext {
+            dedup: Some(Arc::new(ContentHashes::load(path).unwrap())),
+            ..ctx
+        };
+        for (order, text) in [
+            ("00002", "text"),
+            ("00003", "text"),
+            ("00004", "saved before"),
+            ("00005", "other"),
+        ] {
+            let chapter = Chapter {
+                order: order.to_string(),
+                title: format!("title_{order}"),
+                text: text.to_string(),
+            };
+            let url = Url::parse(&format!("https://example.com/{order}")).unwrap();
+            process_save_task(chapter, &url, None, &ctx).await.unwrap();
+        }
+
+        assert!(path.join("00002.txt").is_file());
+        assert!(!path.join("00003.txt").exists());
+        assert!(!path.join("00004.txt").exists());
+        assert!(path.join("00005.txt").is_file());
+
+        dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_verify_chapters() {
+        let dir = TempDir::new("noveler_test_verify_chapters").unwrap();
+        let path = dir.path();
+
+        assert_eq!(
+            sha256_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        for (name, content, checksum) in [
+            ("00001.txt", "ok", Some(sha256_hex(b"ok"))),
+            ("00002.txt", "corrupted", Some(sha256_hex(b"original"))),
+            ("00003.txt", "no sidecar", None),
+        ] {
+            fs::write(path.join(name), content).unwrap();
+            if let Some(checksum) = checksum {
+                fs::write(checksum_path(&path.join(name)), checksum).unwrap();
+            }
+        }
+
+        assert_eq!(
+            verify_chapters(path).unwrap(),
+            [path.join("00002.txt"), path.join("00003.txt")]
+        );
+
+        dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_duplicate_chapters() {
+        let dir = TempDir::new("noveler_test_duplicate_chapters").unwrap();
+        let path = dir.path();
+
+        for (name, content) in [
+            ("00001.txt", "title_1\n\ntext"),
+            ("00002.txt", "title_2\n\nother"),
+            ("00003.txt", "title_3\n\ntext"),
+            ("00004.txt", "title_4\n\nother"),
+            ("00005.txt", "title_5\n\nunique"),
+            ("00006.txt", "title_6\n\n"),
+            ("00007.txt", "title_7\n\n"),
+        ] {
+            fs::write(path.join(name), content).unwrap();
+        }
+        fs::write(checksum_path(&path.join("00001.txt")), "not a chapter").unwrap();
+
+        assert_eq!(
+            duplicate_chapters(path).unwrap(),
+            [
+                [path.join("00001.txt"), path.join("00003.txt")],
+                [path.join("00002.txt"), path.join("00004.txt")],
+            ]
+        );
+
+        dir.close().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_page_log() {
+        let dir = TempDir::new("noveler_test_page_log").unwrap();
+        let path = dir.path().join(PAGES_FILE);
+        let pages = PageLog::new(path.clone());
+        assert!(pages.read().await.unwrap().is_empty());
+
+        let url = Url::parse("https://example.com/1_2").unwrap();
+        pages.push("00001_n", &url).await.unwrap();
+        pages.push("00001_n", &url).await.unwrap();
+        // cut off by an interrupt
+        let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+        write!(file, r#"{{"order":"00002_n","u"#).unwrap();
+
+        assert_eq!(pages.read().await.unwrap(), [("00001_n".to_string(), url)]);
+
+        dir.close().unwrap();
+    }
+
+    #[tokio::test]
+    #[allow(clippy::too_many_lines)]
+    async fn test_basic_noveler() {
+        // Request a new server from the pool
+        let mut server = mockito::Server::new_async().await;
+        let _mock = mock_any_get(&mut server).await;
+
+        // Use one of these addresses to configure your client
+        let url = server.url();
+
+        let fake = FakeNoveler::new(url.clone());
+        let dir = TempDir::new("noveler_test_basic_noveler").unwrap();
+        let path = dir.path();
+        let options = DownloadOptions {
+            concurrency: Some(ConcurrencyPolicy::Fixed(5)),
+            ..DownloadOptions::default()
+        };
+        let (chapter_dir, _) = download_novel(
+            Arc::new(fake),
+            url.as_str(),
+            path,
+            &options,
+            Client::new(),
+            Duration::from_secs(10),
+        )
+        .await
+        .unwrap();
+
+        assert!(path.join("temp/FakeNoveler/author_name/00001.txt").exists());
+        assert!(path
+            .join("temp/FakeNoveler/author_name/00001_n.txt")
+            .exists());
+        assert!(path.join("temp/FakeNoveler/author_name/00002.txt").exists());
+        assert!(path.join("temp/FakeNoveler/author_name/00003.txt").exists());
+        assert!(path.join("temp/FakeNoveler/author_name/00004.txt").exists());
+        assert!(path.join("temp/FakeNoveler/author_name/00005.txt").exists());
+        assert!(path.join("temp/FakeNoveler/author_name/00006.txt").exists());
+        assert!(path.join("temp/FakeNoveler/author_name/00007.txt").exists());
+        assert!(path.join("temp/FakeNoveler/author_name/00008.txt").exists());
+        assert!(path.join("temp/FakeNoveler/author_name/00009.txt").exists());
+        assert!(path.join("temp/FakeNoveler/author_name/00010.txt").exists());
+        assert_eq!(
+            tokio::fs::read_to_string(path.join("temp/FakeNoveler/author_name/00001.txt"))
+                .await
+                .unwrap(),
+            "title_00001\n\ntext_process_00001"
+        );
+
+        combine_txt(&chapter_dir, LineEnding::Lf).unwrap();
+        assert_eq!(
+            tokio::fs::read_to_string(path.join("temp/FakeNoveler/author_name.txt"))
+                .await
+                .unwrap(),
+            r"title_00001
+
+text_process_00001
+
+title_00001_n
+
+text_process_00001_n
+
+title_00002
+
+text_process_00002
+
+title_00002_n
+
+text_process_00002_n
+
+title_00003
+
+text_process_00003
+
+title_00003_n
+
+text_process_00003_n
+
+title_00004
+
+text_process_00004
+
+title_00004_n
+
+text_process_00004_n
+
+title_00005
+
+text_process_00005
+
+title_00005_n
+
+text_process_00005_n
+
+title_00006
+
+text_process_00006
+
+title_00006_n
+
+text_process_00006_n
+
+title_00007
+
+text_process_00007
+
+title_00007_n
+
+text_process_00007_n
+
+title_00008
+
+text_process_00008
+
+title_00008_n
+
+text_process_00008_n
+
+title_00009
+
+text_process_00009
+
+title_00009_n
+
+text_process_00009_n
+
+title_00010
+
+text_process_00010
+
+title_00010_n
+
+text_process_00010_n
+
+"
+        );
+
+        dir.close().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_download_novel_metadata() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = mock_any_get(&mut server).await;
+        let url = server.url();
+
+        let dir = TempDir::new("noveler_test_download_novel_metadata").unwrap();
+        let (chapter_dir, _) = download_novel(
+            Arc::new(FakeNoveler::new(url.clone())),
+            url.as_str(),
+            dir.path(),
+            &DownloadOptions::default(),
+            Client::new(),
+            Duration::from_secs(10),
+        )
+        .await
+        .unwrap();
+
+        let metadata: Metadata =
+            serde_json::from_str(&fs::read_to_string(chapter_dir.join(METADATA_FILE)).unwrap())
+                .unwrap();
+        assert_eq!(metadata.book.to_string(), "author_name");
+        assert_eq!(metadata.source_url, url);
+        assert_eq!(metadata.chapter_count, 20);
+        assert_eq!(metadata.chapters.len(), 20);
+        assert_eq!(
+            metadata.chapters[0],
+            ChapterMeta {
+                order: "00001".to_string(),
+                title: "title_00001".to_string(),
+                source_url: Url::parse(&format!("{url}/1")).unwrap(),
+                word_count: "text_process_00001".len(),
+            }
+        );
+        assert_eq!(metadata.chapters[1].order, "00001_n");
+        assert_eq!(metadata.chapters[19].title, "title_00010_n");
+
+        dir.close().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_download_novel_word_stats() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = mock_any_get(&mut server).await;
+        let url = server.url();
+
+        let dir = TempDir::new("noveler_test_download_novel_word_stats").unwrap();
+        let (chapter_dir, _) = download_novel(
+            Arc::new(FakeNoveler::new(url.clone())),
+            url.as_str(),
+            dir.path(),
+            &DownloadOptions::default(),
+            Client::new(),
+            Duration::from_secs(10),
+        )
+        .await
+        .unwrap();
+
+        let stats: WordStats =
+            serde_json::from_str(&fs::read_to_string(chapter_dir.join(STATS_FILE)).unwrap())
+                .unwrap();
+        assert_eq!(stats.saved_chapters, 20);
+        assert_eq!(stats.saved_word_count, 10 * 18 + 10 * 20);
+        assert_eq!(stats.chapter_count, 20);
+        assert_eq!(stats.word_count, 10 * 18 + 10 * 20);
+        let orders = |chapters: &[ChapterMeta]| -> Vec<String> {
+            chapters
+                .iter()
+                .map(|chapter| chapter.order.clone())
+                .collect()
+        };
+        assert_eq!(
+            orders(&stats.longest),
+            ["00001_n", "00002_n", "00003_n", "00004_n", "00005_n"]
+        );
+        assert_eq!(
+            orders(&stats.shortest),
+            ["00001", "00002", "00003", "00004", "00005"]
+        );
+
+        dir.close().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_download_novel_duplicate_next_pages() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = mock_any_get(&mut server).await;
+        let url = server.url();
+
+        let logs = LogBuffer::default();
+        let writer = logs.clone();
+        let _guard = tracing::subscriber::set_default(
+            tracing_subscriber::fmt()
+                .with_writer(move || writer.clone())
+                .with_ansi(false)
+                .finish(),
+        );
+
+        let fake = FakeNoveler {
+            next_page_is_chapter: true,
+            ..FakeNoveler::new(url.clone())
+        };
+        let dir = TempDir::new("noveler_test_download_novel_duplicate_next_pages").unwrap();
+        let (chapter_dir, _) = download_novel(
+            Arc::new(fake),
+            url.as_str(),
+            dir.path(),
+            &DownloadOptions::default(),
+            Client::new(),
+            Duration::from_secs(10),
+        )
+        .await
+        .unwrap();
+
+        // every next page is one of the 10 chapters, each url is saved once
+        assert_eq!(chapter_files(&chapter_dir).unwrap().len(), 10);
+        let logs = logs.contents();
+        assert_eq!(logs.matches("already downloaded as order").count(), 10);
+        assert!(logs.contains(&format!("URL {url}/1 already downloaded as order ")));
+
+        dir.close().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_download_novel_resume_next_pages() {
+        // Request a new server from the pool
+        let mut server = mockito::Server::new_async().await;
+        let _mock = mock_any_get(&mut server).await;
+
+        // Use one of these addresses to configure your client
+        let url = server.url();
+
+        let dir = TempDir::new("noveler_test_download_novel_resume_next_pages").unwrap();
+        let path = dir.path();
+        let (chapter_dir, _) = download_novel(
+            Arc::new(FakeNoveler::new(url.clone())),
+            url.as_str(),
+            path,
+            &DownloadOptions::default(),
+            Client::new(),
+            Duration::from_secs(10),
+        )
+        .await
+        .unwrap();
+
+        // killed after the chapter is saved, before its next page
+        let next_pages: Vec<PathBuf> = fs::read_dir(&chapter_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.to_string_lossy().ends_with("_n.txt"))
+            .collect();
+        assert!(!next_pages.is_empty());
+        for next_page in &next_pages {
+            fs::remove_file(next_page).unwrap();
+        }
+        fs::write(chapter_dir.join("00001.txt"), "saved").unwrap();
+        fs::write(
+            checksum_path(&chapter_dir.join("00001.txt")),
+            sha256_hex(b"saved"),
+        )
+        .unwrap();
+
+        // no more next pages in the resumed run
+        let fake = FakeNoveler {
+            num: AtomicI32::new(11),
+            ..FakeNoveler::new(url.clone())
+        };
+        download_novel(
+            Arc::new(fake),
+            url.as_str(),
+            path,
+            &DownloadOptions::default(),
+            Client::new(),
+            Duration::from_secs(10),
+        )
+        .await
+        .unwrap();
+
+        for next_page in &next_pages {
+            assert!(next_page.exists(), "{next_page:?}");
+        }
+        assert_eq!(
+            fs::read_to_string(chapter_dir.join("00001.txt")).unwrap(),
+            "saved"
+        );
+
+        dir.close().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_combine_html() {
+        // Request a new server from the pool
+        let mut server = mockito::Server::new_async().await;
+        let _mock = mock_any_get(&mut server).await;
+
+        // Use one of these addresses to configure your client
+        let url = server.url();
+
+        let fake = FakeNoveler::new(url.clone());
+        let dir = TempDir::new("noveler_test_combine_html").unwrap();
+        let path = dir.path();
+        let (chapter_dir, _) = download_novel(
+            Arc::new(fake),
+            url.as_str(),
+            path,
+            &DownloadOptions::default(),
+            Client::new(),
+            Duration::from_secs(10),
+        )
+        .await
+        .unwrap();
+        fs::write(chapter_dir.join("00011.txt"), "a < b\n\nc & d\ne").unwrap();
+
+        combine_html(&chapter_dir).unwrap();
+        let html = fs::read_to_string(path.join("temp/FakeNoveler/author_name.html")).unwrap();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+
+        let document = scraper::Html::parse_document(&html);
+        assert!(document.errors.is_empty(), "{:?}", document.errors);
+        let select = |selector: &str| scraper::Selector::parse(selector).unwrap();
+
+        let title: String = document
+            .select(&select("title"))
+            .flat_map(|x| x.text())
+            .collect();
+        assert_eq!(title, "name");
+        let author = document
+            .select(&select(r#"meta[name="author"]"#))
+            .next()
+            .and_then(|x| x.value().attr("content"));
+        assert_eq!(author, Some("author"));
+
+        let links: Vec<&str> = document
+            .select(&select("nav a"))
+            .filter_map(|x| x.value().attr("href"))
+            .collect();
+        assert_eq!(links.len(), 21);
+        assert_eq!(links[0], "#ch00001");
+        assert_eq!(links[1], "#ch00001_n");
+
+        let articles: Vec<_> = document.select(&select("article")).collect();
+        assert_eq!(articles.len(), 21);
+        assert_eq!(articles[0].value().id(), Some("ch00001"));
+        let h2: String = articles[0]
+            .select(&select("h2"))
+            .flat_map(|x| x.text())
+            .collect();
+        assert_eq!(h2, "title_00001");
+        let last: Vec<String> = articles[20]
+            .select(&select("p"))
+            .map(|x| x.text().collect())
+            .collect();
+        assert_eq!(last, ["c & d", "e"]);
+        let h2: String = articles[20]
+            .select(&select("h2"))
+            .flat_map(|x| x.text())
+            .collect();
+        assert_eq!(h2, "a < b");
+
+        dir.close().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_download_novel_output_json() {
+        // Request a new server from the pool
+        let mut server = mockito::Server::new_async().await;
+        let _mock = mock_any_get(&mut server).await;
+
+        // Use one of these addresses to configure your client
+        let url = server.url();
+
+        let fake = FakeNoveler::new(url.clone());
+        let dir = TempDir::new("noveler_test_download_novel_output_json").unwrap();
+        let path = dir.path();
+        let output_json = path.join("report.json");
+        let options = DownloadOptions {
+            output_json: Some(output_json.clone()),
+            ..DownloadOptions::default()
+        };
+        download_novel(
+            Arc::new(fake),
+            url.as_str(),
+            path,
+            &options,
+            Client::new(),
+            Duration::from_secs(10),
+        )
+        .await
+        .unwrap();
+
+        let report: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(output_json).unwrap()).unwrap();
+        assert_eq!(report["name"], "name");
+        assert_eq!(report["author"], "author");
+        assert_eq!(report["chapter_count"], 20);
+        assert_eq!(report["chapters"][0]["order"], "00001");
+        assert_eq!(report["chapters"][0]["word_count"], 18);
+        // "text_process_00001" and "text_process_00001_n"
+        assert_eq!(report["word_count"], 10 * 18 + 10 * 20);
+        assert_eq!(report["stats"]["queued"], 10);
+        assert_eq!(report["stats"]["failed"], 0);
+        assert_eq!(report["stats"]["interrupted"], false);
+        assert!(report["stats"]["elapsed_secs"].is_f64());
+
+        dir.close().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_combine_txt_volumes() {
+        // Request a new server from the pool
+        let mut server = mockito::Server::new_async().await;
+        let _mock = mock_any_get(&mut server).await;
+
+        // Use one of these addresses to configure your client
+        let url = server.url();
+
+        let fake = FakeNoveler::new(url.clone());
+        let dir = TempDir::new("noveler_test_combine_txt_volumes").unwrap();
+        let path = dir.path();
+        let (chapter_dir, _) = download_novel(
+            Arc::new(fake),
+            url.as_str(),
+            path,
+            &DownloadOptions::default(),
+            Client::new(),
+            Duration::from_secs(10),
+        )
+        .await
+        .unwrap();
+
+        let save_paths =
+            combine_txt_volumes(&chapter_dir, NonZeroUsize::new(4).unwrap(), LineEnding::Lf)
+                .unwrap();
+        assert_eq!(
+            save_paths,
+            [
+                path.join("temp/FakeNoveler/author_name.vol01.txt"),
+                path.join("temp/FakeNoveler/author_name.vol02.txt"),
+                path.join("temp/FakeNoveler/author_name.vol03.txt"),
+            ]
+        );
+
+        // every chapter comes with its next page
+        let volume = fs::read_to_string(&save_paths[0]).unwrap();
+        assert!(volume.starts_with("title_00001\n\ntext_process_00001\n\ntitle_00001_n\n"));
+        assert!(volume.ends_with("title_00004_n\n\ntext_process_00004_n\n\n"));
+        assert_eq!(volume.matches("title_").count(), 8);
+        let volume = fs::read_to_string(&save_paths[2]).unwrap();
+        assert!(volume.starts_with("title_00009\n"));
+        assert_eq!(volume.matches("title_").count(), 4);
+
+        // one volume is the same as combine_txt
+        combine_txt(&chapter_dir, LineEnding::Lf).unwrap();
+        let save_paths = combine_txt_volumes(
+            &chapter_dir,
+            NonZeroUsize::new(100).unwrap(),
+            LineEnding::Lf,
+        )
+        .unwrap();
+        assert_eq!(
+            fs::read_to_string(&save_paths[0]).unwrap(),
+            fs::read_to_string(path.join("temp/FakeNoveler/author_name.txt")).unwrap()
+        );
+
+        dir.close().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_combine_markdown() {
+        // Request a new server from the pool
+        let mut server = mockito::Server::new_async().await;
+        let _mock = mock_any_get(&mut server).await;
+
+        // Use one of these addresses to configure your client
+        let url = server.url();
+
+        let fake = FakeNoveler::new(url.clone());
+        let dir = TempDir::new("noveler_test_combine_markdown").unwrap();
+        let path = dir.path();
+        let (chapter_dir, _) = download_novel(
+            Arc::new(fake),
+            url.as_str(),
+            path,
+            &DownloadOptions::default(),
+            Client::new(),
+            Duration::from_secs(10),
+        )
+        .await
+        .unwrap();
+        fs::write(chapter_dir.join("00011.txt"), "title\n\na: b\n\n\nc").unwrap();
+
+        let save_path = combine_markdown(&chapter_dir).unwrap();
+        assert_eq!(save_path, path.join("temp/FakeNoveler/author_name.md"));
+        let markdown = fs::read_to_string(save_path).unwrap();
+        assert!(markdown.starts_with("---\ntitle: \"name\"\nauthor: \"author\"\n---\n\n## "));
+        assert_eq!(markdown.matches("\n## ").count(), 21);
+        assert!(markdown.ends_with("## title\n\na: b\n\nc\n\n"));
+
+        // without the book info there is no front matter
+        fs::remove_file(chapter_dir.join(METADATA_FILE)).unwrap();
+        fs::remove_file(chapter_dir.join(BOOK_INFO_FILE)).unwrap();
+        let markdown = fs::read_to_string(combine_markdown(&chapter_dir).unwrap()).unwrap();
+        assert!(markdown.starts_with("## "));
+
+        dir.close().unwrap();
+    }
+    #[test]
+    fn test_cleanup_temp_dir() {
+        let dir = TempDir::new("noveler_test_cleanup_temp_dir").unwrap();
+        let temp = dir.path().join("temp");
+        let book = temp.join("site").join("author_name");
+        let other = temp.join("site").join("author_other");
+        fs::create_dir_all(&book).unwrap();
+        fs::create_dir_all(&other).unwrap();
+        fs::write(book.join("00001.txt"), "text").unwrap();
+
+        cleanup_temp_dir(&book).unwrap();
+        assert!(!book.exists());
+        assert!(other.exists());
+
+        cleanup_temp_dir(&other).unwrap();
+        assert!(!temp.exists());
+        assert!(dir.path().exists());
+
+        dir.close().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_download_novel_with_range() {
+        // Request a new server from the pool
+        let mut server = mockito::Server::new_async().await;
+        let _mock = mock_any_get(&mut server).await;
+
+        // Use one of these addresses to configure your client
+        let url = server.url();
+
+        // no next page, only the chapters from the contents
+        let fake = FakeNoveler {
+            num: AtomicI32::new(11),
+            ..FakeNoveler::new(url.clone())
+        };
+        let dir = TempDir::new("noveler_test_download_novel_with_range").unwrap();
+        let path = dir.path();
+        let options = DownloadOptions {
+            concurrency: Some(ConcurrencyPolicy::Fixed(5)),
+            start_index: Some(3),
+            end_index: Some(5),
+            ..DownloadOptions::default()
+        };
+        let (chapter_dir, _) = download_novel(
+            Arc::new(fake),
+            url.as_str(),
+            path,
+            &options,
+            Client::new(),
+            Duration::from_secs(10),
+        )
+        .await
+        .unwrap();
+
+        let mut files: Vec<String> = fs::read_dir(&chapter_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+            .map(|path| path.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        files.sort_unstable();
+        assert_eq!(files, ["00003.txt", "00004.txt", "00005.txt"]);
+
+        dir.close().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_download_novel_with_delay() {
+        // Request a new server from the pool
+        let mut server = mockito::Server::new_async().await;
+        let _mock = mock_any_get(&mut server).await;
+
+        // Use one of these addresses to configure your client
+        let url = server.url();
+
+        // no next page, only the chapters from the contents
+        let fake = FakeNoveler {
+            num: AtomicI32::new(11),
+            ..FakeNoveler::new(url.clone())
+        };
+        let dir = TempDir::new("noveler_test_download_novel_with_delay").unwrap();
+        let path = dir.path();
+        let options = DownloadOptions {
+            concurrency: Some(ConcurrencyPolicy::Fixed(1)),
+            delay: Duration::from_millis(50),
+            end_index: Some(4),
+            ..DownloadOptions::default()
+        };
+
+        let start = std::time::Instant::now();
+        download_novel(
+            Arc::new(fake),
+            url.as_str(),
+            path,
+            &options,
+            Client::new(),
+            Duration::from_secs(10),
+        )
+        .await
+        .unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(50 * 4));
+
+        dir.close().unwrap();
+    }
+
+    /// resolves once the chapters are being downloaded
+    async fn ctrl_c_after_start(path: &Path) {
+        let state = path.join("temp/FakeNoveler/author_name").join(STATE_FILE);
+        while !state.exists() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
     #[tokio::test]
-    async fn test_check_coding() {
-        let client = reqwest::Client::new();
-        let document = get_html_and_fix_encoding(
-            client,
-            "https://www.qbtr.cc/tongren/3655.html",
-            Some(encoding_rs::GBK),
+    async fn test_download_novel_interrupted() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = mock_any_get(&mut server).await;
+
+        // Use one of these addresses to configure your client
+        let url = server.url();
+
+        let dir = TempDir::new("noveler_test_download_novel_interrupted").unwrap();
+        let path = dir.path();
+        let options = DownloadOptions {
+            delay: Duration::from_millis(300),
+            ..DownloadOptions::default()
+        };
+        // Ctrl-C while the first chapters are waiting for their delay
+        let (chapter_dir, stats) = download_novel_until(
+            Arc::new(FakeNoveler::new(url.clone())),
+            url.as_str(),
+            path,
+            &options,
+            Client::new(),
+            ctrl_c_after_start(path),
+            Duration::from_secs(10),
         )
         .await
         .unwrap();
-        dbg!(document);
+
+        assert!(stats.interrupted);
+        // the chapters in progress are all saved, no next page is started
+        let files = chapter_files(&chapter_dir).unwrap();
+        assert_eq!(files.len(), 10);
+        assert!(verify_chapters(&chapter_dir).unwrap().is_empty());
+        assert!(!chapter_dir.join("00001_n.txt").exists());
+        assert!(chapter_dir.join(STATE_FILE).is_file());
+        let pages = PageLog::new(chapter_dir.join(PAGES_FILE));
+        assert_eq!(pages.read().await.unwrap().len(), 10);
+
+        dir.close().unwrap();
     }
 
-    struct FakeNoveler {
-        re: Regex,
-        host: String,
-        num: AtomicI32,
+    #[tokio::test]
+    async fn test_download_novel_shutdown_timeout() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = mock_any_get(&mut server).await;
+
+        // Use one of these addresses to configure your client
+        let url = server.url();
+
+        let dir = TempDir::new("noveler_test_download_novel_shutdown_timeout").unwrap();
+        let path = dir.path();
+        let options = DownloadOptions {
+            delay: Duration::from_secs(10),
+            ..DownloadOptions::default()
+        };
+        let started = Instant::now();
+        let (chapter_dir, stats) = download_novel_until(
+            Arc::new(FakeNoveler::new(url.clone())),
+            url.as_str(),
+            path,
+            &options,
+            Client::new(),
+            ctrl_c_after_start(path),
+            Duration::from_millis(100),
+        )
+        .await
+        .unwrap();
+
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(stats.interrupted);
+        assert!(chapter_files(&chapter_dir).unwrap().is_empty());
+
+        dir.close().unwrap();
     }
 
-    impl FakeNoveler {
-        fn new(host: String) -> Self {
-            Self {
-                re: Regex::new(r"text").expect("pattern"),
-                host,
-                num: AtomicI32::new(1),
-            }
+    #[test]
+    fn test_jitter_delay() {
+        let delay = Duration::from_secs(1);
+        assert_eq!(jitter_delay(delay, 0), delay);
+        for _ in 0..100 {
+            let jittered = jitter_delay(delay, 20);
+            assert!(jittered >= Duration::from_millis(800));
+            assert!(jittered <= Duration::from_millis(1200));
+        }
+        for _ in 0..100 {
+            assert!(jitter_delay(delay, 100) <= Duration::from_secs(2));
         }
     }
 
-    impl Display for FakeNoveler {
-        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-            write!(f, "FakeNoveler")
-        }
+    #[tokio::test]
+    async fn test_download_novel_with_filter() {
+        // Request a new server from the pool
+        let mut server = mockito::Server::new_async().await;
+        let _mock = mock_any_get(&mut server).await;
+
+        // Use one of these addresses to configure your client
+        let url = server.url();
+
+        let dir = TempDir::new("noveler_test_download_novel_with_filter").unwrap();
+        let path = dir.path();
+
+        let options = DownloadOptions {
+            novel_filter: Some(Regex::new("other").unwrap()),
+            ..DownloadOptions::default()
+        };
+        let fake = FakeNoveler::new(url.clone());
+        let result = download_novel(
+            Arc::new(fake),
+            url.as_str(),
+            path,
+            &options,
+            Client::new(),
+            Duration::from_secs(10),
+        )
+        .await;
+        assert!(matches!(result, Err(NovelError::Skipped(book)) if book == "author_name"));
+        assert!(!path.join("temp/FakeNoveler/author_name").exists());
+
+        let options = DownloadOptions {
+            novel_filter: Some(Regex::new("^author_").unwrap()),
+            end_index: Some(1),
+            ..DownloadOptions::default()
+        };
+        let fake = FakeNoveler::new(url.clone());
+        download_novel(
+            Arc::new(fake),
+            url.as_str(),
+            path,
+            &options,
+            Client::new(),
+            Duration::from_secs(10),
+        )
+        .await
+        .unwrap();
+        assert!(path.join("temp/FakeNoveler/author_name/00001.txt").exists());
+
+        dir.close().unwrap();
     }
 
-    impl Noveler for FakeNoveler {
-        fn get_book_info(&self, _document: &Elements) -> Result<Book, NovelError> {
-            let name = "name".to_string();
-            let author = "author".to_string();
-            Ok(Book { name, author })
+    #[tokio::test]
+    async fn test_concurrency_limiter() {
+        let fixed = ConcurrencyLimiter::new(ConcurrencyPolicy::Fixed(2));
+        for _ in 0..ADAPTIVE_FAST_STREAK {
+            let permit = fixed.acquire().await;
+            fixed.release(permit, Duration::ZERO, false);
         }
+        assert_eq!(fixed.semaphore.available_permits(), 2);
 
-        fn get_chapter_urls_sorted(&self, _document: &Elements) -> Result<Vec<Url>, NovelError> {
-            Ok((1..)
-                .take(10)
-                .map(|n| Url::parse(&format!("{}/{}", &self.host, n)).unwrap())
-                .collect())
+        let adaptive = ConcurrencyLimiter::new(ConcurrencyPolicy::Adaptive { initial: 1, max: 2 });
+        assert_eq!(adaptive.semaphore.available_permits(), 1);
+        for _ in 0..ADAPTIVE_FAST_STREAK * 2 {
+            let permit = adaptive.acquire().await;
+            adaptive.release(permit, Duration::ZERO, false);
         }
-
-        fn get_chapter(&self, _document: &Elements, order: &str) -> Result<Chapter, NovelError> {
-            let title = format!("title_{order}");
-            let text = format!("text_{order}");
-            let order = order.to_string();
-            Ok(Chapter { order, title, text })
+        // no more than max
+        assert_eq!(adaptive.semaphore.available_permits(), 2);
+
+        let permit = adaptive.acquire().await;
+        adaptive.release(permit, ADAPTIVE_TARGET_LATENCY * 3, false);
+        assert_eq!(adaptive.semaphore.available_permits(), 1);
+
+        // never below one
+        let permit = adaptive.acquire().await;
+        adaptive.release(permit, Duration::ZERO, true);
+        assert_eq!(adaptive.semaphore.available_permits(), 1);
+
+        // a slow fetch breaks the streak
+        for n in 0..ADAPTIVE_FAST_STREAK {
+            let permit = adaptive.acquire().await;
+            let latency = if n == 5 {
+                ADAPTIVE_TARGET_LATENCY * 3 / 2
+            } else {
+                Duration::ZERO
+            };
+            adaptive.release(permit, latency, false);
         }
+        assert_eq!(adaptive.semaphore.available_permits(), 1);
+    }
 
-        fn get_next_page(&self, _document: &Elements) -> Result<Option<Url>, NovelError> {
-            let num = self.num.fetch_add(1, Ordering::SeqCst);
+    #[test]
+    fn test_retry_policy_backoff() {
+        let retry = RetryPolicy {
+            max_attempts: 5,
+            base_delay: Duration::from_millis(100),
+        };
+        assert_eq!(retry.backoff(1), Duration::from_millis(100));
+        assert_eq!(retry.backoff(2), Duration::from_millis(200));
+        assert_eq!(retry.backoff(3), Duration::from_millis(400));
+    }
 
-            if num > 10 {
-                Ok(None)
-            } else {
-                let url = Url::parse(&format!("{}/next_page/{num}", &self.host))?;
-                Ok(Some(url))
-            }
+    #[tokio::test]
+    async fn test_download_novel_with_retry() {
+        for (max_attempts, downloaded) in [(2, false), (3, true)] {
+            // Request a new server from the pool
+            let mut server = mockito::Server::new_async().await;
+            let url = server.url();
+
+            // the first two fetches of chapter 1 fail
+            let _unavailable = server
+                .mock("GET", "/1")
+                .with_status(503)
+                .expect(2)
+                .create_async()
+                .await;
+            let _mock = mock_any_get(&mut server).await;
+
+            // no next page, only the chapters from the contents
+            let fake = FakeNoveler {
+                num: AtomicI32::new(11),
+                ..FakeNoveler::new(url.clone())
+            };
+            let dir = TempDir::new("noveler_test_download_novel_with_retry").unwrap();
+            let path = dir.path();
+            let options = DownloadOptions {
+                end_index: Some(1),
+                retry: RetryPolicy {
+                    max_attempts,
+                    base_delay: Duration::from_millis(1),
+                },
+                ..DownloadOptions::default()
+            };
+            let (chapter_dir, _) = download_novel(
+                Arc::new(fake),
+                url.as_str(),
+                path,
+                &options,
+                Client::new(),
+                Duration::from_secs(10),
+            )
+            .await
+            .unwrap();
+
+            assert_eq!(chapter_dir.join("00001.txt").exists(), downloaded);
+
+            dir.close().unwrap();
         }
+    }
 
-        fn process_chapter(&self, chapter: Chapter) -> Chapter {
-            Chapter {
-                text: self
-                    .re
-                    .replace_all(&chapter.text, "text_process")
-                    .to_string(),
-                ..chapter
-            }
+    #[test]
+    fn test_retry_after() {
+        let headers = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(RETRY_AFTER, value.parse().unwrap());
+            headers
+        };
+        assert_eq!(retry_after(&headers("120")), Some(Duration::from_mins(2)));
+        assert_eq!(
+            retry_after(&headers("Wed, 21 Oct 2015 07:28:00 GMT")),
+            Some(Duration::ZERO)
+        );
+        let later = httpdate::fmt_http_date(SystemTime::now() + Duration::from_mins(1));
+        let wait = retry_after(&headers(&later)).unwrap();
+        assert!(wait > Duration::from_secs(55) && wait <= Duration::from_mins(1));
+        assert_eq!(retry_after(&headers("soon")), None);
+        assert_eq!(retry_after(&HeaderMap::new()), None);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_response_too_many_requests() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = server
+            .mock("GET", "/")
+            .with_status(429)
+            .with_header("retry-after", "3")
+            .create_async()
+            .await;
+
+        let err = fetch_response(Client::new(), server.url())
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            NovelError::TooManyRequests(Some(wait)) if wait == Duration::from_secs(3)
+        ));
+        assert!(is_retryable(&err));
+    }
+
+    #[tokio::test]
+    async fn test_download_novel_too_many_requests() {
+        let mut server = mockito::Server::new_async().await;
+        let url = server.url();
+
+        // the first fetch of chapter 1 is rate limited
+        let limited = server
+            .mock("GET", "/1")
+            .with_status(429)
+            .with_header("retry-after", "1")
+            .expect(1)
+            .create_async()
+            .await;
+        let _mock = mock_any_get(&mut server).await;
+
+        // no next page, only the chapters from the contents
+        let fake = FakeNoveler {
+            num: AtomicI32::new(11),
+            ..FakeNoveler::new(url.clone())
+        };
+        let dir = TempDir::new("noveler_test_download_novel_too_many_requests").unwrap();
+        let path = dir.path();
+        let options = DownloadOptions {
+            end_index: Some(1),
+            retry: RetryPolicy {
+                max_attempts: 2,
+                base_delay: Duration::from_millis(1),
+            },
+            ..DownloadOptions::default()
+        };
+        let started = Instant::now();
+        let (chapter_dir, _) = download_novel(
+            Arc::new(fake),
+            url.as_str(),
+            path,
+            &options,
+            Client::new(),
+            Duration::from_secs(10),
+        )
+        .await
+        .unwrap();
+
+        // waited for `Retry-After` instead of the backoff
+        assert!(started.elapsed() >= Duration::from_secs(1));
+        limited.assert_async().await;
+        assert!(chapter_dir.join("00001.txt").exists());
+
+        dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_chapter_word_count() {
+        let chapter = Chapter {
+            order: "00001".to_string(),
+            title: "第一章 標題".to_string(),
+            text: "大漠孤烟直，\n黄河落日圆。\u{3000}abc 12".to_string(),
+        };
+        assert_eq!(chapter.word_count(), 17);
+        assert_eq!(count_words(" \n\t\u{3000}"), 0);
+    }
+
+    #[test]
+    fn test_text_is_short() {
+        assert!(text_is_short("", 1));
+        assert!(text_is_short("请稍后\n \n", 4));
+        assert!(!text_is_short("请稍后再试", 4));
+        assert!(!text_is_short("", 0));
+    }
+
+    #[tokio::test]
+    async fn test_download_novel_retry_short() {
+        for (retry_short, fetches) in [(false, 1), (true, 3)] {
+            let mut server = mockito::Server::new_async().await;
+            let url = server.url();
+
+            let chapter = server
+                .mock("GET", "/1")
+                .expect(fetches)
+                .create_async()
+                .await;
+            let _mock = mock_any_get(&mut server).await;
+
+            // no next page, only the chapters from the contents
+            let fake = FakeNoveler {
+                num: AtomicI32::new(11),
+                ..FakeNoveler::new(url.clone())
+            };
+            let dir = TempDir::new("noveler_test_download_novel_retry_short").unwrap();
+            let path = dir.path();
+            let options = DownloadOptions {
+                end_index: Some(1),
+                min_chars: 100,
+                retry_short,
+                retry: RetryPolicy {
+                    max_attempts: 3,
+                    base_delay: Duration::from_millis(1),
+                },
+                ..DownloadOptions::default()
+            };
+            let (chapter_dir, _) = download_novel(
+                Arc::new(fake),
+                url.as_str(),
+                path,
+                &options,
+                Client::new(),
+                Duration::from_secs(10),
+            )
+            .await
+            .unwrap();
+
+            // saved after the last attempt all the same
+            chapter.assert_async().await;
+            assert!(chapter_dir.join("00001.txt").exists());
+
+            dir.close().unwrap();
         }
     }
 
     #[tokio::test]
-    async fn test_process_url_contents() {
+    async fn test_download_novel_with_word_limit() {
         // Request a new server from the pool
-        let server = mockito::Server::new();
+        let mut server = mockito::Server::new_async().await;
+        let _mock = mock_any_get(&mut server).await;
 
         // Use one of these addresses to configure your client
         let url = server.url();
 
-        let fake = Arc::new(FakeNoveler::new(url));
-        let dir = TempDir::new("noveler_test_process_url_contents").unwrap();
+        let dir = TempDir::new("noveler_test_download_novel_with_word_limit").unwrap();
         let path = dir.path();
-        let (tx, _) = mpsc::channel::<(String, Url)>(5);
 
-        let contents: &str = include_str!(concat!(
-            env!("CARGO_MANIFEST_DIR"),
-            "/tests/hjwzw/contents.html"
-        ));
-        let document = visdom::Vis::load(contents).unwrap();
+        // "text_00001" is 10 chars, no next page
+        let fake = FakeNoveler {
+            num: AtomicI32::new(11),
+            word_limit: Some(9),
+            ..FakeNoveler::new(url.clone())
+        };
+        let options = DownloadOptions {
+            end_index: Some(2),
+            ..DownloadOptions::default()
+        };
+        let (chapter_dir, _) = download_novel(
+            Arc::new(fake),
+            url.as_str(),
+            path,
+            &options,
+            Client::new(),
+            Duration::from_secs(10),
+        )
+        .await
+        .unwrap();
 
-        let result = process_url_contents(&fake, &document, path, tx).unwrap();
-        assert_eq!(result, 10);
+        assert!(!chapter_dir.join("00001.txt").exists());
+        assert!(!chapter_dir.join("00002.txt").exists());
+        let failed: Vec<FailedChapter> =
+            serde_json::from_str(&fs::read_to_string(chapter_dir.join(FAILED_FILE)).unwrap())
+                .unwrap();
+        assert_eq!(failed.len(), 2);
+        assert!(failed
+            .iter()
+            .all(|chapter| chapter.last_error.contains(WORD_LIMIT_EXCEEDED)));
+
+        let fake = FakeNoveler {
+            num: AtomicI32::new(11),
+            word_limit: Some(10),
+            ..FakeNoveler::new(url.clone())
+        };
+        download_novel(
+            Arc::new(fake),
+            url.as_str(),
+            path,
+            &options,
+            Client::new(),
+            Duration::from_secs(10),
+        )
+        .await
+        .unwrap();
+        assert!(chapter_dir.join("00001.txt").exists());
+        assert!(chapter_dir.join("00002.txt").exists());
+
+        dir.close().unwrap();
     }
 
     #[tokio::test]
-    async fn test_process_save_task() {
-        let dir = TempDir::new("noveler_test_process_save_task").unwrap();
+    async fn test_download_novel_with_retry_failed() {
+        // Request a new server from the pool
+        let mut server = mockito::Server::new_async().await;
+        let url = server.url();
+
+        let unavailable = server
+            .mock("GET", "/2")
+            .with_status(503)
+            .expect(1)
+            .create_async()
+            .await;
+        let _mock = mock_any_get(&mut server).await;
+
+        let dir = TempDir::new("noveler_test_download_novel_with_retry_failed").unwrap();
         let path = dir.path();
+        let manifest = path.join("temp/FakeNoveler/author_name").join(FAILED_FILE);
+
+        // no next page, only the chapters from the contents
+        let fake = FakeNoveler {
+            num: AtomicI32::new(11),
+            ..FakeNoveler::new(url.clone())
+        };
+        let options = DownloadOptions {
+            end_index: Some(3),
+            retry: RetryPolicy {
+                max_attempts: 1,
+                base_delay: Duration::from_millis(1),
+            },
+            ..DownloadOptions::default()
+        };
+        let (chapter_dir, _) = download_novel(
+            Arc::new(fake),
+            url.as_str(),
+            path,
+            &options,
+            Client::new(),
+            Duration::from_secs(10),
+        )
+        .await
+        .unwrap();
+        unavailable.assert_async().await;
+
+        assert!(chapter_dir.join("00001.txt").exists());
+        assert!(!chapter_dir.join("00002.txt").exists());
+        assert!(chapter_dir.join("00003.txt").exists());
+        let failed: Vec<FailedChapter> =
+            serde_json::from_str(&fs::read_to_string(&manifest).unwrap()).unwrap();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].order, "00002");
+        assert_eq!(failed[0].url, Url::parse(&format!("{url}/2")).unwrap());
+
+        // only the failed chapter is fetched again
+        fs::remove_file(chapter_dir.join("00001.txt")).unwrap();
+        let fake = FakeNoveler {
+            num: AtomicI32::new(11),
+            ..FakeNoveler::new(url.clone())
+        };
+        let options = DownloadOptions {
+            retry_failed: true,
+            ..options
+        };
+        download_novel(
+            Arc::new(fake),
+            url.as_str(),
+            path,
+            &options,
+            Client::new(),
+            Duration::from_secs(10),
+        )
+        .await
+        .unwrap();
 
-        let (tx, _) = mpsc::channel::<(String, Url)>(5);
+        assert!(!chapter_dir.join("00001.txt").exists());
+        assert!(chapter_dir.join("00002.txt").exists());
+        assert!(!manifest.exists());
 
-        let chapter = Chapter {
-            order: "order".to_string(),
-            title: "title".to_string(),
-            text: "text".to_string(),
+        dir.close().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_download_novel_with_missing_chapter() {
+        let mut server = mockito::Server::new_async().await;
+        let url = server.url();
+
+        let missing = server
+            .mock("GET", "/2")
+            .with_status(404)
+            .expect(1)
+            .create_async()
+            .await;
+        let _mock = mock_any_get(&mut server).await;
+
+        let dir = TempDir::new("noveler_test_download_novel_with_missing_chapter").unwrap();
+        let path = dir.path();
+
+        // no next page, only the chapters from the contents
+        let fake = FakeNoveler {
+            num: AtomicI32::new(11),
+            ..FakeNoveler::new(url.clone())
         };
-        process_save_task(chapter.clone(), None, path, tx)
-            .await
-            .unwrap();
+        let options = DownloadOptions {
+            end_index: Some(3),
+            ..DownloadOptions::default()
+        };
+        let (chapter_dir, stats) = download_novel(
+            Arc::new(fake),
+            url.as_str(),
+            path,
+            &options,
+            Client::new(),
+            Duration::from_secs(10),
+        )
+        .await
+        .unwrap();
+        // not retried
+        missing.assert_async().await;
+        assert_eq!(stats.failed, 1);
+        assert!(!stats.interrupted);
+
+        assert!(chapter_dir.join("00001.txt").exists());
+        assert!(!chapter_dir.join("00002.txt").exists());
+        assert!(chapter_dir.join("00003.txt").exists());
+        let failed: Vec<FailedChapter> =
+            serde_json::from_str(&fs::read_to_string(chapter_dir.join(FAILED_FILE)).unwrap())
+                .unwrap();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].order, "00002");
+        assert!(chapter_dir.join(METADATA_FILE).exists());
+
+        dir.close().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_download_novel_redownload_corrupted() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = mock_any_get(&mut server).await;
+
+        // Use one of these addresses to configure your client
+        let url = server.url();
+
+        let dir = TempDir::new("noveler_test_download_novel_redownload_corrupted").unwrap();
+        let path = dir.path();
+        let (chapter_dir, _) = download_novel(
+            Arc::new(FakeNoveler::new(url.clone())),
+            url.as_str(),
+            path,
+            &DownloadOptions::default(),
+            Client::new(),
+            Duration::from_secs(10),
+        )
+        .await
+        .unwrap();
+
+        let chapter = chapter_dir.join("00002.txt");
+        let content = fs::read_to_string(&chapter).unwrap();
+        fs::write(&chapter, "corrupted").unwrap();
+        assert_eq!(
+            verify_chapters(&chapter_dir).unwrap(),
+            std::slice::from_ref(&chapter)
+        );
+
+        download_novel(
+            Arc::new(FakeNoveler::new(url.clone())),
+            url.as_str(),
+            path,
+            &DownloadOptions::default(),
+            Client::new(),
+            Duration::from_secs(10),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(fs::read_to_string(&chapter).unwrap(), content);
+        assert!(verify_chapters(&chapter_dir).unwrap().is_empty());
+
+        dir.close().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_download_novel_with_client() {
+        let mut server = mockito::Server::new_async().await;
+        // only the requests with the cookie are answered
+        let _mock = server
+            .mock("GET", mockito::Matcher::Any)
+            .match_header("cookie", "cf_clearance=abc123")
+            .create_async()
+            .await;
+
+        // Use one of these addresses to configure your client
+        let url = server.url();
+
+        let dir = TempDir::new("noveler_test_download_novel_with_client").unwrap();
+        let path = dir.path();
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            reqwest::header::COOKIE,
+            "cf_clearance=abc123".parse().unwrap(),
+        );
+        let (chapter_dir, _) = download_novel(
+            Arc::new(FakeNoveler::new(url.clone())),
+            url.as_str(),
+            path,
+            &DownloadOptions::default(),
+            build_client(None, Some(headers), None).unwrap(),
+            Duration::from_secs(10),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(chapter_files(&chapter_dir).unwrap().len(), 20);
+        let failed = FailedManifest::new(chapter_dir.join(FAILED_FILE));
+        assert_eq!(failed.len().await, 0);
+
+        dir.close().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_download_novel_default_user_agent() {
+        let mut server = mockito::Server::new_async().await;
+        // the contents and the chapters alike
+        let _mock = server
+            .mock("GET", mockito::Matcher::Any)
+            .match_header("user-agent", DEFAULT_USER_AGENT)
+            .create_async()
+            .await;
+
+        // Use one of these addresses to configure your client
+        let url = server.url();
+
+        let dir = TempDir::new("noveler_test_download_novel_default_user_agent").unwrap();
+        let path = dir.path();
+        let (chapter_dir, _) = download_novel(
+            Arc::new(FakeNoveler::new(url.clone())),
+            url.as_str(),
+            path,
+            &DownloadOptions::default(),
+            build_client(None, None, None).unwrap(),
+            Duration::from_secs(10),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(chapter_files(&chapter_dir).unwrap().len(), 20);
+
+        dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_file_name() {
+        assert_eq!(file_name("00001", None), "00001.txt");
+        assert_eq!(file_name("00001", Some(" \n")), "00001.txt");
+        assert_eq!(
+            file_name("00001", Some("第一章 老地方")),
+            "00001_第一章老地方.txt"
+        );
+        assert_eq!(file_name("00002_n", Some("a/b")), "00002_n_a_b.txt");
 
-        let file_path = path.join(file_name(&chapter.order));
-        dbg!(&file_path);
-        assert!(file_path.is_file());
-        assert_eq!(fs::read_to_string(file_path).unwrap(), "title\n\ntext");
+        let long = file_name("00001", Some(&"說".repeat(100)));
+        assert!(long.len() <= MAX_PATH_COMPONENT_BYTES);
+        assert!(long.ends_with("說.txt"));
     }
 
-    #[tokio::test]
-    async fn test_basic_noveler() {
-        // Request a new server from the pool
-        let server = mockito::Server::new();
+    #[test]
+    fn test_chapter_order() {
+        let order = |name: &str| chapter_order(Path::new(name));
+        assert_eq!(order("00001.txt").as_deref(), Some("00001"));
+        assert_eq!(order("00001_n_n.txt").as_deref(), Some("00001_n_n"));
+        assert_eq!(order("00001_第一章老地方.txt").as_deref(), Some("00001"));
+        assert_eq!(order("00001_n_第一章_下.txt").as_deref(), Some("00001_n"));
+        assert_eq!(order("author_name.txt"), None);
+    }
 
-        // Use one of these addresses to configure your client
+    #[tokio::test]
+    async fn test_download_novel_next_page_chain() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = mock_any_get(&mut server).await;
         let url = server.url();
 
-        let fake = FakeNoveler::new(url.clone());
-        let dir = TempDir::new("noveler_test_basic_noveler").unwrap();
-        let path = dir.path();
-        let chapter_dir = download_novel(Arc::new(fake), url.as_str(), path, 5)
-            .await
-            .unwrap();
+        // chapter 1 has two next pages, `/next_page/9` and `/next_page/10`
+        let fake = FakeNoveler {
+            num: AtomicI32::new(9),
+            ..FakeNoveler::new(url.clone())
+        };
+        let dir = TempDir::new("noveler_test_download_novel_next_page_chain").unwrap();
+        let options = DownloadOptions {
+            end_index: Some(1),
+            ..DownloadOptions::default()
+        };
+        let (chapter_dir, _) = download_novel(
+            Arc::new(fake),
+            url.as_str(),
+            dir.path(),
+            &options,
+            Client::new(),
+            Duration::from_secs(10),
+        )
+        .await
+        .unwrap();
+        // a later chapter, by file name `00010` would come before `00001_n_n`
+        for order in ["00002", "00010"] {
+            let path = chapter_dir.join(format!("{order}.txt"));
+            let content = format!("title_{order}\n\ntext_process_{order}");
+            fs::write(&path, &content).unwrap();
+            fs::write(checksum_path(&path), sha256_hex(content.as_bytes())).unwrap();
+        }
 
-        assert!(path.join("temp/FakeNoveler/author_name/00001.txt").exists());
-        assert!(path
-            .join("temp/FakeNoveler/author_name/00001_n.txt")
-            .exists());
-        assert!(path.join("temp/FakeNoveler/author_name/00002.txt").exists());
-        assert!(path.join("temp/FakeNoveler/author_name/00003.txt").exists());
-        assert!(path.join("temp/FakeNoveler/author_name/00004.txt").exists());
-        assert!(path.join("temp/FakeNoveler/author_name/00005.txt").exists());
-        assert!(path.join("temp/FakeNoveler/author_name/00006.txt").exists());
-        assert!(path.join("temp/FakeNoveler/author_name/00007.txt").exists());
-        assert!(path.join("temp/FakeNoveler/author_name/00008.txt").exists());
-        assert!(path.join("temp/FakeNoveler/author_name/00009.txt").exists());
-        assert!(path.join("temp/FakeNoveler/author_name/00010.txt").exists());
+        combine_txt(&chapter_dir, LineEnding::Lf).unwrap();
+        let txt = fs::read_to_string(dir.path().join("temp/FakeNoveler/author_name.txt")).unwrap();
+        let titles: Vec<&str> = txt
+            .lines()
+            .filter(|line| line.starts_with("title_"))
+            .collect();
         assert_eq!(
-            tokio::fs::read_to_string(path.join("temp/FakeNoveler/author_name/00001.txt"))
-                .await
-                .unwrap(),
-            "title_00001\n\ntext_process_00001"
+            titles,
+            [
+                "title_00001",
+                "title_00001_n",
+                "title_00001_n_n",
+                "title_00002",
+                "title_00010"
+            ]
         );
 
-        combine_txt(&chapter_dir).unwrap();
-        assert_eq!(
-            tokio::fs::read_to_string(path.join("temp/FakeNoveler/author_name.txt"))
-                .await
-                .unwrap(),
-            r#"title_00001
-
-text_process_00001
-
-title_00001_n
+        dir.close().unwrap();
+    }
 
-text_process_00001_n
+    #[test]
+    fn test_combine_txt_numeric_order() {
+        let dir = TempDir::new("noveler_test_combine_txt_numeric_order").unwrap();
+        let book = dir.path().join("book");
+        fs::create_dir(&book).unwrap();
+        // by file name `100000.txt` would come first and `00042_n.txt` before `00042.txt`
+        let orders = [
+            "00042",
+            "00042_n",
+            "00042_n_n",
+            "00100",
+            "99999",
+            "99999_n",
+            "100000",
+            "100001",
+        ];
+        for order in orders.iter().rev() {
+            fs::write(book.join(format!("{order}.txt")), *order).unwrap();
+        }
 
-title_00002
+        let names: Vec<String> = chapter_files(&book)
+            .unwrap()
+            .iter()
+            .map(|path| path.file_stem().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names, orders);
 
-text_process_00002
+        combine_txt(&book, LineEnding::Lf).unwrap();
+        let txt = fs::read_to_string(dir.path().join("book.txt")).unwrap();
+        assert_eq!(txt, orders.map(|order| format!("{order}\n\n")).concat());
 
-title_00002_n
+        dir.close().unwrap();
+    }
 
-text_process_00002_n
+    #[test]
+    fn test_chapter_files_with_titles() {
+        let dir = TempDir::new("noveler_test_chapter_files_with_titles").unwrap();
+        let path = dir.path();
+        for name in [
+            "00002.txt",
+            "00001_n_第一章（下）.txt",
+            "00001_第一章.txt",
+            "00010_第十章.txt",
+        ] {
+            fs::write(path.join(name), "title\n\ntext").unwrap();
+        }
 
-title_00003
+        let names: Vec<String> = chapter_files(path)
+            .unwrap()
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "00001_第一章.txt",
+                "00001_n_第一章（下）.txt",
+                "00002.txt",
+                "00010_第十章.txt"
+            ]
+        );
 
-text_process_00003
+        dir.close().unwrap();
+    }
 
-title_00003_n
+    #[tokio::test]
+    async fn test_download_novel_respect_robots_txt() {
+        let mut server = mockito::Server::new_async().await;
+        let robots = server
+            .mock("GET", "/robots.txt")
+            .with_body("User-agent: *\nDisallow: /\n")
+            .create_async()
+            .await;
+        let url = server.url();
 
-text_process_00003_n
+        let dir = TempDir::new("noveler_test_download_novel_respect_robots_txt").unwrap();
+        let options = DownloadOptions {
+            respect_robots_txt: true,
+            ..DownloadOptions::default()
+        };
+        let result = download_novel(
+            Arc::new(FakeNoveler::new(url.clone())),
+            url.as_str(),
+            dir.path(),
+            &options,
+            Client::new(),
+            Duration::from_secs(10),
+        )
+        .await;
+        assert!(matches!(result, Err(NovelError::DisallowedByRobotsTxt(_))));
+        robots.assert_async().await;
+        // refused before the contents page is fetched
+        assert!(!dir.path().join("temp").exists());
 
-title_00004
+        dir.close().unwrap();
+    }
 
-text_process_00004
+    #[tokio::test]
+    async fn test_download_novel_chapter_title_in_filename() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = mock_any_get(&mut server).await;
+        let url = server.url();
 
-title_00004_n
+        let dir = TempDir::new("noveler_test_download_novel_chapter_title_in_filename").unwrap();
+        let path = dir.path();
+        let download = |options: DownloadOptions| {
+            let url = url.clone();
+            async move {
+                download_novel(
+                    Arc::new(FakeNoveler::new(url.clone())),
+                    url.as_str(),
+                    path,
+                    &options,
+                    Client::new(),
+                    Duration::from_secs(10),
+                )
+                .await
+                .unwrap()
+                .0
+            }
+        };
+        let options = DownloadOptions {
+            chapter_title_in_filename: true,
+            ..DownloadOptions::default()
+        };
+        let chapter_dir = download(options.clone()).await;
+        assert!(chapter_dir.join("00001_title_00001.txt").is_file());
+        assert!(chapter_dir.join("00001_n_title_00001_n.txt").is_file());
+        assert!(!chapter_dir.join("00001.txt").exists());
+        let files = chapter_files(&chapter_dir).unwrap();
+        assert!(verify_chapters(&chapter_dir).unwrap().is_empty());
+
+        // the titled files count as downloaded
+        let urls = remove_url_with_exist_file(
+            ["00001", "00001_n", "99999"]
+                .map(|order| (order.to_string(), Url::parse(&url).unwrap()))
+                .to_vec(),
+            &chapter_dir,
+        );
+        assert_eq!(urls.len(), 1);
+        assert_eq!(urls[0].0, "99999");
+
+        combine_txt(&chapter_dir, LineEnding::Lf).unwrap();
+        let txt = fs::read_to_string(path.join("temp/FakeNoveler/author_name.txt")).unwrap();
+        let first = txt.find("title_00001\n").unwrap();
+        let next_page = txt.find("title_00001_n\n").unwrap();
+        let second = txt.find("title_00002\n").unwrap();
+        assert!(first < next_page && next_page < second);
+
+        // downloaded again without the title, the titled copies are replaced
+        download(DownloadOptions {
+            force: true,
+            ..DownloadOptions::default()
+        })
+        .await;
+        assert!(chapter_dir.join("00001.txt").is_file());
+        assert!(!chapter_dir.join("00001_title_00001.txt").exists());
+        assert!(!chapter_dir.join("00001_title_00001.txt.sha256").exists());
+        assert_eq!(chapter_files(&chapter_dir).unwrap().len(), files.len());
 
-text_process_00004_n
+        dir.close().unwrap();
+    }
 
-title_00005
+    #[tokio::test]
+    async fn test_download_novel_book_info() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = mock_any_get(&mut server).await;
+        let url = server.url();
 
-text_process_00005
+        let dir = TempDir::new("noveler_test_download_novel_book_info").unwrap();
+        let path = dir.path();
+        let download = |options: DownloadOptions| {
+            let url = url.clone();
+            async move {
+                download_novel(
+                    Arc::new(FakeNoveler::new(url.clone())),
+                    url.as_str(),
+                    path,
+                    &options,
+                    Client::new(),
+                    Duration::from_secs(10),
+                )
+                .await
+                .unwrap()
+                .0
+            }
+        };
+        let chapter_dir = download(DownloadOptions::default()).await;
 
-title_00005_n
+        // the optional fields are left out when empty
+        let book_info = chapter_dir.join(BOOK_INFO_FILE);
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&book_info).unwrap()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"name": "name", "author": "author"})
+        );
 
-text_process_00005_n
+        // kept as it is, unless forced
+        let edited = r#"{"name":"name","author":"author","tags":["edited"]}"#;
+        fs::write(&book_info, edited).unwrap();
+        download(DownloadOptions::default()).await;
+        assert_eq!(fs::read_to_string(&book_info).unwrap(), edited);
+        download(DownloadOptions {
+            force: true,
+            ..DownloadOptions::default()
+        })
+        .await;
+        let book: Book = serde_json::from_str(&fs::read_to_string(&book_info).unwrap()).unwrap();
+        assert!(book.tags.is_empty());
 
-title_00006
+        dir.close().unwrap();
+    }
 
-text_process_00006
+    #[tokio::test]
+    async fn test_download_novel_force() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = mock_any_get(&mut server).await;
 
-title_00006_n
+        // Use one of these addresses to configure your client
+        let url = server.url();
 
-text_process_00006_n
+        let dir = TempDir::new("noveler_test_download_novel_force").unwrap();
+        let path = dir.path();
+        let (chapter_dir, _) = download_novel(
+            Arc::new(FakeNoveler::new(url.clone())),
+            url.as_str(),
+            path,
+            &DownloadOptions::default(),
+            Client::new(),
+            Duration::from_secs(10),
+        )
+        .await
+        .unwrap();
+        let files = chapter_files(&chapter_dir).unwrap();
+
+        // a valid file with outdated text
+        let chapter = chapter_dir.join("00002.txt");
+        let content = fs::read_to_string(&chapter).unwrap();
+        fs::write(&chapter, "outdated").unwrap();
+        fs::write(checksum_path(&chapter), sha256_hex(b"outdated") + "\n").unwrap();
+
+        download_novel(
+            Arc::new(FakeNoveler::new(url.clone())),
+            url.as_str(),
+            path,
+            &DownloadOptions::default(),
+            Client::new(),
+            Duration::from_secs(10),
+        )
+        .await
+        .unwrap();
+        assert_eq!(fs::read_to_string(&chapter).unwrap(), "outdated");
 
-title_00007
+        let options = DownloadOptions {
+            force: true,
+            dedup: true,
+            ..DownloadOptions::default()
+        };
+        download_novel(
+            Arc::new(FakeNoveler::new(url.clone())),
+            url.as_str(),
+            path,
+            &options,
+            Client::new(),
+            Duration::from_secs(10),
+        )
+        .await
+        .unwrap();
+        assert_eq!(fs::read_to_string(&chapter).unwrap(), content);
+        assert_eq!(chapter_files(&chapter_dir).unwrap(), files);
+        assert!(verify_chapters(&chapter_dir).unwrap().is_empty());
 
-text_process_00007
+        dir.close().unwrap();
+    }
 
-title_00007_n
+    #[tokio::test]
+    async fn test_write_atomic() {
+        let dir = TempDir::new("noveler_test_write_atomic").unwrap();
+        let path = dir.path().join("00001.txt");
+        let part = dir.path().join("00001.txt.part");
+
+        write_atomic(&path, "old").await.unwrap();
+        write_atomic(&path, "new").await.unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert!(!part.exists());
+
+        // the rename fails onto a dir, neither a partial file nor the `.part` is left
+        let blocked = dir.path().join("00002.txt");
+        fs::create_dir(&blocked).unwrap();
+        fs::write(blocked.join("keep"), "").unwrap();
+        assert!(write_atomic(&blocked, "text").await.is_err());
+        assert!(blocked.is_dir());
+        assert!(!dir.path().join("00002.txt.part").exists());
+
+        // the write itself fails
+        let missing = dir.path().join("missing/00003.txt");
+        assert!(write_atomic(&missing, "text").await.is_err());
+        assert!(!missing.exists());
 
-text_process_00007_n
+        dir.close().unwrap();
+    }
 
-title_00008
+    #[tokio::test]
+    async fn test_download_novel_partial_write() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = mock_any_get(&mut server).await;
 
-text_process_00008
+        // Use one of these addresses to configure your client
+        let url = server.url();
 
-title_00008_n
+        let dir = TempDir::new("noveler_test_download_novel_partial_write").unwrap();
+        let path = dir.path();
+        let (chapter_dir, _) = download_novel(
+            Arc::new(FakeNoveler::new(url.clone())),
+            url.as_str(),
+            path,
+            &DownloadOptions::default(),
+            Client::new(),
+            Duration::from_secs(10),
+        )
+        .await
+        .unwrap();
 
-text_process_00008_n
+        // interrupted while 00003.txt was being written
+        let chapter = chapter_dir.join("00003.txt");
+        let content = fs::read_to_string(&chapter).unwrap();
+        let part = chapter_dir.join("00003.txt.part");
+        fs::remove_file(checksum_path(&chapter)).unwrap();
+        fs::remove_file(&chapter).unwrap();
+        fs::write(&part, &content[..5]).unwrap();
+
+        download_novel(
+            Arc::new(FakeNoveler::new(url.clone())),
+            url.as_str(),
+            path,
+            &DownloadOptions::default(),
+            Client::new(),
+            Duration::from_secs(10),
+        )
+        .await
+        .unwrap();
 
-title_00009
+        assert_eq!(fs::read_to_string(&chapter).unwrap(), content);
+        assert!(chapter_file_is_valid(&chapter));
+        assert!(!part.exists());
+        let part_files = fs::read_dir(&chapter_dir)
+            .unwrap()
+            .filter(|entry| {
+                let path = entry.as_ref().unwrap().path();
+                path.extension().is_some_and(|ext| ext == PART_EXTENSION)
+            })
+            .count();
+        assert_eq!(part_files, 0);
 
-text_process_00009
+        dir.close().unwrap();
+    }
 
-title_00009_n
+    #[tokio::test]
+    async fn test_download_novel_resume_from_state() {
+        // Request a new server from the pool
+        let mut server = mockito::Server::new_async().await;
+        let url = server.url();
 
-text_process_00009_n
+        let contents = server.mock("GET", "/").expect(2).create_async().await;
+        let _chapters = server
+            .mock("GET", mockito::Matcher::Regex(r"^/\d+$".to_string()))
+            .create_async()
+            .await;
 
-title_00010
+        let dir = TempDir::new("noveler_test_download_novel_resume_from_state").unwrap();
+        let path = dir.path();
+        let options = DownloadOptions {
+            end_index: Some(3),
+            ..DownloadOptions::default()
+        };
+        let download = |options: DownloadOptions| {
+            // no next page, only the chapters from the contents
+            let fake = FakeNoveler {
+                num: AtomicI32::new(11),
+                ..FakeNoveler::new(url.clone())
+            };
+            let url = url.clone();
+            async move {
+                download_novel(
+                    Arc::new(fake),
+                    url.as_str(),
+                    path,
+                    &options,
+                    Client::new(),
+                    Duration::from_secs(10),
+                )
+                .await
+                .unwrap()
+                .0
+            }
+        };
 
-text_process_00010
+        let chapter_dir = download(options.clone()).await;
+        let state: DownloadState =
+            serde_json::from_str(&fs::read_to_string(chapter_dir.join(STATE_FILE)).unwrap())
+                .unwrap();
+        assert_eq!(state.url_contents, url);
+        assert_eq!(state.chapters.len(), 10);
 
-title_00010_n
+        // resume from state.json, without fetching the contents
+        fs::remove_file(chapter_dir.join("00002.txt")).unwrap();
+        download(options.clone()).await;
+        assert!(chapter_dir.join("00002.txt").exists());
 
-text_process_00010_n
+        // the contents is fetched again
+        download(DownloadOptions {
+            no_resume: true,
+            ..options
+        })
+        .await;
 
-"#
-        );
+        contents.assert_async().await;
 
         dir.close().unwrap();
     }
@@ -596,11 +5428,22 @@ text_process_00010_n
         let url = "https://www.novel543.com/0413188175/dir";
         let noveler = Novel543::new(url).expect("create Novel543 ok");
 
-        let chapter_dir = download_novel(Arc::new(noveler), url, path, 1)
-            .await
-            .expect("download ok");
+        let options = DownloadOptions {
+            concurrency: Some(ConcurrencyPolicy::Fixed(1)),
+            ..DownloadOptions::default()
+        };
+        let (chapter_dir, _) = download_novel(
+            Arc::new(noveler),
+            url,
+            path,
+            &options,
+            Client::new(),
+            Duration::from_secs(10),
+        )
+        .await
+        .expect("download ok");
 
-        combine_txt(&chapter_dir).expect("combine txt ok");
+        combine_txt(&chapter_dir, LineEnding::Lf).expect("combine txt ok");
 
         dir.close().unwrap();
     }
@@ -614,11 +5457,22 @@ text_process_00010_n
         let url = "https://tw.hjwzw.com/Book/Chapter/48386";
         let noveler = Hjwzw::new(url).expect("create Hjwzw ok");
 
-        let chapter_dir = download_novel(Arc::new(noveler), url, path, 10)
-            .await
-            .expect("download ok");
+        let options = DownloadOptions {
+            concurrency: Some(ConcurrencyPolicy::Fixed(10)),
+            ..DownloadOptions::default()
+        };
+        let (chapter_dir, _) = download_novel(
+            Arc::new(noveler),
+            url,
+            path,
+            &options,
+            Client::new(),
+            Duration::from_secs(10),
+        )
+        .await
+        .expect("download ok");
 
-        combine_txt(&chapter_dir).expect("combine txt ok");
+        combine_txt(&chapter_dir, LineEnding::Lf).expect("combine txt ok");
 
         dir.close().unwrap();
     }
@@ -632,11 +5486,22 @@ text_process_00010_n
         let url = "https://www.piaotia.com/html/14/14881/";
         let noveler = Piaotia::new(url).expect("create Piaotia ok");
 
-        let chapter_dir = download_novel(Arc::new(noveler), url, path, 10)
-            .await
-            .expect("download ok");
+        let options = DownloadOptions {
+            concurrency: Some(ConcurrencyPolicy::Fixed(10)),
+            ..DownloadOptions::default()
+        };
+        let (chapter_dir, _) = download_novel(
+            Arc::new(noveler),
+            url,
+            path,
+            &options,
+            Client::new(),
+            Duration::from_secs(10),
+        )
+        .await
+        .expect("download ok");
 
-        combine_txt(&chapter_dir).expect("combine txt ok");
+        combine_txt(&chapter_dir, LineEnding::Lf).expect("combine txt ok");
 
         dir.close().unwrap();
     }
@@ -650,15 +5515,231 @@ text_process_00010_n
         let url = "https://tw.uukanshu.com/b/239329/";
         let noveler: UUkanshu = UUkanshu::new(url).expect("create UUkanshu ok");
 
-        let chapter_dir = download_novel(Arc::new(noveler), url, path, 10)
+        let options = DownloadOptions {
+            concurrency: Some(ConcurrencyPolicy::Fixed(10)),
+            ..DownloadOptions::default()
+        };
+        let (chapter_dir, _) = download_novel(
+            Arc::new(noveler),
+            url,
+            path,
+            &options,
+            Client::new(),
+            Duration::from_secs(10),
+        )
+        .await
+        .expect("download ok");
+
+        combine_txt(&chapter_dir, LineEnding::Lf).expect("combine txt ok");
+
+        dir.close().unwrap();
+    }
+
+    /// save the contents page of `url` and its first chapter under `tests/<site>/` as served
+    async fn record_fixtures(noveler: impl Noveler, url: &str, site: &str, chapter_file: &str) {
+        async fn save(client: &Client, url: Url, path: PathBuf) {
+            let response = client.get(url).send().await.unwrap();
+            let bytes = response.error_for_status().unwrap().bytes().await.unwrap();
+            fs::write(path, bytes).unwrap();
+        }
+
+        let client = Client::new();
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join(site);
+        save(&client, Url::parse(url).unwrap(), dir.join("contents.html")).await;
+        let chapters = fetch_chapter_list(&noveler, url, client.clone(), None)
             .await
-            .expect("download ok");
+            .expect("chapter list ok");
+        let (_, chapter_url) = chapters.into_iter().next().expect("a chapter");
+        save(&client, chapter_url, dir.join(chapter_file)).await;
+    }
+
+    #[ignore = "only for recording the fixtures, the tests must be updated after"]
+    #[tokio::test]
+    async fn test_record_fixtures() {
+        let url = "https://www.biqubao.com/book/410/";
+        record_fixtures(Biquge::new(url).unwrap(), url, "biquge", "chapter.html").await;
+        let url = "https://www.123du.cc/dudu-31/1181731/";
+        record_fixtures(Du123::new(url).unwrap(), url, "du123", "chapter.html").await;
+        let url = "https://www.dxmwx.org/chapter/1573.html";
+        record_fixtures(Dxmwx::new(url).unwrap(), url, "dxmwx", "chapter.html").await;
+        let url = "https://www.jjwxc.net/onebook.php?novelid=2456159";
+        record_fixtures(
+            JjwxcApi::new(url, None).unwrap(),
+            url,
+            "jjwxc_api",
+            "content.json",
+        )
+        .await;
+        let url = "https://www.piaotian.net/html/14/14881/";
+        record_fixtures(
+            PiaotianNet::new(url).unwrap(),
+            url,
+            "piaotian_net",
+            "chapter.html",
+        )
+        .await;
+        let url = "https://book.qidian.com/info/1035420986/";
+        record_fixtures(Qidian::new(url).unwrap(), url, "qidian", "chapter.html").await;
+        let url = "https://www.qimao.com/shuku/1846389/";
+        record_fixtures(Qimao::new(url).unwrap(), url, "qimao", "content.json").await;
+        let url = "https://www.readm.org/light-novel/the-beginning-after-the-end";
+        record_fixtures(Readm::new(url).unwrap(), url, "readm", "chapter.html").await;
+        let url = "https://www.shuhai.com/book/42851.htm";
+        record_fixtures(Shuhai::new(url).unwrap(), url, "shuhai", "chapter.html").await;
+        let url = "https://www.tadu.com/book/892406/";
+        record_fixtures(Tadu::new(url, None).unwrap(), url, "tadu", "content.json").await;
+        let url = "https://www.wfxs.cc/book/2716/";
+        record_fixtures(Wfxs::new(url).unwrap(), url, "wfxs", "chapter.html").await;
+        let url = "https://www.wnmtl.org/book/4139-reincarnation-of-the-strongest-sword-god";
+        record_fixtures(Wnmtl::new(url).unwrap(), url, "wnmtl", "chapter.html").await;
+        let url = "https://www.xbiquge.so/book/53099/";
+        record_fixtures(
+            XbiqugeSo::new(url).unwrap(),
+            url,
+            "xbiquge_so",
+            "chapter.html",
+        )
+        .await;
+        let url = "https://www.yunqi.qq.com/books/25413.html";
+        record_fixtures(Yunqi::new(url).unwrap(), url, "yunqi", "content.json").await;
+        let url = "https://book.zongheng.com/showchapter/189169.html";
+        record_fixtures(Zongheng::new(url).unwrap(), url, "zongheng", "chapter.html").await;
+    }
 
-        combine_txt(&chapter_dir).expect("combine txt ok");
+    /// download the first chapters of `url` from the live site, the parsers are
+    /// only tested against trimmed pages otherwise
+    async fn download_online(noveler: impl Noveler, url: &str) {
+        let dir = TempDir::new("noveler_test_online").unwrap();
+        let options = DownloadOptions {
+            concurrency: Some(ConcurrencyPolicy::Fixed(1)),
+            start_index: Some(1),
+            end_index: Some(3),
+            ..DownloadOptions::default()
+        };
+        let (chapter_dir, _) = download_novel(
+            Arc::new(noveler),
+            url,
+            dir.path(),
+            &options,
+            Client::new(),
+            Duration::from_secs(10),
+        )
+        .await
+        .expect("download ok");
+
+        assert_eq!(chapter_files(&chapter_dir).unwrap().len(), 3);
+        combine_txt(&chapter_dir, LineEnding::Lf).expect("combine txt ok");
 
         dir.close().unwrap();
     }
 
+    #[ignore = "online test"]
+    #[tokio::test]
+    async fn test_biquge_online() {
+        let url = "https://www.biqubao.com/book/410/";
+        download_online(Biquge::new(url).expect("create Biquge ok"), url).await;
+    }
+
+    #[ignore = "online test"]
+    #[tokio::test]
+    async fn test_du123_online() {
+        let url = "https://www.123du.cc/dudu-31/1181731/";
+        download_online(Du123::new(url).expect("create Du123 ok"), url).await;
+    }
+
+    #[ignore = "online test"]
+    #[tokio::test]
+    async fn test_dxmwx_online() {
+        let url = "https://www.dxmwx.org/chapter/1573.html";
+        download_online(Dxmwx::new(url).expect("create Dxmwx ok"), url).await;
+    }
+
+    #[ignore = "online test"]
+    #[tokio::test]
+    async fn test_jjwxc_online() {
+        let url = "https://www.jjwxc.net/onebook.php?novelid=2456159";
+        download_online(JjwxcApi::new(url, None).expect("create JjwxcApi ok"), url).await;
+    }
+
+    #[ignore = "online test"]
+    #[tokio::test]
+    async fn test_piaotian_net_online() {
+        let url = "https://www.piaotian.net/html/14/14881/";
+        download_online(PiaotianNet::new(url).expect("create PiaotianNet ok"), url).await;
+    }
+
+    #[ignore = "online test"]
+    #[tokio::test]
+    async fn test_qidian_online() {
+        let url = "https://book.qidian.com/info/1035420986/";
+        download_online(Qidian::new(url).expect("create Qidian ok"), url).await;
+    }
+
+    #[ignore = "online test"]
+    #[tokio::test]
+    async fn test_qimao_online() {
+        let url = "https://www.qimao.com/shuku/1846389/";
+        download_online(Qimao::new(url).expect("create Qimao ok"), url).await;
+    }
+
+    #[ignore = "online test"]
+    #[tokio::test]
+    async fn test_readm_online() {
+        let url = "https://www.readm.org/light-novel/the-beginning-after-the-end";
+        download_online(Readm::new(url).expect("create Readm ok"), url).await;
+    }
+
+    #[ignore = "online test"]
+    #[tokio::test]
+    async fn test_shuhai_online() {
+        let url = "https://www.shuhai.com/book/42851.htm";
+        download_online(Shuhai::new(url).expect("create Shuhai ok"), url).await;
+    }
+
+    #[ignore = "online test"]
+    #[tokio::test]
+    async fn test_tadu_online() {
+        let url = "https://www.tadu.com/book/892406/";
+        download_online(Tadu::new(url, None).expect("create Tadu ok"), url).await;
+    }
+
+    #[ignore = "online test"]
+    #[tokio::test]
+    async fn test_wfxs_online() {
+        let url = "https://www.wfxs.cc/book/2716/";
+        download_online(Wfxs::new(url).expect("create Wfxs ok"), url).await;
+    }
+
+    #[ignore = "online test"]
+    #[tokio::test]
+    async fn test_wnmtl_online() {
+        let url = "https://www.wnmtl.org/book/4139-reincarnation-of-the-strongest-sword-god";
+        download_online(Wnmtl::new(url).expect("create Wnmtl ok"), url).await;
+    }
+
+    #[ignore = "online test"]
+    #[tokio::test]
+    async fn test_xbiquge_so_online() {
+        let url = "https://www.xbiquge.so/book/53099/";
+        download_online(XbiqugeSo::new(url).expect("create XbiqugeSo ok"), url).await;
+    }
+
+    #[ignore = "online test"]
+    #[tokio::test]
+    async fn test_yunqi_online() {
+        let url = "https://www.yunqi.qq.com/books/25413.html";
+        download_online(Yunqi::new(url).expect("create Yunqi ok"), url).await;
+    }
+
+    #[ignore = "online test"]
+    #[tokio::test]
+    async fn test_zongheng_online() {
+        let url = "https://book.zongheng.com/showchapter/189169.html";
+        download_online(Zongheng::new(url).expect("create Zongheng ok"), url).await;
+    }
+
     #[ignore = "only for compare"]
     #[test]
     fn test_compare_parser() {
@@ -0,0 +1,223 @@
+//! 笔趣阁 <https://www.biquge.co/>
+//!
+//! the mirrors, e.g. <https://www.biquge.info/> and <https://www.biqubao.com/>, share the layout
+use super::{Book, Chapter, NovelError, Noveler, TextProcessor};
+use std::fmt::{self, Display};
+use url::Url;
+use visdom::types::Elements;
+
+pub(crate) struct Biquge {
+    base: Url,
+    processor: TextProcessor,
+}
+
+impl Biquge {
+    pub(crate) fn new(url: &str) -> Result<Self, NovelError> {
+        let mut base = Url::parse(url)?;
+
+        match base.path_segments_mut() {
+            Ok(mut path) => {
+                path.clear();
+            }
+            Err(()) => {
+                return Err(NovelError::CannotBeABase(url.to_string()));
+            }
+        }
+        base.set_query(None);
+
+        let processor = TextProcessor::new()
+            .add_regex_replacement(
+                r"(?m)^.*(?:biquge\.(?:co|info)|biqubao\.com|笔趣阁|天才一秒记住本站地址).*$",
+                "",
+            )?
+            // the navigation links copied into the text
+            .add_strip_navigation_text(&["上一章", "下一章", "章节目录", "加入书签"])
+            .add_regex_replacement(r"readx\(\);", "")?
+            .add_line_split_and_trim(&['\n', '\u{a0}', '\u{3000}', '\r'])
+            .add_filter_empty_lines();
+
+        Ok(Self { base, processor })
+    }
+}
+
+impl Display for Biquge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "笔趣阁")
+    }
+}
+
+impl Noveler for Biquge {
+    fn base_url(&self) -> Option<&Url> {
+        Some(&self.base)
+    }
+
+    fn need_encoding(&self) -> Option<&'static encoding_rs::Encoding> {
+        Some(encoding_rs::GBK)
+    }
+
+    fn auto_detect_encoding(&self) -> bool {
+        true
+    }
+
+    fn get_book_info(&self, document: &Elements) -> Result<Book, NovelError> {
+        let selector = r"div#info h1";
+        let name = document.find(selector).text().trim().to_string();
+
+        let selector = r"div#info p:first-of-type";
+        let author = document.find(selector).text();
+        let author = author
+            .split_once('：')
+            .map(|(_, author)| author.trim().to_string())
+            .ok_or(NovelError::NotFound("author".to_string()))?;
+        Ok(Book {
+            name,
+            author,
+            ..Book::default()
+        })
+    }
+
+    fn get_synopsis(&self, document: &Elements) -> Option<String> {
+        let selector = r"div#intro";
+        let synopsis = document.find(selector).text().trim().to_string();
+        (!synopsis.is_empty()).then_some(synopsis)
+    }
+
+    fn get_chapter_urls_sorted(&self, document: &Elements) -> Result<Vec<Url>, NovelError> {
+        // the latest chapters are listed again before the last dt
+        let selector = r"div#list dl dt:last-of-type ~ dd a";
+        document
+            .find(selector)
+            .into_iter()
+            .map(|x| {
+                x.get_attribute("href")
+                    .map(|attr| attr.to_string())
+                    .ok_or(NovelError::NotFound("href".to_string()))
+            })
+            .map(|x| x.and_then(|url_str| self.base.join(&url_str).map_err(NovelError::ParseError)))
+            .collect()
+    }
+
+    fn get_chapter(&self, document: &Elements, order: &str) -> Result<Chapter, NovelError> {
+        let selector = r"div.bookname h1";
+        let title = document.find(selector).text().trim().to_string();
+
+        let doc = document.cloned();
+        doc.find("div#content script").remove();
+        let selector = r"div#content";
+        let text: String = doc.find(selector).text();
+
+        let order = order.to_string();
+        Ok(Chapter { order, title, text })
+    }
+
+    fn get_next_page(&self, _document: &Elements) -> Result<Option<Url>, NovelError> {
+        Ok(None)
+    }
+
+    fn process_chapter_text(&self, text: &str) -> String {
+        self.processor.process(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static CONTENTS: &[u8] = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/biquge/contents.html"
+    ));
+    static CHAPTER: &[u8] = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/biquge/chapter.html"
+    ));
+
+    #[test]
+    fn test_new_mirror() {
+        let novel = Biquge::new("https://www.biqubao.com/book/410/").unwrap();
+        assert_eq!(
+            novel.base_url(),
+            Some(&Url::parse("https://www.biqubao.com/").unwrap())
+        );
+
+        let html = CONTENTS;
+        let (html, _, _) = novel.need_encoding().unwrap().decode(html);
+        let document = visdom::Vis::load(html).unwrap();
+        let urls = novel.get_chapter_urls_sorted(&document).unwrap();
+        assert_eq!(
+            urls.first().unwrap(),
+            &Url::parse("https://www.biqubao.com/0_410/1.html").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_get_book_info() {
+        let novel = Biquge::new("https://www.biquge.co/0_410/").unwrap();
+        let (html, _, _) = novel.need_encoding().unwrap().decode(CONTENTS);
+        let document = visdom::Vis::load(html).unwrap();
+        let book = novel.get_book_info(&document).unwrap();
+        assert_eq!(
+            book,
+            Book {
+                name: "圣墟".to_string(),
+                author: "辰东".to_string(),
+                ..Book::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_get_book_details() {
+        let novel = Biquge::new("https://www.biquge.co/0_410/").unwrap();
+        let (html, _, _) = novel.need_encoding().unwrap().decode(CONTENTS);
+        let document = visdom::Vis::load(html).unwrap();
+        let book = novel.get_book(&document).unwrap();
+        assert_eq!(
+            book.synopsis.as_deref(),
+            Some("在破败中崛起，在寂灭中复苏。")
+        );
+    }
+
+    #[test]
+    fn test_get_chapter_urls_sorted() {
+        let novel = Biquge::new("https://www.biquge.co/0_410/").unwrap();
+        let (html, _, _) = novel.need_encoding().unwrap().decode(CONTENTS);
+        let document = visdom::Vis::load(html).unwrap();
+        let urls = novel.get_chapter_urls_sorted(&document).unwrap();
+        assert_eq!(urls.len(), 3);
+        assert_eq!(
+            urls.first().unwrap(),
+            &Url::parse("https://www.biquge.co/0_410/1.html").unwrap()
+        );
+        assert_eq!(
+            urls.last().unwrap(),
+            &Url::parse("https://www.biquge.co/0_410/3.html").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_get_chapter_content() {
+        let novel = Biquge::new("https://www.biquge.co/0_410/").unwrap();
+        let (html, _, _) = novel.need_encoding().unwrap().decode(CHAPTER);
+        let document = visdom::Vis::load(html).unwrap();
+        let chapter = novel.get_chapter(&document, "1").unwrap();
+        assert_eq!(chapter.order, "1".to_string());
+        assert_eq!(chapter.title, "第一章 沙漠中的彼岸花".to_string());
+        let chapter = novel.process_chapter(chapter);
+        dbg!(&chapter.text);
+        assert!(chapter.text.starts_with("大漠孤烟直，黄河落日圆。"));
+        assert!(chapter.text.ends_with("天色渐暗，他看到了一朵血红色的花。"));
+        assert!(!chapter.text.contains("笔趣阁"));
+        assert!(!chapter.text.contains("天才一秒记住本站地址"));
+        assert!(!chapter.text.contains("下一章"));
+    }
+
+    #[test]
+    fn test_get_next_page() {
+        let novel = Biquge::new("https://www.biquge.co/0_410/").unwrap();
+        let (html, _, _) = novel.need_encoding().unwrap().decode(CHAPTER);
+        let document = visdom::Vis::load(html).unwrap();
+        let url = novel.get_next_page(&document).unwrap();
+        assert_eq!(url, None);
+    }
+}
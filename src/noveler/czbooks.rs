@@ -1,27 +1,29 @@
-/// 小說狂人 <https://czbooks.net/>
-use super::{Book, Chapter, NovelError, Noveler};
+//! 小說狂人 <https://czbooks.net/>
+use super::{Book, Chapter, NovelError, Noveler, TextProcessor};
 use aho_corasick::AhoCorasick;
 use std::fmt::{self, Display};
 use url::Url;
 use visdom::types::Elements;
 
+const BASE: &str = "https://czbooks.net/";
+
 pub(crate) struct Czbooks {
-    replacer: (AhoCorasick, Vec<String>),
+    processor: TextProcessor,
 }
 
 impl Czbooks {
     pub(crate) fn new() -> Result<Self, NovelError> {
         let patterns = ["\u{3000}", "\n\n"];
-        let replace_with = ["", "\n"]
+        let replace_with: Vec<String> = ["", "\n"]
             .into_iter()
             .map(std::string::ToString::to_string)
             .collect();
 
         let ac = AhoCorasick::new(patterns)?;
+        let processor =
+            TextProcessor::new().add_step(move |text| ac.replace_all(text, &replace_with));
 
-        Ok(Self {
-            replacer: (ac, replace_with),
-        })
+        Ok(Self { processor })
     }
 }
 
@@ -38,7 +40,41 @@ impl Noveler for Czbooks {
 
         let selector = r"span.author > a";
         let author = document.find(selector).text();
-        Ok(Book { name, author })
+        Ok(Book {
+            name,
+            author,
+            ..Book::default()
+        })
+    }
+
+    fn get_synopsis(&self, document: &Elements) -> Option<String> {
+        let selector = r"div.novel-detail div.description";
+        let synopsis = document
+            .find(selector)
+            .text()
+            .lines()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n");
+        (!synopsis.is_empty()).then_some(synopsis)
+    }
+
+    fn get_cover_url(&self, document: &Elements) -> Result<Option<Url>, NovelError> {
+        let selector = r"div.novel-detail div.thumbnail img";
+        let Some(src) = document.find(selector).attr("src") else {
+            return Ok(None);
+        };
+        Ok(Some(Url::parse(&src.to_string())?))
+    }
+
+    fn get_tags(&self, document: &Elements) -> Vec<String> {
+        let selector = r"div.novel-detail div.state a";
+        document
+            .find(selector)
+            .into_iter()
+            .map(|x| x.text().trim().to_string())
+            .collect()
     }
 
     fn get_chapter_urls_sorted(&self, document: &Elements) -> Result<Vec<Url>, NovelError> {
@@ -65,8 +101,7 @@ impl Noveler for Czbooks {
             .find(selector)
             .text()
             .trim()
-            .replace("《射手凶猛》", "")
-            .to_string();
+            .replace("《射手凶猛》", "");
 
         let selector = r"div.content";
         let text = document.find(selector).text();
@@ -75,17 +110,25 @@ impl Noveler for Czbooks {
         Ok(Chapter { order, title, text })
     }
 
-    fn get_next_page(&self, _document: &Elements) -> Result<Option<Url>, NovelError> {
-        Ok(None)
+    // a long chapter links its next page beside the next chapter
+    fn get_next_page(&self, document: &Elements) -> Result<Option<Url>, NovelError> {
+        let selector = r"ul.chapter-nav a";
+        let Some(href) = document
+            .find(selector)
+            .into_iter()
+            .find(|a| {
+                let text = a.text();
+                text.contains("下一頁") || text.contains("下一页")
+            })
+            .and_then(|a| a.get_attribute("href"))
+        else {
+            return Ok(None);
+        };
+        Ok(Some(Url::parse(BASE)?.join(&href.to_string())?))
     }
 
-    fn process_chapter(&self, mut chapter: Chapter) -> Chapter {
-        chapter.text = self
-            .replacer
-            .0
-            .replace_all(&chapter.text, &self.replacer.1)
-            .to_string();
-        chapter
+    fn process_chapter_text(&self, text: &str) -> String {
+        self.processor.process(text)
     }
 }
 
@@ -101,6 +144,10 @@ mod tests {
         env!("CARGO_MANIFEST_DIR"),
         "/tests/czbooks/chapter.html"
     ));
+    static CHAPTER_PAGED: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/czbooks/chapter_paged.html"
+    ));
 
     #[test]
     fn test_get_book_info() {
@@ -112,11 +159,31 @@ mod tests {
             book,
             Book {
                 name: "射手凶猛".to_string(),
-                author: "初四兮".to_string()
+                author: "初四兮".to_string(),
+                ..Book::default()
             }
         );
     }
 
+    #[test]
+    fn test_get_book_details() {
+        let html = CONTENTS;
+        let document = visdom::Vis::load(html).unwrap();
+        let novel = Czbooks::new().unwrap();
+        let book = novel.get_book(&document).unwrap();
+        let synopsis = book.synopsis.unwrap();
+        assert!(synopsis.starts_with("你覺得自己能成為像誰一樣的職業adc？"));
+        assert!(synopsis.ends_with("《這個選手罪孽深重》"));
+        assert_eq!(
+            book.cover_url,
+            Some(
+                Url::parse("https://img.uukanshu.com/fengmian/2022/3/637829208702318117.jpg")
+                    .unwrap()
+            )
+        );
+        assert_eq!(book.tags, ["科幻未來"]);
+    }
+
     #[test]
     fn test_get_chapter_urls_sorted() {
         let html = CONTENTS;
@@ -154,5 +221,12 @@ mod tests {
         let novel = Czbooks::new().unwrap();
         let url = novel.get_next_page(&document).unwrap();
         assert_eq!(url, None);
+
+        let document = visdom::Vis::load(CHAPTER_PAGED).unwrap();
+        let url = novel.get_next_page(&document).unwrap();
+        assert_eq!(
+            url,
+            Some(Url::parse("https://czbooks.net/n/uilla7/und20/2").unwrap())
+        );
     }
 }
@@ -0,0 +1,175 @@
+//! 123讀小說 <https://www.123du.cc/>
+use super::{parse_word_count, Book, NovelError, Noveler, TextProcessor};
+use std::fmt::{self, Display};
+use url::Url;
+use visdom::types::Elements;
+
+pub(crate) struct Du123 {
+    base: Url,
+    processor: TextProcessor,
+}
+
+impl Du123 {
+    pub(crate) fn new(url: &str) -> Result<Self, NovelError> {
+        let mut base = Url::parse(url)?;
+
+        match base.path_segments_mut() {
+            Ok(mut path) => {
+                path.clear();
+            }
+            Err(()) => {
+                return Err(NovelError::CannotBeABase(url.to_string()));
+            }
+        }
+        base.set_query(None);
+
+        let processor = TextProcessor::new()
+            .add_regex_replacement(r"(?m)^.*(?:123du\.cc|123读小说).*$", "")?
+            .add_line_split_and_trim(&['\n', '\u{a0}', '\u{3000}', '\r'])
+            .add_filter_empty_lines();
+
+        Ok(Self { base, processor })
+    }
+}
+
+impl Display for Du123 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "123讀小說")
+    }
+}
+
+impl Noveler for Du123 {
+    fn base_url(&self) -> Option<&Url> {
+        Some(&self.base)
+    }
+
+    fn need_encoding(&self) -> Option<&'static encoding_rs::Encoding> {
+        Some(encoding_rs::GBK)
+    }
+
+    fn get_book_info(&self, document: &Elements) -> Result<Book, NovelError> {
+        let selector = r"h1";
+        let name = document.find(selector).text().trim().to_string();
+
+        let selector = r"table.binfo tr:nth-child(2) td:first-of-type";
+        let author = document
+            .find(selector)
+            .text()
+            .replace("作者：", "")
+            .trim()
+            .to_string();
+        Ok(Book {
+            name,
+            author,
+            ..Book::default()
+        })
+    }
+
+    fn get_book_word_count(&self, document: &Elements) -> Option<usize> {
+        let selector = r"table.binfo td";
+        let td = document
+            .find(selector)
+            .into_iter()
+            .find(|td| td.text().contains("字数"))?;
+        parse_word_count(&td.text())
+    }
+
+    fn get_chapter_urls_sorted(&self, document: &Elements) -> Result<Vec<Url>, NovelError> {
+        let selector = r"div#list a";
+        document
+            .find(selector)
+            .into_iter()
+            .map(|x| {
+                x.get_attribute("href")
+                    .map(|attr| attr.to_string())
+                    .ok_or(NovelError::NotFound("href".to_string()))
+            })
+            .map(|x| x.and_then(|url_str| self.base.join(&url_str).map_err(NovelError::ParseError)))
+            .collect()
+    }
+
+    fn get_next_page(&self, _document: &Elements) -> Result<Option<Url>, NovelError> {
+        Ok(None)
+    }
+
+    fn process_chapter_text(&self, text: &str) -> String {
+        self.processor.process(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static CONTENTS: &[u8] = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/du123/contents.html"
+    ));
+    static CHAPTER: &[u8] = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/du123/chapter.html"
+    ));
+
+    #[test]
+    fn test_get_book_info() {
+        let novel = Du123::new("https://www.123du.cc/dudu-31/1181731/").unwrap();
+        let (html, _, _) = novel.need_encoding().unwrap().decode(CONTENTS);
+        let document = visdom::Vis::load(html).unwrap();
+        let book = novel.get_book_info(&document).unwrap();
+        assert_eq!(
+            book,
+            Book {
+                name: "凡人修仙传".to_string(),
+                author: "忘语".to_string(),
+                ..Book::default()
+            }
+        );
+        assert_eq!(
+            novel.get_book(&document).unwrap().word_count,
+            Some(7_446_890)
+        );
+    }
+
+    #[test]
+    fn test_get_chapter_urls_sorted() {
+        let novel = Du123::new("https://www.123du.cc/dudu-31/1181731/").unwrap();
+        let (html, _, _) = novel.need_encoding().unwrap().decode(CONTENTS);
+        let document = visdom::Vis::load(html).unwrap();
+        let urls = novel.get_chapter_urls_sorted(&document).unwrap();
+        assert_eq!(urls.len(), 3);
+        assert_eq!(
+            urls.first().unwrap(),
+            &Url::parse("https://www.123du.cc/dudu-31/1181731/2891001.html").unwrap()
+        );
+        assert_eq!(
+            urls.last().unwrap(),
+            &Url::parse("https://www.123du.cc/dudu-31/1181731/2891003.html").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_get_chapter_content() {
+        let novel = Du123::new("https://www.123du.cc/dudu-31/1181731/").unwrap();
+        let (html, _, _) = novel.need_encoding().unwrap().decode(CHAPTER);
+        let document = visdom::Vis::load(html).unwrap();
+        let chapter = novel.get_chapter(&document, "1").unwrap();
+        assert_eq!(chapter.order, "1".to_string());
+        assert_eq!(chapter.title, "第一章 山边小村".to_string());
+        let chapter = novel.process_chapter(chapter);
+        dbg!(&chapter.text);
+        assert!(chapter
+            .text
+            .starts_with("二愣子睁大着双眼，直直望着茅草和烂泥糊成的黑屋顶"));
+        assert!(chapter.text.ends_with("这是韩立第一次离开村子。"));
+        assert!(!chapter.text.contains("123读小说"));
+    }
+
+    #[test]
+    fn test_get_next_page() {
+        let novel = Du123::new("https://www.123du.cc/dudu-31/1181731/").unwrap();
+        let (html, _, _) = novel.need_encoding().unwrap().decode(CHAPTER);
+        let document = visdom::Vis::load(html).unwrap();
+        let url = novel.get_next_page(&document).unwrap();
+        assert_eq!(url, None);
+    }
+}
@@ -0,0 +1,179 @@
+//! 頂點小說 <https://www.dxmwx.org/>
+use super::{Book, NovelError, Noveler, TextProcessor};
+use std::fmt::{self, Display};
+use url::Url;
+use visdom::types::Elements;
+
+pub(crate) struct Dxmwx {
+    base: Url,
+    processor: TextProcessor,
+}
+
+impl Dxmwx {
+    pub(crate) fn new(url: &str) -> Result<Self, NovelError> {
+        let mut base = Url::parse(url)?;
+
+        match base.path_segments_mut() {
+            Ok(mut path) => {
+                path.clear();
+            }
+            Err(()) => {
+                return Err(NovelError::CannotBeABase(url.to_string()));
+            }
+        }
+        base.set_query(None);
+
+        let processor = TextProcessor::new()
+            .add_regex_replacement(r"(?m)^.*(?:dxmwx\.org|顶点小说).*$", "")?
+            .add_line_split_and_trim(&['\n', '\u{a0}', '\u{3000}', '\r'])
+            .add_filter_empty_lines();
+
+        Ok(Self { base, processor })
+    }
+}
+
+impl Display for Dxmwx {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "頂點小說")
+    }
+}
+
+impl Noveler for Dxmwx {
+    fn base_url(&self) -> Option<&Url> {
+        Some(&self.base)
+    }
+
+    fn need_encoding(&self) -> Option<&'static encoding_rs::Encoding> {
+        Some(encoding_rs::GBK)
+    }
+
+    fn get_book_info(&self, document: &Elements) -> Result<Book, NovelError> {
+        let selector = r"h1";
+        let name = document.find(selector).text().trim().to_string();
+
+        let selector = r"table.mulu_main tr:nth-child(2) td:first-of-type";
+        let author = document
+            .find(selector)
+            .text()
+            .replace("作者：", "")
+            .trim()
+            .to_string();
+        Ok(Book {
+            name,
+            author,
+            ..Book::default()
+        })
+    }
+
+    fn get_tags(&self, document: &Elements) -> Vec<String> {
+        let selector = r"table.mulu_main tr:nth-child(2) td:nth-of-type(2)";
+        let text = document.find(selector).text();
+        text.split_once('：')
+            .map(|(_, category)| vec![category.trim().to_string()])
+            .unwrap_or_default()
+    }
+
+    fn get_chapter_urls_sorted(&self, document: &Elements) -> Result<Vec<Url>, NovelError> {
+        let selector = r"div#catalog a";
+        document
+            .find(selector)
+            .into_iter()
+            .map(|x| {
+                x.get_attribute("href")
+                    .map(|attr| attr.to_string())
+                    .ok_or(NovelError::NotFound("href".to_string()))
+            })
+            .map(|x| x.and_then(|url_str| self.base.join(&url_str).map_err(NovelError::ParseError)))
+            .collect()
+    }
+
+    fn get_next_page(&self, _document: &Elements) -> Result<Option<Url>, NovelError> {
+        Ok(None)
+    }
+
+    fn process_chapter_text(&self, text: &str) -> String {
+        self.processor.process(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static CONTENTS: &[u8] = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/dxmwx/contents.html"
+    ));
+    static CHAPTER: &[u8] = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/dxmwx/chapter.html"
+    ));
+
+    #[test]
+    fn test_get_book_info() {
+        let novel = Dxmwx::new("https://www.dxmwx.org/chapter/1573.html").unwrap();
+        let (html, _, _) = novel.need_encoding().unwrap().decode(CONTENTS);
+        let document = visdom::Vis::load(html).unwrap();
+        let book = novel.get_book_info(&document).unwrap();
+        assert_eq!(
+            book,
+            Book {
+                name: "斗破苍穹".to_string(),
+                author: "天蚕土豆".to_string(),
+                ..Book::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_get_book_details() {
+        let novel = Dxmwx::new("https://www.dxmwx.org/chapter/1573.html").unwrap();
+        let (html, _, _) = novel.need_encoding().unwrap().decode(CONTENTS);
+        let document = visdom::Vis::load(html).unwrap();
+        let book = novel.get_book(&document).unwrap();
+        assert_eq!(book.tags, ["玄幻魔法"]);
+    }
+
+    #[test]
+    fn test_get_chapter_urls_sorted() {
+        let novel = Dxmwx::new("https://www.dxmwx.org/chapter/1573.html").unwrap();
+        let (html, _, _) = novel.need_encoding().unwrap().decode(CONTENTS);
+        let document = visdom::Vis::load(html).unwrap();
+        let urls = novel.get_chapter_urls_sorted(&document).unwrap();
+        assert_eq!(urls.len(), 3);
+        assert_eq!(
+            urls.first().unwrap(),
+            &Url::parse("https://www.dxmwx.org/read/1573_1.html").unwrap()
+        );
+        assert_eq!(
+            urls.last().unwrap(),
+            &Url::parse("https://www.dxmwx.org/read/1573_3.html").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_get_chapter_content() {
+        let novel = Dxmwx::new("https://www.dxmwx.org/chapter/1573.html").unwrap();
+        let (html, _, _) = novel.need_encoding().unwrap().decode(CHAPTER);
+        let document = visdom::Vis::load(html).unwrap();
+        let chapter = novel.get_chapter(&document, "1").unwrap();
+        assert_eq!(chapter.order, "1".to_string());
+        assert_eq!(chapter.title, "第一章 陨落的天才".to_string());
+        let chapter = novel.process_chapter(chapter);
+        dbg!(&chapter.text);
+        assert!(chapter.text.starts_with("“斗之力，三段！”"));
+        assert!(chapter
+            .text
+            .ends_with("少年缓缓抬起头来，露出一张有些清秀的稚嫩脸庞。"));
+        assert!(!chapter.text.contains("顶点小说"));
+    }
+
+    #[test]
+    fn test_get_next_page() {
+        let novel = Dxmwx::new("https://www.dxmwx.org/chapter/1573.html").unwrap();
+        let (html, _, _) = novel.need_encoding().unwrap().decode(CHAPTER);
+        let document = visdom::Vis::load(html).unwrap();
+        let url = novel.get_next_page(&document).unwrap();
+        assert_eq!(url, None);
+    }
+}
@@ -1,13 +1,12 @@
-/// 黃金屋 <https://tw.hjwzw.com/>
-use super::{Book, Chapter, NovelError, Noveler};
-use regex::Regex;
+//! 黃金屋 <https://tw.hjwzw.com/>
+use super::{Book, Chapter, ConcurrencyPolicy, NovelError, Noveler, TextProcessor};
 use std::fmt::{self, Display};
 use url::Url;
 use visdom::types::Elements;
 
 pub(crate) struct Hjwzw {
     base: Url,
-    replacer: (Vec<Regex>, Vec<String>),
+    processor: TextProcessor,
 }
 
 impl Hjwzw {
@@ -24,20 +23,12 @@ impl Hjwzw {
         }
         base.set_query(None);
 
-        let patterns = [""];
-        let replace_with = [""]
-            .into_iter()
-            .map(std::string::ToString::to_string)
-            .collect();
-        let regexes = patterns
-            .into_iter()
-            .map(Regex::new)
-            .collect::<Result<Vec<_>, _>>()?;
+        let processor = TextProcessor::new()
+            .add_line_split_and_trim(&['\n', '\r'])
+            .add_filter_empty_lines()
+            .add_skip_first_n_lines(2);
 
-        Ok(Self {
-            base,
-            replacer: (regexes, replace_with),
-        })
+        Ok(Self { base, processor })
     }
 }
 
@@ -48,6 +39,17 @@ impl Display for Hjwzw {
 }
 
 impl Noveler for Hjwzw {
+    fn base_url(&self) -> Option<&Url> {
+        Some(&self.base)
+    }
+
+    fn default_concurrency(&self) -> ConcurrencyPolicy {
+        ConcurrencyPolicy::Adaptive {
+            initial: 5,
+            max: 20,
+        }
+    }
+
     fn get_book_info(&self, document: &Elements) -> Result<Book, NovelError> {
         let selector = r"h1";
         let name = document.find(selector).text();
@@ -55,7 +57,23 @@ impl Noveler for Hjwzw {
         let selector =
             r"body > div:first-child > table:nth-of-type(7) tr:nth-child(2) a:first-child";
         let author = document.find(selector).text().replace("作者 / ", "");
-        Ok(Book { name, author })
+        Ok(Book {
+            name,
+            author,
+            ..Book::default()
+        })
+    }
+
+    fn get_tags(&self, document: &Elements) -> Vec<String> {
+        let info = document.find(r"body > div:first-child > table:nth-of-type(7) tr:nth-child(2)");
+        // the author and the book name are listed as tags too
+        let name = document.find(r"h1").text();
+        let author = info.find(r#"a[href^="/List/"]"#).text();
+        info.find(r#"a[href^="/Channel/"]"#)
+            .into_iter()
+            .map(|x| x.text().trim().to_string())
+            .filter(|tag| *tag != name.trim() && *tag != author.trim())
+            .collect()
     }
 
     fn get_chapter_urls_sorted(&self, document: &Elements) -> Result<Vec<Url>, NovelError> {
@@ -89,20 +107,8 @@ impl Noveler for Hjwzw {
         Ok(None)
     }
 
-    fn process_chapter(&self, chapter: Chapter) -> Chapter {
-        let mut text = chapter.text;
-        text = text
-            .split(['\n', '\r'])
-            .map(str::trim)
-            .filter(|s| !s.is_empty())
-            .skip(2)
-            .collect::<Vec<&str>>()
-            .join("\n");
-        for (re, s) in self.replacer.0.iter().zip(self.replacer.1.iter()) {
-            text = re.replace_all(&text, s).to_string();
-        }
-
-        Chapter { text, ..chapter }
+    fn process_chapter_text(&self, text: &str) -> String {
+        self.processor.process(text)
     }
 }
 
@@ -133,11 +139,23 @@ mod tests {
             book,
             Book {
                 name: "修真聊天群".to_string(),
-                author: "圣騎士的傳說".to_string()
+                author: "圣騎士的傳說".to_string(),
+                ..Book::default()
             }
         );
     }
 
+    #[test]
+    fn test_get_book_details() {
+        let html = CONTENTS;
+        let document = visdom::Vis::load(html).unwrap();
+        let novel = Hjwzw::new("https://tw.hjwzw.com/Book/Chapter/35728").unwrap();
+        let book = novel.get_book(&document).unwrap();
+        assert_eq!(book.synopsis, None);
+        assert_eq!(book.cover_url, None);
+        assert_eq!(book.tags, ["都市", "異術超能"]);
+    }
+
     #[test]
     fn test_get_chapter_urls_sorted() {
         let html = CONTENTS;
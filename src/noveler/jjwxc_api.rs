@@ -0,0 +1,392 @@
+//! 晉江文學城 <https://www.jjwxc.net/>
+use super::{Book, Chapter, NovelError, Noveler, TextProcessor};
+use reqwest::{Client, RequestBuilder};
+use serde::Deserialize;
+use std::fmt::{self, Display};
+use url::Url;
+use visdom::types::Elements;
+
+/// the chapter list and the chapters come from the api of the app, the web chapters are behind
+/// a captcha on most ips
+const API_BASE: &str = "https://app.jjwxc.net/";
+
+pub(crate) struct JjwxcApi {
+    base: Url,
+    api_base: Url,
+    novel_id: String,
+    /// the token of the app, sent with every api request, the VIP chapters need a logged in one
+    token: Option<String>,
+    processor: TextProcessor,
+}
+
+/// response of `/androidapi/chapterList`
+#[derive(Debug, Deserialize)]
+struct ChaptersResponse {
+    chapterlist: Vec<ChapterInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChapterInfo {
+    chapterid: String,
+    /// "1" is a volume heading, not a chapter
+    #[serde(default)]
+    chaptertype: String,
+}
+
+/// response of `/androidapi/chapterContent`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ContentResponse {
+    #[serde(default)]
+    chapter_name: String,
+    #[serde(default)]
+    content: String,
+    /// why the content is left out, e.g. a VIP chapter without a token
+    message: Option<String>,
+}
+
+impl JjwxcApi {
+    pub(crate) fn new(url: &str, token: Option<String>) -> Result<Self, NovelError> {
+        let mut base = Url::parse(url)?;
+
+        // https://www.jjwxc.net/onebook.php?novelid={novelId}
+        let novel_id = base
+            .query_pairs()
+            .find(|(key, _)| key == "novelid")
+            .map(|(_, value)| value.to_string())
+            .filter(|id| !id.is_empty())
+            .ok_or(NovelError::NotFound("novelid".to_string()))?;
+
+        match base.path_segments_mut() {
+            Ok(mut path) => {
+                path.clear();
+            }
+            Err(()) => {
+                return Err(NovelError::CannotBeABase(url.to_string()));
+            }
+        }
+        base.set_query(None);
+
+        let processor = TextProcessor::new()
+            .add_regex_replacement(r"(?m)^.*(?:jjwxc\.net|[晉晋]江文[學学]城).*$", "")?
+            .add_line_split_and_trim(&['\n', '\u{a0}', '\u{3000}', '\r'])
+            .add_filter_empty_lines();
+
+        Ok(Self {
+            base,
+            api_base: Url::parse(API_BASE)?,
+            novel_id,
+            token,
+            processor,
+        })
+    }
+
+    fn chapters_url(&self) -> Result<Url, NovelError> {
+        let mut url = self.api_base.join("/androidapi/chapterList")?;
+        url.query_pairs_mut().append_pair("novelId", &self.novel_id);
+        Ok(url)
+    }
+
+    /// the token goes in the query of the request only, the chapter urls are saved in state.json
+    fn api_get(&self, client: &Client, url: Url) -> RequestBuilder {
+        let request = client.get(url);
+        match &self.token {
+            Some(token) => request.query(&[("token", token)]),
+            None => request,
+        }
+    }
+
+    fn parse_chapters(&self, json: &str) -> Result<Vec<Url>, NovelError> {
+        let response: ChaptersResponse = serde_json::from_str(json)?;
+        response
+            .chapterlist
+            .into_iter()
+            .filter(|chapter| chapter.chaptertype != "1")
+            .map(|chapter| {
+                let mut url = self.api_base.join("/androidapi/chapterContent")?;
+                url.query_pairs_mut()
+                    .append_pair("novelId", &self.novel_id)
+                    .append_pair("chapterId", &chapter.chapterid);
+                Ok(url)
+            })
+            .collect()
+    }
+
+    fn parse_content(json: &str, order: &str) -> Result<Chapter, NovelError> {
+        let response: ContentResponse = serde_json::from_str(json)?;
+        let title = response.chapter_name.trim().to_string();
+        if response.content.trim().is_empty() {
+            let reason = response.message.unwrap_or_default();
+            return Err(NovelError::VipChapter(
+                format!("{order} {title} {reason}").trim().to_string(),
+            ));
+        }
+        Ok(Chapter {
+            order: order.to_string(),
+            title,
+            text: response.content,
+        })
+    }
+
+    async fn fetch_chapters(&self, client: Client) -> Result<Vec<Url>, NovelError> {
+        let json = self
+            .api_get(&client, self.chapters_url()?)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        self.parse_chapters(&json)
+    }
+}
+
+impl Display for JjwxcApi {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "晉江文學城")
+    }
+}
+
+impl Noveler for JjwxcApi {
+    fn base_url(&self) -> Option<&Url> {
+        Some(&self.base)
+    }
+
+    fn need_encoding(&self) -> Option<&'static encoding_rs::Encoding> {
+        Some(encoding_rs::GB18030)
+    }
+
+    // the chapters are json from the api, not html pages
+    async fn process_url(
+        &self,
+        client: Client,
+        order: &str,
+        url: Url,
+        _encoding: Option<&'static encoding_rs::Encoding>,
+    ) -> Result<(Chapter, Option<Url>), NovelError> {
+        let json = self
+            .api_get(&client, url)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        let chapter = Self::parse_content(&json, order)?;
+        Ok((self.process_chapter(chapter), None))
+    }
+
+    fn get_book_info(&self, document: &Elements) -> Result<Book, NovelError> {
+        let selector = r#"span[itemprop="articleSection"]"#;
+        let name = document.find(selector).text().trim().to_string();
+        if name.is_empty() {
+            return Err(NovelError::NotFound("articleSection".to_string()));
+        }
+
+        let selector = r#"span[itemprop="author"]"#;
+        let author = document.find(selector).text().trim().to_string();
+        Ok(Book {
+            name,
+            author,
+            ..Book::default()
+        })
+    }
+
+    fn get_synopsis(&self, document: &Elements) -> Option<String> {
+        let selector = r"div#novelintro";
+        let synopsis = document
+            .find(selector)
+            .text()
+            .lines()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n");
+        (!synopsis.is_empty()).then_some(synopsis)
+    }
+
+    fn get_cover_url(&self, document: &Elements) -> Result<Option<Url>, NovelError> {
+        let selector = r"img.noveldefaultimage";
+        let Some(src) = document.find(selector).attr("src") else {
+            return Ok(None);
+        };
+        Ok(Some(self.base.join(&src.to_string())?))
+    }
+
+    // the book is on www.jjwxc.net, the chapters on app.jjwxc.net
+    fn chapter_url_is_valid(&self, url: &Url) -> bool {
+        url.host_str()
+            .is_some_and(|host| host == "jjwxc.net" || host.ends_with(".jjwxc.net"))
+            && url.path() != "/"
+    }
+
+    // the web chapters are behind a captcha, only the api is used
+    fn get_chapter_urls_sorted(&self, _document: &Elements) -> Result<Vec<Url>, NovelError> {
+        Ok(Vec::new())
+    }
+
+    async fn fetch_chapter_urls_sorted(
+        &self,
+        client: Client,
+        _document: &Elements<'_>,
+    ) -> Result<Vec<Url>, NovelError> {
+        self.fetch_chapters(client).await
+    }
+
+    fn get_next_page(&self, _document: &Elements) -> Result<Option<Url>, NovelError> {
+        Ok(None)
+    }
+
+    fn process_chapter_text(&self, text: &str) -> String {
+        self.processor.process(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static CONTENTS: &[u8] = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/jjwxc_api/contents.html"
+    ));
+    static CHAPTERS: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/jjwxc_api/chapters.json"
+    ));
+    static CONTENT: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/jjwxc_api/content.json"
+    ));
+    static VIP: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/jjwxc_api/vip.json"
+    ));
+
+    #[test]
+    fn test_new() {
+        let novel =
+            JjwxcApi::new("https://www.jjwxc.net/onebook.php?novelid=2456159", None).unwrap();
+        assert_eq!(novel.novel_id, "2456159");
+        assert_eq!(
+            novel.chapters_url().unwrap(),
+            Url::parse("https://app.jjwxc.net/androidapi/chapterList?novelId=2456159").unwrap()
+        );
+
+        assert!(matches!(
+            JjwxcApi::new("https://www.jjwxc.net/onebook.php", None),
+            Err(NovelError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_get_book_info() {
+        let novel =
+            JjwxcApi::new("https://www.jjwxc.net/onebook.php?novelid=2456159", None).unwrap();
+        let (html, _, _) = novel.need_encoding().unwrap().decode(CONTENTS);
+        let document = visdom::Vis::load(html).unwrap();
+        let book = novel.get_book_info(&document).unwrap();
+        assert_eq!(
+            book,
+            Book {
+                name: "天官赐福".to_string(),
+                author: "墨香铜臭".to_string(),
+                ..Book::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_get_book_details() {
+        let novel =
+            JjwxcApi::new("https://www.jjwxc.net/onebook.php?novelid=2456159", None).unwrap();
+        let (html, _, _) = novel.need_encoding().unwrap().decode(CONTENTS);
+        let document = visdom::Vis::load(html).unwrap();
+        let book = novel.get_book(&document).unwrap();
+        assert_eq!(
+            book.synopsis.as_deref(),
+            Some("八百年前，谢怜是金枝玉叶的太子殿下。\n八百年后，谢怜飞升了。")
+        );
+        assert_eq!(
+            book.cover_url,
+            Some(Url::parse("https://i9-static.jjwxc.net/novelimage.php?novelid=2456159").unwrap())
+        );
+    }
+
+    #[test]
+    fn test_parse_chapters() {
+        let novel =
+            JjwxcApi::new("https://www.jjwxc.net/onebook.php?novelid=2456159", None).unwrap();
+        let urls = novel.parse_chapters(CHAPTERS).unwrap();
+        // the volume heading is left out
+        assert_eq!(urls.len(), 3);
+        assert_eq!(
+            urls.first().unwrap(),
+            &Url::parse(
+                "https://app.jjwxc.net/androidapi/chapterContent?novelId=2456159&chapterId=1"
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            urls.last().unwrap(),
+            &Url::parse(
+                "https://app.jjwxc.net/androidapi/chapterContent?novelId=2456159&chapterId=3"
+            )
+            .unwrap()
+        );
+        assert!(urls.iter().all(|url| novel.chapter_url_is_valid(url)));
+    }
+
+    #[test]
+    fn test_parse_content() {
+        let novel =
+            JjwxcApi::new("https://www.jjwxc.net/onebook.php?novelid=2456159", None).unwrap();
+        let chapter = JjwxcApi::parse_content(CONTENT, "1").unwrap();
+        assert_eq!(chapter.order, "1".to_string());
+        assert_eq!(chapter.title, "第一章 上元佳节".to_string());
+        let chapter = novel.process_chapter(chapter);
+        dbg!(&chapter.text);
+        assert!(chapter.text.starts_with("仙乐国的上元祭天游，是一场盛事。"));
+        assert!(chapter.text.ends_with("太子悦神。"));
+        assert!(!chapter.text.contains("晋江"));
+    }
+
+    #[test]
+    fn test_parse_content_vip() {
+        let err = JjwxcApi::parse_content(VIP, "3").unwrap_err();
+        assert!(matches!(
+            &err,
+            NovelError::VipChapter(chapter) if chapter == "3 第三章 鬼新娘 请先登录"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_process_url_with_token() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/androidapi/chapterContent")
+            .match_query(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("chapterId".to_string(), "1".to_string()),
+                mockito::Matcher::UrlEncoded("token".to_string(), "token".to_string()),
+            ]))
+            .with_body(CONTENT)
+            .create_async()
+            .await;
+
+        let novel = JjwxcApi::new(
+            "https://www.jjwxc.net/onebook.php?novelid=2456159",
+            Some("token".to_string()),
+        )
+        .unwrap();
+        let url = Url::parse(&format!(
+            "{}/androidapi/chapterContent?novelId=2456159&chapterId=1",
+            server.url()
+        ))
+        .unwrap();
+        let (chapter, next_page) = novel
+            .process_url(Client::new(), "1", url, None)
+            .await
+            .unwrap();
+        assert_eq!(chapter.title, "第一章 上元佳节".to_string());
+        assert_eq!(next_page, None);
+        mock.assert_async().await;
+    }
+}
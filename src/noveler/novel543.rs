@@ -1,11 +1,12 @@
-/// 稷下書院 <https://www.novel543.com/>
-use super::{Book, Chapter, NovelError, Noveler};
+//! 稷下書院 <https://www.novel543.com/>
+use super::{Book, ConcurrencyPolicy, NovelError, Noveler, TextProcessor};
 use std::fmt::{self, Display};
 use url::Url;
 use visdom::types::Elements;
 
 pub(crate) struct Novel543 {
     base: Url,
+    processor: TextProcessor,
 }
 
 impl Novel543 {
@@ -23,7 +24,42 @@ impl Novel543 {
 
         base.set_query(None);
 
-        Ok(Self { base })
+        let processor = TextProcessor::new().add_step(|text| {
+            text.trim()
+                .split_inclusive('。')
+                .map(|s| s.trim().replace('㱕', ""))
+                .filter(|s| !s.is_empty())
+                .collect::<Vec<_>>()
+                .join("\n")
+        });
+
+        Ok(Self { base, processor })
+    }
+
+    /// the current page without `.html`, relative to the base, and the href of the next link
+    fn page_links(&self, document: &Elements) -> Result<(String, String), NovelError> {
+        let selector = r"head > link:nth-last-of-type(1)";
+        let curr_page = document
+            .find(selector)
+            .attr("href")
+            .ok_or(NovelError::NotFound("curr_page href".to_string()))?
+            .to_string();
+        let curr_page = Url::parse(&curr_page)?;
+
+        // std::fs::write("test.html", &document.html())?;
+        let selector = r"#read > div > div.warp.my-5.foot-nav > a:nth-child(5)";
+        let next_page = document
+            .find(selector)
+            .attr("href")
+            .ok_or(NovelError::NotFound("next_page href".to_string()))?
+            .to_string();
+
+        let relative = self
+            .base
+            .make_relative(&curr_page)
+            .ok_or(NovelError::NotFound("curr_page relative".to_string()))?;
+
+        Ok((relative.replace(".html", ""), next_page))
     }
 }
 
@@ -34,13 +70,25 @@ impl Display for Novel543 {
 }
 
 impl Noveler for Novel543 {
+    fn base_url(&self) -> Option<&Url> {
+        Some(&self.base)
+    }
+
+    fn default_concurrency(&self) -> ConcurrencyPolicy {
+        ConcurrencyPolicy::Fixed(1)
+    }
+
     fn get_book_info(&self, document: &Elements) -> Result<Book, NovelError> {
         let selector = r"h1.title.is-2";
         let name = document.find(selector).text().replace(" 章節列表", "");
 
         let selector = r"h2.title.is-4";
         let author = document.find(selector).text().replace("作者 / ", "");
-        Ok(Book { name, author })
+        Ok(Book {
+            name,
+            author,
+            ..Book::default()
+        })
     }
 
     fn get_chapter_urls_sorted(&self, document: &Elements) -> Result<Vec<Url>, NovelError> {
@@ -57,55 +105,31 @@ impl Noveler for Novel543 {
             .collect()
     }
 
-    fn get_chapter(&self, document: &Elements, order: &str) -> Result<Chapter, NovelError> {
-        let selector = r"#chapterWarp > div.chapter-content.px-3 > h1";
-        let title = document.find(selector).text().trim().to_string();
-
-        let selector = r"#chapterWarp > div.chapter-content.px-3 > div";
-        let text: String = document.find(selector).text();
+    fn chapter_title_selector(&self) -> &'static str {
+        r"#chapterWarp > div.chapter-content.px-3 > h1"
+    }
 
-        let order = order.to_string();
-        Ok(Chapter { order, title, text })
+    fn chapter_content_selector(&self) -> &'static str {
+        r"#chapterWarp > div.chapter-content.px-3 > div"
     }
 
     fn get_next_page(&self, document: &Elements) -> Result<Option<Url>, NovelError> {
-        let selector = r"head > link:nth-last-of-type(1)";
-        let curr_page = document
-            .find(selector)
-            .attr("href")
-            .ok_or(NovelError::NotFound("curr_page href".to_string()))?
-            .to_string();
-        let curr_page = Url::parse(&curr_page)?;
-
-        // std::fs::write("test.html", &document.html())?;
-        let selector = r"#read > div > div.warp.my-5.foot-nav > a:nth-child(5)";
-        let next_page = document
-            .find(selector)
-            .attr("href")
-            .ok_or(NovelError::NotFound("next_page href".to_string()))?
-            .to_string();
-
-        let relative = self
-            .base
-            .make_relative(&curr_page)
-            .ok_or(NovelError::NotFound("curr_page relative".to_string()))?;
-
-        if next_page.contains(&relative.replace(".html", "")) {
+        let (current, next_page) = self.page_links(document)?;
+        if next_page.contains(&current) {
             Ok(Some(self.base.join(&next_page)?))
         } else {
             Ok(None)
         }
     }
 
-    fn process_chapter(&self, chapter: Chapter) -> Chapter {
-        let mut text = chapter.text.trim().to_string();
-        text = text
-            .split_inclusive('。')
-            .map(|s| s.trim().replace('㱕', ""))
-            .filter(|s| !s.is_empty())
-            .collect::<Vec<_>>()
-            .join("\n");
-        Chapter { text, ..chapter }
+    // the next link leads to another chapter, e.g. `8001_2.html` from `8001_1_2.html`
+    fn is_last_chapter(&self, document: &Elements) -> bool {
+        self.page_links(document)
+            .is_ok_and(|(current, next_page)| !next_page.contains(&current))
+    }
+
+    fn process_chapter_text(&self, text: &str) -> String {
+        self.processor.process(text)
     }
 }
 
@@ -132,7 +156,8 @@ mod tests {
             book,
             Book {
                 name: "我的大寶劍".to_string(),
-                author: "學霸殿下".to_string()
+                author: "學霸殿下".to_string(),
+                ..Book::default()
             }
         );
     }
@@ -181,4 +206,20 @@ mod tests {
             Url::parse("https://www.novel543.com/0413188175/8001_1_2.html").unwrap(),
         );
     }
+
+    #[test]
+    fn test_is_last_chapter() {
+        let novel = Novel543::new("https://www.novel543.com/0413188175/dir").unwrap();
+        let document = visdom::Vis::load(CHAPTER).unwrap();
+        assert!(!novel.is_last_chapter(&document));
+
+        // the next link is the next chapter, not a page of this one
+        let html = CHAPTER.replace(
+            r#"href="/0413188175/8001_1_2.html" >"#,
+            r#"href="/0413188175/8001_2.html" >"#,
+        );
+        let document = visdom::Vis::load(html).unwrap();
+        assert!(novel.is_last_chapter(&document));
+        assert_eq!(novel.get_next_page(&document).unwrap(), None);
+    }
 }
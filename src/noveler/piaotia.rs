@@ -1,33 +1,26 @@
-/// 飄天 <https://www.piaotia.com/>
-use super::{Book, Chapter, NovelError, Noveler};
-use regex::Regex;
+//! 飄天 <https://www.piaotia.com/>
+use super::{Book, Chapter, NovelError, Noveler, TextProcessor};
 use std::fmt::{self, Display};
 use url::Url;
 use visdom::types::Elements;
 
 pub(crate) struct Piaotia {
     base: Url,
-    replacer: (Vec<Regex>, Vec<String>),
+    processor: TextProcessor,
 }
 
 impl Piaotia {
     pub(crate) fn new(url: &str) -> Result<Self, NovelError> {
         let base = Url::parse(url)?;
 
-        let patterns = ["(?s)（快捷键 ←）.*", "(?s).*返回书页"];
-        let replace_with = ["", ""]
-            .into_iter()
-            .map(std::string::ToString::to_string)
-            .collect();
-        let regexes = patterns
-            .into_iter()
-            .map(Regex::new)
-            .collect::<Result<Vec<_>, _>>()?;
+        // the navigation around the text of the pages without `div.showtxt`
+        let processor = TextProcessor::new()
+            .add_regex_replacement("(?s)（快捷键 ←）.*", "")?
+            .add_regex_replacement("(?s).*返回书页", "")?
+            .add_line_split_and_trim(&['\n', '\u{a0}'])
+            .add_filter_empty_lines();
 
-        Ok(Self {
-            base,
-            replacer: (regexes, replace_with),
-        })
+        Ok(Self { base, processor })
     }
 }
 
@@ -38,6 +31,10 @@ impl Display for Piaotia {
 }
 
 impl Noveler for Piaotia {
+    fn base_url(&self) -> Option<&Url> {
+        Some(&self.base)
+    }
+
     fn need_encoding(&self) -> Option<&'static encoding_rs::Encoding> {
         Some(encoding_rs::GBK)
     }
@@ -52,7 +49,11 @@ impl Noveler for Piaotia {
             .attr("content")
             .ok_or(NovelError::NotFound("author content".to_string()))?
             .to_string();
-        Ok(Book { name, author })
+        Ok(Book {
+            name,
+            author,
+            ..Book::default()
+        })
     }
 
     fn get_chapter_urls_sorted(&self, document: &Elements) -> Result<Vec<Url>, NovelError> {
@@ -75,11 +76,22 @@ impl Noveler for Piaotia {
             .find(selector)
             .text()
             .trim()
-            .replace("射手凶猛 ", "")
-            .to_string();
-
-        let selector = r"html";
-        let text: String = document.find(selector).text();
+            .replace("射手凶猛 ", "");
+
+        let selector = r"div.showtxt";
+        let mut text: String = document.find(selector).text();
+        if text.trim().is_empty() {
+            // older pages put the text right in the body after the `H1`, the page script wraps
+            // it in `div#content` only in the browser, so everything around it is removed instead,
+            // `head` is never closed on these pages and holds the text too
+            let doc = document.cloned();
+            doc.find(
+                r#"title, script, a, td, table, center, H1, div#guild, div#shop, div.toplink,
+                div.bottomlink, div#Commenddiv, div#feit2, div[align="center"]"#,
+            )
+            .remove();
+            text = doc.find("html").text();
+        }
 
         let order = order.to_string();
         Ok(Chapter { order, title, text })
@@ -89,20 +101,8 @@ impl Noveler for Piaotia {
         Ok(None)
     }
 
-    fn process_chapter(&self, chapter: Chapter) -> Chapter {
-        let mut text = chapter.text;
-        for (re, s) in self.replacer.0.iter().zip(self.replacer.1.iter()) {
-            text = re.replace_all(&text, s).to_string();
-        }
-
-        text = text
-            .split(['\n', '\u{a0}'])
-            .map(str::trim)
-            .filter(|s| !s.is_empty())
-            .collect::<Vec<&str>>()
-            .join("\n");
-
-        Chapter { text, ..chapter }
+    fn process_chapter_text(&self, text: &str) -> String {
+        self.processor.process(text)
     }
 }
 
@@ -129,7 +129,8 @@ mod tests {
             book,
             Book {
                 name: "射手凶猛".to_string(),
-                author: "初四兮".to_string()
+                author: "初四兮".to_string(),
+                ..Book::default()
             }
         );
     }
@@ -162,6 +163,24 @@ mod tests {
         dbg!(&chapter.text);
         assert!(chapter.text.starts_with("六月的首都日渐炎热。"));
         assert!(chapter.text.ends_with("“开个机子。”"));
+        assert!(!chapter.text.contains("飘天文学"));
+        assert!(!chapter.text.contains("繁體中文"));
+    }
+
+    #[test]
+    fn test_get_chapter_showtxt() {
+        let html = r#"<html><body>
+<H1><a href="https://www.piaotia.com/bookinfo/14/14881.html">射手凶猛</a> 第一章 老地方</H1>
+<div class="toplink"><a href="./">返回目录</a>&nbsp;<a href="https://www.piaotia.com/bookinfo/14/14881.html">返回书页</a></div>
+<div class="showtxt">&nbsp;&nbsp;&nbsp;&nbsp;六月的首都日渐炎热。<br/><br/>&nbsp;&nbsp;&nbsp;&nbsp;“开个机子。”<br/><br/></div>
+<div class="bottomlink"><a href="index.html"><span>（快捷键 ←）</span>上一章</a></div>
+</body></html>"#;
+        let novel = Piaotia::new("https://www.piaotia.com/html/14/14881/").unwrap();
+        let document = visdom::Vis::load(html).unwrap();
+        let chapter = novel.get_chapter(&document, "1").unwrap();
+        assert!(!chapter.text.contains("返回书页"));
+        let chapter = novel.process_chapter(chapter);
+        assert_eq!(chapter.text, "六月的首都日渐炎热。\n“开个机子。”");
     }
 
     #[test]
@@ -0,0 +1,150 @@
+//! 飄天文學 <https://www.piaotian.net/>, the utf-8 mirror of `Piaotia`
+use super::{Book, NovelError, Noveler, TextProcessor};
+use std::fmt::{self, Display};
+use url::Url;
+use visdom::types::Elements;
+
+pub(crate) struct PiaotianNet {
+    base: Url,
+    processor: TextProcessor,
+}
+
+impl PiaotianNet {
+    pub(crate) fn new(url: &str) -> Result<Self, NovelError> {
+        let base = Url::parse(url)?;
+
+        let processor = TextProcessor::new()
+            .add_regex_replacement(r"(?m)^.*(?:piaotian\.net|飘天文学).*$", "")?
+            .add_line_split_and_trim(&['\n', '\u{a0}'])
+            .add_filter_empty_lines();
+
+        Ok(Self { base, processor })
+    }
+}
+
+impl Display for PiaotianNet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "飄天文學")
+    }
+}
+
+impl Noveler for PiaotianNet {
+    fn base_url(&self) -> Option<&Url> {
+        Some(&self.base)
+    }
+
+    fn get_book_info(&self, document: &Elements) -> Result<Book, NovelError> {
+        let selector = r"div#info h1";
+        let name = document.find(selector).text().trim().to_string();
+
+        let selector = r"div#info p:first-of-type";
+        let author = document
+            .find(selector)
+            .text()
+            .replace("作者：", "")
+            .trim()
+            .to_string();
+        Ok(Book {
+            name,
+            author,
+            ..Book::default()
+        })
+    }
+
+    fn get_chapter_urls_sorted(&self, document: &Elements) -> Result<Vec<Url>, NovelError> {
+        let selector = r"div#list dl dd a";
+        document
+            .find(selector)
+            .into_iter()
+            .map(|x| {
+                x.get_attribute("href")
+                    .map(|attr| attr.to_string())
+                    .ok_or(NovelError::NotFound("href".to_string()))
+            })
+            .map(|x| x.and_then(|url_str| self.base.join(&url_str).map_err(NovelError::ParseError)))
+            .collect()
+    }
+
+    fn chapter_title_selector(&self) -> &'static str {
+        r"div.bookname h1"
+    }
+
+    fn get_next_page(&self, _document: &Elements) -> Result<Option<Url>, NovelError> {
+        Ok(None)
+    }
+
+    fn process_chapter_text(&self, text: &str) -> String {
+        self.processor.process(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static CONTENTS: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/piaotian_net/contents.html"
+    ));
+    static CHAPTER: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/piaotian_net/chapter.html"
+    ));
+
+    #[test]
+    fn test_get_book_info() {
+        let html = CONTENTS;
+        let document = visdom::Vis::load(html).unwrap();
+        let novel = PiaotianNet::new("https://www.piaotian.net/html/14/14881/").unwrap();
+        assert!(novel.need_encoding().is_none());
+        let book = novel.get_book_info(&document).unwrap();
+        assert_eq!(
+            book,
+            Book {
+                name: "射手凶猛".to_string(),
+                author: "初四兮".to_string(),
+                ..Book::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_get_chapter_urls_sorted() {
+        let html = CONTENTS;
+        let document = visdom::Vis::load(html).unwrap();
+        let novel = PiaotianNet::new("https://www.piaotian.net/html/14/14881/").unwrap();
+        let urls = novel.get_chapter_urls_sorted(&document).unwrap();
+        assert_eq!(
+            urls.first().unwrap(),
+            &Url::parse("https://www.piaotian.net/html/14/14881/9983851.html").unwrap()
+        );
+        assert_eq!(
+            urls.last().unwrap(),
+            &Url::parse("https://www.piaotian.net/html/14/14881/10573157.html").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_get_chapter_content() {
+        let html = CHAPTER;
+        let document = visdom::Vis::load(html).unwrap();
+        let novel = PiaotianNet::new("https://www.piaotian.net/html/14/14881/").unwrap();
+        let chapter = novel.get_chapter(&document, "1").unwrap();
+        assert_eq!(chapter.order, "1".to_string());
+        assert_eq!(chapter.title, "第一章 老地方".to_string());
+        let chapter = novel.process_chapter(chapter);
+        dbg!(&chapter.text);
+        assert!(chapter.text.starts_with("六月的首都日渐炎热。"));
+        assert!(chapter.text.ends_with("“开个机子。”"));
+        assert!(!chapter.text.contains("飘天文学"));
+    }
+
+    #[test]
+    fn test_get_next_page() {
+        let html = CHAPTER;
+        let document = visdom::Vis::load(html).unwrap();
+        let novel = PiaotianNet::new("https://www.piaotian.net/html/14/14881/").unwrap();
+        let url = novel.get_next_page(&document).unwrap();
+        assert_eq!(url, None);
+    }
+}
@@ -1,12 +1,12 @@
-/// 全本同人 <https://www.qbtr.cc/>
-use super::{Book, Chapter, NovelError, Noveler};
-//use regex::Regex;
+//! 全本同人 <https://www.qbtr.cc/>
+use super::{Book, Chapter, NovelError, Noveler, TextProcessor};
 use std::fmt::{self, Display};
 use url::Url;
 use visdom::types::Elements;
 
 pub(crate) struct Qbtr {
     base: Url,
+    processor: TextProcessor,
 }
 
 impl Qbtr {
@@ -24,7 +24,12 @@ impl Qbtr {
 
         base.set_query(None);
 
-        Ok(Self { base })
+        let processor = TextProcessor::new()
+            .add_line_split_and_trim(&['\n'])
+            .add_filter_empty_lines()
+            .add_skip_first_n_lines(2);
+
+        Ok(Self { base, processor })
     }
 }
 
@@ -35,6 +40,10 @@ impl Display for Qbtr {
 }
 
 impl Noveler for Qbtr {
+    fn base_url(&self) -> Option<&Url> {
+        Some(&self.base)
+    }
+
     fn need_encoding(&self) -> Option<&'static encoding_rs::Encoding> {
         Some(encoding_rs::GBK)
     }
@@ -45,7 +54,17 @@ impl Noveler for Qbtr {
 
         let selector = r"div.date > span";
         let author = document.find(selector).text().replace("作者：", "");
-        Ok(Book { name, author })
+        Ok(Book {
+            name,
+            author,
+            ..Book::default()
+        })
+    }
+
+    fn get_synopsis(&self, document: &Elements) -> Option<String> {
+        let selector = r"div.infos > p";
+        let synopsis = document.find(selector).text().trim().to_string();
+        (!synopsis.is_empty()).then_some(synopsis)
     }
 
     fn get_chapter_urls_sorted(&self, document: &Elements) -> Result<Vec<Url>, NovelError> {
@@ -82,17 +101,8 @@ impl Noveler for Qbtr {
         Ok(None)
     }
 
-    fn process_chapter(&self, chapter: Chapter) -> Chapter {
-        let mut text = chapter.text;
-        text = text
-            .split(['\n'])
-            .map(str::trim)
-            .filter(|s| !s.is_empty())
-            .skip(2)
-            .collect::<Vec<&str>>()
-            .join("\n");
-
-        Chapter { text, ..chapter }
+    fn process_chapter_text(&self, text: &str) -> String {
+        self.processor.process(text)
     }
 }
 
@@ -119,11 +129,23 @@ mod tests {
             book,
             Book {
                 name: "我的大宝剑".to_string(),
-                author: "学霸殿下".to_string()
+                author: "学霸殿下".to_string(),
+                ..Book::default()
             }
         );
     }
 
+    #[test]
+    fn test_get_book_details() {
+        let novel = Qbtr::new("https://www.qbtr.cc/tongren/3655.html").unwrap();
+        let (html, _, _) = novel.need_encoding().unwrap().decode(CONTENTS);
+        let document = visdom::Vis::load(html).unwrap();
+        let book = novel.get_book(&document).unwrap();
+        let synopsis = book.synopsis.unwrap();
+        assert!(synopsis.starts_with("始皇历1838年"));
+        assert!(synopsis.ends_with("就从他重获自由的两年后开始。"));
+    }
+
     #[test]
     fn test_get_chapter_urls_sorted() {
         let novel = Qbtr::new("https://www.qbtr.cc/tongren/3655.html").unwrap();
@@ -0,0 +1,333 @@
+//! 起點中文網 <https://www.qidian.com/>
+use super::{Book, Chapter, NovelError, Noveler, TextProcessor};
+use reqwest::Client;
+use serde::Deserialize;
+use std::fmt::{self, Display};
+use url::Url;
+use visdom::types::Elements;
+
+pub(crate) struct Qidian {
+    base: Url,
+    book_id: String,
+    processor: TextProcessor,
+}
+
+/// response of `/ajax/book/category`
+#[derive(Debug, Deserialize)]
+struct Category {
+    data: CategoryData,
+}
+
+#[derive(Debug, Deserialize)]
+struct CategoryData {
+    vs: Vec<Volume>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Volume {
+    cs: Vec<CategoryChapter>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CategoryChapter {
+    id: u64,
+}
+
+impl Qidian {
+    pub(crate) fn new(url: &str) -> Result<Self, NovelError> {
+        let mut base = Url::parse(url)?;
+
+        // https://book.qidian.com/info/{bookId}/
+        let book_id = base
+            .path_segments()
+            .and_then(|mut segments| segments.rfind(|s| !s.is_empty()))
+            .ok_or(NovelError::NotFound("book id".to_string()))?
+            .to_string();
+
+        match base.path_segments_mut() {
+            Ok(mut path) => {
+                path.clear();
+            }
+            Err(()) => {
+                return Err(NovelError::CannotBeABase(url.to_string()));
+            }
+        }
+        base.set_query(None);
+
+        let processor = TextProcessor::new()
+            .add_regex_replacement(r"(?m)^.*(?:qidian\.com|起[點点]中文[網网]).*$", "")?
+            .add_line_split_and_trim(&['\n', '\u{3000}', '\r'])
+            .add_filter_empty_lines();
+
+        Ok(Self {
+            base,
+            book_id,
+            processor,
+        })
+    }
+
+    fn category_url(&self) -> Result<Url, NovelError> {
+        let mut url = self.base.join("/ajax/book/category")?;
+        url.query_pairs_mut().append_pair("bookId", &self.book_id);
+        Ok(url)
+    }
+
+    fn parse_category(&self, json: &str) -> Result<Vec<Url>, NovelError> {
+        let category: Category = serde_json::from_str(json)?;
+        category
+            .data
+            .vs
+            .into_iter()
+            .flat_map(|volume| volume.cs)
+            .map(|chapter| {
+                Url::parse(&format!(
+                    "https://www.qidian.com/chapter/{}/{}/",
+                    self.book_id, chapter.id
+                ))
+                .map_err(NovelError::ParseError)
+            })
+            .collect()
+    }
+
+    async fn fetch_category(&self, client: Client) -> Result<Vec<Url>, NovelError> {
+        let json = client
+            .get(self.category_url()?)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        self.parse_category(&json)
+    }
+}
+
+impl Display for Qidian {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "起點中文網")
+    }
+}
+
+impl Noveler for Qidian {
+    fn base_url(&self) -> Option<&Url> {
+        Some(&self.base)
+    }
+
+    fn get_book_info(&self, document: &Elements) -> Result<Book, NovelError> {
+        let selector = r#"meta[property="og:novel:book_name"]"#;
+        let name = document
+            .find(selector)
+            .attr("content")
+            .ok_or(NovelError::NotFound("book_name content".to_string()))?
+            .to_string();
+
+        let selector = r#"meta[property="og:novel:author"]"#;
+        let author = document
+            .find(selector)
+            .attr("content")
+            .ok_or(NovelError::NotFound("author content".to_string()))?
+            .to_string();
+        Ok(Book {
+            name,
+            author,
+            ..Book::default()
+        })
+    }
+
+    fn get_synopsis(&self, document: &Elements) -> Option<String> {
+        let selector = r#"meta[property="og:description"]"#;
+        Some(document.find(selector).attr("content")?.to_string())
+    }
+
+    fn get_cover_url(&self, document: &Elements) -> Result<Option<Url>, NovelError> {
+        let selector = r#"meta[property="og:image"]"#;
+        let Some(src) = document.find(selector).attr("content") else {
+            return Ok(None);
+        };
+        Ok(Some(self.base.join(&src.to_string())?))
+    }
+
+    fn get_tags(&self, document: &Elements) -> Vec<String> {
+        let selector = r#"meta[property="og:novel:category"]"#;
+        document
+            .find(selector)
+            .attr("content")
+            .map(|category| vec![category.to_string()])
+            .unwrap_or_default()
+    }
+
+    // the book is on book.qidian.com, the chapters on www.qidian.com
+    fn chapter_url_is_valid(&self, url: &Url) -> bool {
+        url.host_str()
+            .is_some_and(|host| host == "qidian.com" || host.ends_with(".qidian.com"))
+            && url.path() != "/"
+    }
+
+    fn get_chapter_urls_sorted(&self, document: &Elements) -> Result<Vec<Url>, NovelError> {
+        let selector = r"#allCatalog li a";
+        document
+            .find(selector)
+            .into_iter()
+            .map(|x| {
+                x.get_attribute("href")
+                    .map(|attr| attr.to_string())
+                    .ok_or(NovelError::NotFound("href".to_string()))
+            })
+            .map(|x| x.and_then(|url_str| self.base.join(&url_str).map_err(NovelError::ParseError)))
+            .collect()
+    }
+
+    async fn fetch_chapter_urls_sorted(
+        &self,
+        client: Client,
+        document: &Elements<'_>,
+    ) -> Result<Vec<Url>, NovelError> {
+        // the chapter list is rendered by javascript, the api is more reliable
+        match self.fetch_category(client).await {
+            Ok(urls) if !urls.is_empty() => Ok(urls),
+            _ => self.get_chapter_urls_sorted(document),
+        }
+    }
+
+    fn get_chapter(&self, document: &Elements, order: &str) -> Result<Chapter, NovelError> {
+        let selector = r"h1.title";
+        let title = document.find(selector).text().trim().to_string();
+
+        let doc = document.cloned();
+        doc.find("div.read-content script").remove();
+        doc.find("div.read-content .review").remove();
+        let selector = r"div.read-content p";
+        let text: String = doc
+            .find(selector)
+            .into_iter()
+            .map(|x| x.text().trim().to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let order = order.to_string();
+        Ok(Chapter { order, title, text })
+    }
+
+    fn get_next_page(&self, _document: &Elements) -> Result<Option<Url>, NovelError> {
+        Ok(None)
+    }
+
+    fn process_chapter_text(&self, text: &str) -> String {
+        self.processor.process(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static CONTENTS: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/qidian/contents.html"
+    ));
+    static CATEGORY: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/qidian/category.json"
+    ));
+    static CHAPTER: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/qidian/chapter.html"
+    ));
+
+    #[test]
+    fn test_new() {
+        let novel = Qidian::new("https://book.qidian.com/info/1035420986/").unwrap();
+        assert_eq!(novel.book_id, "1035420986");
+        assert_eq!(
+            novel.category_url().unwrap(),
+            Url::parse("https://book.qidian.com/ajax/book/category?bookId=1035420986").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_get_book_info() {
+        let html = CONTENTS;
+        let document = visdom::Vis::load(html).unwrap();
+        let novel = Qidian::new("https://book.qidian.com/info/1035420986/").unwrap();
+        let book = novel.get_book_info(&document).unwrap();
+        assert_eq!(
+            book,
+            Book {
+                name: "夜的命名術".to_string(),
+                author: "會說話的肘子".to_string(),
+                ..Book::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_get_book_details() {
+        let html = CONTENTS;
+        let document = visdom::Vis::load(html).unwrap();
+        let novel = Qidian::new("https://book.qidian.com/info/1035420986/").unwrap();
+        let book = novel.get_book(&document).unwrap();
+        assert_eq!(
+            book.synopsis.as_deref(),
+            Some("夜幕降臨，世界變成了兩個。表世界與裡世界的時間行者，開始了新的旅程。")
+        );
+        assert_eq!(
+            book.cover_url,
+            Some(Url::parse("https://bookcover.yuewen.com/qdbimg/349573/1035420986/180").unwrap())
+        );
+        assert_eq!(book.tags, ["都市"]);
+    }
+
+    #[test]
+    fn test_parse_category() {
+        let novel = Qidian::new("https://book.qidian.com/info/1035420986/").unwrap();
+        let urls = novel.parse_category(CATEGORY).unwrap();
+        assert_eq!(urls.len(), 3);
+        assert_eq!(
+            urls.first().unwrap(),
+            &Url::parse("https://www.qidian.com/chapter/1035420986/694135582/").unwrap()
+        );
+        assert_eq!(
+            urls.last().unwrap(),
+            &Url::parse("https://www.qidian.com/chapter/1035420986/694297862/").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_get_chapter_urls_sorted() {
+        let html = CONTENTS;
+        let document = visdom::Vis::load(html).unwrap();
+        let novel = Qidian::new("https://book.qidian.com/info/1035420986/").unwrap();
+        let urls = novel.get_chapter_urls_sorted(&document).unwrap();
+        assert_eq!(
+            urls.first().unwrap(),
+            &Url::parse("https://www.qidian.com/chapter/1035420986/694135582/").unwrap()
+        );
+        assert_eq!(
+            urls.last().unwrap(),
+            &Url::parse("https://www.qidian.com/chapter/1035420986/694297862/").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_get_chapter_content() {
+        let html = CHAPTER;
+        let document = visdom::Vis::load(html).unwrap();
+        let novel = Qidian::new("https://book.qidian.com/info/1035420986/").unwrap();
+        let chapter = novel.get_chapter(&document, "1").unwrap();
+        assert_eq!(chapter.order, "1".to_string());
+        assert_eq!(chapter.title, "第一章 表世界".to_string());
+        let chapter = novel.process_chapter(chapter);
+        dbg!(&chapter.text);
+        assert!(chapter.text.starts_with("有人曾經說過"));
+        assert!(chapter.text.ends_with("慶塵看著窗外。"));
+        assert!(!chapter.text.contains("起點"));
+    }
+
+    #[test]
+    fn test_get_next_page() {
+        let html = CHAPTER;
+        let document = visdom::Vis::load(html).unwrap();
+        let novel = Qidian::new("https://book.qidian.com/info/1035420986/").unwrap();
+        let url = novel.get_next_page(&document).unwrap();
+        assert_eq!(url, None);
+    }
+}
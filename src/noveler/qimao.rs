@@ -0,0 +1,380 @@
+//! 七貓小說 <https://www.qimao.com/>
+use super::{Book, Chapter, NovelError, Noveler, TextProcessor};
+use reqwest::Client;
+use serde::Deserialize;
+use std::fmt::{self, Display};
+use url::Url;
+use visdom::types::Elements;
+
+/// the chapter list and the chapters come from the api of the app,
+/// the paid chapters need the `jwt` cookie of a logged in session
+const API_BASE: &str = "https://api-bc.wtzw.com/";
+
+pub(crate) struct Qimao {
+    base: Url,
+    api_base: Url,
+    book_id: String,
+    processor: TextProcessor,
+}
+
+/// response of `/api/v1/chapter/chapter-list`
+#[derive(Debug, Deserialize)]
+struct ChaptersResponse {
+    data: ChaptersData,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChaptersData {
+    chapter_lists: Vec<ChapterInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChapterInfo {
+    id: String,
+}
+
+/// response of `/api/v1/chapter/content`
+#[derive(Debug, Deserialize)]
+struct ContentResponse {
+    data: ContentData,
+}
+
+#[derive(Debug, Deserialize)]
+struct ContentData {
+    title: String,
+    content: String,
+}
+
+impl Qimao {
+    pub(crate) fn new(url: &str) -> Result<Self, NovelError> {
+        let mut base = Url::parse(url)?;
+
+        // https://www.qimao.com/shuku/{bookId}/
+        let book_id = base
+            .path_segments()
+            .and_then(|mut segments| segments.rfind(|s| !s.is_empty()))
+            .ok_or(NovelError::NotFound("book id".to_string()))?
+            .to_string();
+
+        match base.path_segments_mut() {
+            Ok(mut path) => {
+                path.clear();
+            }
+            Err(()) => {
+                return Err(NovelError::CannotBeABase(url.to_string()));
+            }
+        }
+        base.set_query(None);
+
+        let processor = TextProcessor::new()
+            .add_regex_replacement(r"</?p[^>]*>|<br\s*/?>", "\n")?
+            .add_regex_replacement(r"(?m)^.*(?:qimao\.com|七[猫貓]小[说說]).*$", "")?
+            .add_line_split_and_trim(&['\n', '\u{a0}', '\u{3000}', '\r'])
+            .add_filter_empty_lines();
+
+        Ok(Self {
+            base,
+            api_base: Url::parse(API_BASE)?,
+            book_id,
+            processor,
+        })
+    }
+
+    fn chapters_url(&self) -> Result<Url, NovelError> {
+        let mut url = self.api_base.join("/api/v1/chapter/chapter-list")?;
+        url.query_pairs_mut().append_pair("id", &self.book_id);
+        Ok(url)
+    }
+
+    fn parse_chapters(&self, json: &str) -> Result<Vec<Url>, NovelError> {
+        let response: ChaptersResponse = serde_json::from_str(json)?;
+        response
+            .data
+            .chapter_lists
+            .into_iter()
+            .map(|chapter| {
+                let mut url = self.api_base.join("/api/v1/chapter/content")?;
+                url.query_pairs_mut()
+                    .append_pair("id", &self.book_id)
+                    .append_pair("chapterId", &chapter.id);
+                Ok(url)
+            })
+            .collect()
+    }
+
+    fn parse_content(json: &str, order: &str) -> Result<Chapter, NovelError> {
+        let response: ContentResponse = serde_json::from_str(json)?;
+        Ok(Chapter {
+            order: order.to_string(),
+            title: response.data.title.trim().to_string(),
+            text: response.data.content,
+        })
+    }
+
+    async fn fetch_chapters(&self, client: Client) -> Result<Vec<Url>, NovelError> {
+        let json = client
+            .get(self.chapters_url()?)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        self.parse_chapters(&json)
+    }
+}
+
+impl Display for Qimao {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "七貓小說")
+    }
+}
+
+impl Noveler for Qimao {
+    fn base_url(&self) -> Option<&Url> {
+        Some(&self.base)
+    }
+
+    // the chapters are json from the api, not html pages
+    async fn process_url(
+        &self,
+        client: Client,
+        order: &str,
+        url: Url,
+        _encoding: Option<&'static encoding_rs::Encoding>,
+    ) -> Result<(Chapter, Option<Url>), NovelError> {
+        let json = client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        let chapter = Self::parse_content(&json, order)?;
+        Ok((self.process_chapter(chapter), None))
+    }
+
+    fn get_book_info(&self, document: &Elements) -> Result<Book, NovelError> {
+        let selector = r"div.book-information div.title span.txt";
+        let name = document.find(selector).text().trim().to_string();
+
+        let selector = r"div.book-information div.sub-title span.txt a";
+        let author = document.find(selector).text().trim().to_string();
+        if author.is_empty() {
+            return Err(NovelError::NotFound("author".to_string()));
+        }
+        Ok(Book {
+            name,
+            author,
+            ..Book::default()
+        })
+    }
+
+    fn get_synopsis(&self, document: &Elements) -> Option<String> {
+        let selector = r"div.book-introduction p.intro";
+        let synopsis = document.find(selector).text().trim().to_string();
+        (!synopsis.is_empty()).then_some(synopsis)
+    }
+
+    fn get_cover_url(&self, document: &Elements) -> Result<Option<Url>, NovelError> {
+        let selector = r"div.wrap-pic img";
+        let Some(src) = document.find(selector).attr("src") else {
+            return Ok(None);
+        };
+        Ok(Some(self.base.join(&src.to_string())?))
+    }
+
+    fn get_tags(&self, document: &Elements) -> Vec<String> {
+        let selector = r"div.book-information div.tags-wrap a";
+        document
+            .find(selector)
+            .into_iter()
+            .map(|x| x.text().trim().to_string())
+            .filter(|tag| !tag.is_empty())
+            .collect()
+    }
+
+    // the book is on www.qimao.com, the chapters on the api of the app
+    fn chapter_url_is_valid(&self, url: &Url) -> bool {
+        url.host_str().is_some_and(|host| {
+            host == "qimao.com" || host.ends_with(".qimao.com") || host.ends_with(".wtzw.com")
+        }) && url.path() != "/"
+    }
+
+    fn get_chapter_urls_sorted(&self, document: &Elements) -> Result<Vec<Url>, NovelError> {
+        let selector = r"ul.qm-chapter-list li a";
+        document
+            .find(selector)
+            .into_iter()
+            .map(|x| {
+                x.get_attribute("href")
+                    .map(|attr| attr.to_string())
+                    .ok_or(NovelError::NotFound("href".to_string()))
+            })
+            .map(|x| x.and_then(|url_str| self.base.join(&url_str).map_err(NovelError::ParseError)))
+            .collect()
+    }
+
+    async fn fetch_chapter_urls_sorted(
+        &self,
+        client: Client,
+        document: &Elements<'_>,
+    ) -> Result<Vec<Url>, NovelError> {
+        // the contents page only lists the first chapters
+        match self.fetch_chapters(client).await {
+            Ok(urls) if !urls.is_empty() => Ok(urls),
+            _ => self.get_chapter_urls_sorted(document),
+        }
+    }
+
+    fn chapter_title_selector(&self) -> &'static str {
+        r"div.chapter-detail-wrap-title h2"
+    }
+
+    fn chapter_content_selector(&self) -> &'static str {
+        r"div.chapter-detail-wrap-content div.article"
+    }
+
+    fn get_next_page(&self, _document: &Elements) -> Result<Option<Url>, NovelError> {
+        Ok(None)
+    }
+
+    fn process_chapter_text(&self, text: &str) -> String {
+        self.processor.process(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::build_client;
+    use super::*;
+    use reqwest::header::{HeaderMap, HeaderValue, COOKIE};
+
+    static CONTENTS: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/qimao/contents.html"
+    ));
+    static CHAPTERS: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/qimao/chapters.json"
+    ));
+    static CONTENT: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/qimao/content.json"
+    ));
+
+    #[test]
+    fn test_new() {
+        let novel = Qimao::new("https://www.qimao.com/shuku/1846389/").unwrap();
+        assert_eq!(novel.book_id, "1846389");
+        assert_eq!(
+            novel.chapters_url().unwrap(),
+            Url::parse("https://api-bc.wtzw.com/api/v1/chapter/chapter-list?id=1846389").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_get_book_info() {
+        let html = CONTENTS;
+        let document = visdom::Vis::load(html).unwrap();
+        let novel = Qimao::new("https://www.qimao.com/shuku/1846389/").unwrap();
+        let book = novel.get_book_info(&document).unwrap();
+        assert_eq!(
+            book,
+            Book {
+                name: "我在大明當捕快".to_string(),
+                author: "燈下觀書".to_string(),
+                ..Book::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_get_book_details() {
+        let html = CONTENTS;
+        let document = visdom::Vis::load(html).unwrap();
+        let novel = Qimao::new("https://www.qimao.com/shuku/1846389/").unwrap();
+        let book = novel.get_book(&document).unwrap();
+        assert_eq!(
+            book.synopsis.as_deref(),
+            Some("一把繡春刀，一身捕快服，且看少年如何在京城闖出一片天地。")
+        );
+        assert_eq!(
+            book.cover_url,
+            Some(
+                Url::parse("https://cdn.wtzw.com/bookimg/public/images/cover/1846389.jpg").unwrap()
+            )
+        );
+        assert_eq!(book.tags, ["歷史", "穿越"]);
+    }
+
+    #[test]
+    fn test_parse_chapters() {
+        let novel = Qimao::new("https://www.qimao.com/shuku/1846389/").unwrap();
+        let urls = novel.parse_chapters(CHAPTERS).unwrap();
+        assert_eq!(urls.len(), 3);
+        assert_eq!(
+            urls.first().unwrap(),
+            &Url::parse("https://api-bc.wtzw.com/api/v1/chapter/content?id=1846389&chapterId=1001")
+                .unwrap()
+        );
+        assert_eq!(
+            urls.last().unwrap(),
+            &Url::parse("https://api-bc.wtzw.com/api/v1/chapter/content?id=1846389&chapterId=1003")
+                .unwrap()
+        );
+        assert!(urls.iter().all(|url| novel.chapter_url_is_valid(url)));
+    }
+
+    #[test]
+    fn test_get_chapter_urls_sorted() {
+        let html = CONTENTS;
+        let document = visdom::Vis::load(html).unwrap();
+        let novel = Qimao::new("https://www.qimao.com/shuku/1846389/").unwrap();
+        let urls = novel.get_chapter_urls_sorted(&document).unwrap();
+        assert_eq!(urls.len(), 2);
+        assert_eq!(
+            urls.first().unwrap(),
+            &Url::parse("https://www.qimao.com/shuku/1846389-1001/").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_content() {
+        let novel = Qimao::new("https://www.qimao.com/shuku/1846389/").unwrap();
+        let chapter = Qimao::parse_content(CONTENT, "1").unwrap();
+        assert_eq!(chapter.order, "1".to_string());
+        assert_eq!(chapter.title, "第一章 繡春刀".to_string());
+        let chapter = novel.process_chapter(chapter);
+        dbg!(&chapter.text);
+        assert!(chapter.text.starts_with("京城的雨下了三天。"));
+        assert!(chapter.text.ends_with("林平握緊了腰間的刀。"));
+        assert!(!chapter.text.contains("<p>"));
+        assert!(!chapter.text.contains("七貓"));
+    }
+
+    #[tokio::test]
+    async fn test_process_url_with_jwt_cookie() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/api/v1/chapter/content")
+            .match_query(mockito::Matcher::Any)
+            .match_header("cookie", "jwt=token")
+            .with_body(CONTENT)
+            .create_async()
+            .await;
+
+        let mut headers = HeaderMap::new();
+        headers.insert(COOKIE, HeaderValue::from_static("jwt=token"));
+        let client = build_client(None, Some(headers), None).unwrap();
+        let novel = Qimao::new("https://www.qimao.com/shuku/1846389/").unwrap();
+        let url = Url::parse(&format!(
+            "{}/api/v1/chapter/content?id=1846389&chapterId=1001",
+            server.url()
+        ))
+        .unwrap();
+        let (chapter, next_page) = novel.process_url(client, "1", url, None).await.unwrap();
+        assert_eq!(chapter.title, "第一章 繡春刀".to_string());
+        assert_eq!(next_page, None);
+        mock.assert_async().await;
+    }
+}
@@ -0,0 +1,167 @@
+//! Readm <https://www.readm.org/light-novel/>
+use super::{Book, NovelError, Noveler, TextProcessor};
+use std::fmt::{self, Display};
+use url::Url;
+use visdom::types::Elements;
+
+pub(crate) struct Readm {
+    base: Url,
+    processor: TextProcessor,
+}
+
+impl Readm {
+    pub(crate) fn new(url: &str) -> Result<Self, NovelError> {
+        let mut base = Url::parse(url)?;
+
+        match base.path_segments_mut() {
+            Ok(mut path) => {
+                path.clear();
+            }
+            Err(()) => {
+                return Err(NovelError::CannotBeABase(url.to_string()));
+            }
+        }
+        base.set_query(None);
+
+        let processor = TextProcessor::new()
+            .add_line_split_and_trim(&['\n', '\u{a0}', '\r'])
+            .add_filter_empty_lines();
+
+        Ok(Self { base, processor })
+    }
+}
+
+impl Display for Readm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Readm")
+    }
+}
+
+impl Noveler for Readm {
+    fn base_url(&self) -> Option<&Url> {
+        Some(&self.base)
+    }
+
+    fn get_book_info(&self, document: &Elements) -> Result<Book, NovelError> {
+        let selector = r"h1.page-title";
+        let name = document.find(selector).text().trim().to_string();
+
+        let selector = r"div.author a";
+        let author = document.find(selector).text().trim().to_string();
+        Ok(Book {
+            name,
+            author,
+            ..Book::default()
+        })
+    }
+
+    fn get_chapter_urls_sorted(&self, document: &Elements) -> Result<Vec<Url>, NovelError> {
+        let selector = r"ul.volumes a";
+        document
+            .find(selector)
+            .into_iter()
+            .map(|x| {
+                x.get_attribute("href")
+                    .map(|attr| attr.to_string())
+                    .ok_or(NovelError::NotFound("href".to_string()))
+            })
+            .map(|x| x.and_then(|url_str| self.base.join(&url_str).map_err(NovelError::ParseError)))
+            .collect()
+    }
+
+    fn chapter_title_selector(&self) -> &'static str {
+        r"h2.chapter-title"
+    }
+
+    fn chapter_content_selector(&self) -> &'static str {
+        r"div.chapter-container"
+    }
+
+    fn get_next_page(&self, _document: &Elements) -> Result<Option<Url>, NovelError> {
+        Ok(None)
+    }
+
+    fn process_chapter_text(&self, text: &str) -> String {
+        self.processor.process(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static CONTENTS: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/readm/contents.html"
+    ));
+    static CHAPTER: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/readm/chapter.html"
+    ));
+
+    #[test]
+    fn test_get_book_info() {
+        let html = CONTENTS;
+        let document = visdom::Vis::load(html).unwrap();
+        let novel =
+            Readm::new("https://www.readm.org/light-novel/the-beginning-after-the-end").unwrap();
+        let book = novel.get_book_info(&document).unwrap();
+        assert_eq!(
+            book,
+            Book {
+                name: "The Beginning After The End".to_string(),
+                author: "TurtleMe".to_string(),
+                ..Book::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_get_chapter_urls_sorted() {
+        let html = CONTENTS;
+        let document = visdom::Vis::load(html).unwrap();
+        let novel =
+            Readm::new("https://www.readm.org/light-novel/the-beginning-after-the-end").unwrap();
+        let urls = novel.get_chapter_urls_sorted(&document).unwrap();
+        assert_eq!(
+            urls.first().unwrap(),
+            &Url::parse("https://www.readm.org/light-novel/the-beginning-after-the-end/1").unwrap()
+        );
+        assert_eq!(
+            urls.last().unwrap(),
+            &Url::parse("https://www.readm.org/light-novel/the-beginning-after-the-end/3").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_get_chapter_content() {
+        let html = CHAPTER;
+        let document = visdom::Vis::load(html).unwrap();
+        let novel =
+            Readm::new("https://www.readm.org/light-novel/the-beginning-after-the-end").unwrap();
+        let chapter = novel.get_chapter(&document, "1").unwrap();
+        assert_eq!(chapter.order, "1".to_string());
+        assert_eq!(
+            chapter.title,
+            "Chapter 1: Light at the End of the Tunnel".to_string()
+        );
+        let chapter = novel.process_chapter(chapter);
+        dbg!(&chapter.text);
+        assert!(chapter
+            .text
+            .starts_with("Is this the light at the end of the tunnel?"));
+        assert!(chapter
+            .text
+            .ends_with("I opened my eyes and saw a blurry figure above me."));
+    }
+
+    #[test]
+    fn test_get_next_page() {
+        let html = CHAPTER;
+        let document = visdom::Vis::load(html).unwrap();
+        let novel =
+            Readm::new("https://www.readm.org/light-novel/the-beginning-after-the-end").unwrap();
+        let url = novel.get_next_page(&document).unwrap();
+        assert_eq!(url, None);
+    }
+}
@@ -0,0 +1,163 @@
+//! 書海小說網 <https://www.shuhai.com/>
+use super::{Book, NovelError, Noveler, TextProcessor};
+use std::fmt::{self, Display};
+use url::Url;
+use visdom::types::Elements;
+
+pub(crate) struct Shuhai {
+    base: Url,
+    processor: TextProcessor,
+}
+
+impl Shuhai {
+    pub(crate) fn new(url: &str) -> Result<Self, NovelError> {
+        let base = Url::parse(url)?;
+
+        let processor = TextProcessor::new()
+            .add_line_split_and_trim(&['\n', '\u{3000}', '\u{a0}', '\r'])
+            .add_filter_empty_lines();
+
+        Ok(Self { base, processor })
+    }
+}
+
+impl Display for Shuhai {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "書海小說網")
+    }
+}
+
+impl Noveler for Shuhai {
+    fn base_url(&self) -> Option<&Url> {
+        Some(&self.base)
+    }
+
+    fn need_encoding(&self) -> Option<&'static encoding_rs::Encoding> {
+        Some(encoding_rs::GBK)
+    }
+
+    fn get_book_info(&self, document: &Elements) -> Result<Book, NovelError> {
+        let selector = r"div.info h1";
+        let name = document.find(selector).text().trim().to_string();
+
+        let selector = r"div.info p:first-of-type a";
+        let author = document.find(selector).text().trim().to_string();
+        Ok(Book {
+            name,
+            author,
+            ..Book::default()
+        })
+    }
+
+    fn get_tags(&self, document: &Elements) -> Vec<String> {
+        let selector = r"div.info p:nth-of-type(2)";
+        let text = document.find(selector).text();
+        text.split_once('：')
+            .map(|(_, category)| vec![category.trim().to_string()])
+            .unwrap_or_default()
+    }
+
+    fn get_chapter_urls_sorted(&self, document: &Elements) -> Result<Vec<Url>, NovelError> {
+        let selector = r"dl dd a";
+        document
+            .find(selector)
+            .into_iter()
+            .map(|x| {
+                x.get_attribute("href")
+                    .map(|attr| attr.to_string())
+                    .ok_or(NovelError::NotFound("href".to_string()))
+            })
+            .map(|x| x.and_then(|url_str| self.base.join(&url_str).map_err(NovelError::ParseError)))
+            .collect()
+    }
+
+    fn chapter_title_selector(&self) -> &'static str {
+        r"h1.chapter"
+    }
+
+    fn get_next_page(&self, _document: &Elements) -> Result<Option<Url>, NovelError> {
+        Ok(None)
+    }
+
+    fn process_chapter_text(&self, text: &str) -> String {
+        self.processor.process(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static CONTENTS: &[u8] = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/shuhai/contents.html"
+    ));
+    static CHAPTER: &[u8] = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/shuhai/chapter.html"
+    ));
+
+    #[test]
+    fn test_get_book_info() {
+        let novel = Shuhai::new("https://www.shuhai.com/book/42851.htm").unwrap();
+        let (html, _, _) = novel.need_encoding().unwrap().decode(CONTENTS);
+        let document = visdom::Vis::load(html).unwrap();
+        let book = novel.get_book_info(&document).unwrap();
+        assert_eq!(
+            book,
+            Book {
+                name: "北城以北".to_string(),
+                author: "苏小暖".to_string(),
+                ..Book::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_get_book_details() {
+        let novel = Shuhai::new("https://www.shuhai.com/book/42851.htm").unwrap();
+        let (html, _, _) = novel.need_encoding().unwrap().decode(CONTENTS);
+        let document = visdom::Vis::load(html).unwrap();
+        let book = novel.get_book(&document).unwrap();
+        assert_eq!(book.tags, ["都市言情"]);
+    }
+
+    #[test]
+    fn test_get_chapter_urls_sorted() {
+        let novel = Shuhai::new("https://www.shuhai.com/book/42851.htm").unwrap();
+        let (html, _, _) = novel.need_encoding().unwrap().decode(CONTENTS);
+        let document = visdom::Vis::load(html).unwrap();
+        let urls = novel.get_chapter_urls_sorted(&document).unwrap();
+        assert_eq!(
+            urls.first().unwrap(),
+            &Url::parse("https://www.shuhai.com/read/42851/1.html").unwrap()
+        );
+        assert_eq!(
+            urls.last().unwrap(),
+            &Url::parse("https://www.shuhai.com/read/42851/3.html").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_get_chapter_content() {
+        let novel = Shuhai::new("https://www.shuhai.com/book/42851.htm").unwrap();
+        let (html, _, _) = novel.need_encoding().unwrap().decode(CHAPTER);
+        let document = visdom::Vis::load(html).unwrap();
+        let chapter = novel.get_chapter(&document, "1").unwrap();
+        assert_eq!(chapter.order, "1".to_string());
+        assert_eq!(chapter.title, "第一章 初雪".to_string());
+        let chapter = novel.process_chapter(chapter);
+        dbg!(&chapter.text);
+        assert!(chapter.text.starts_with("那年的第一场雪来得很早。"));
+        assert!(chapter.text.ends_with("她没有回头。"));
+    }
+
+    #[test]
+    fn test_get_next_page() {
+        let novel = Shuhai::new("https://www.shuhai.com/book/42851.htm").unwrap();
+        let (html, _, _) = novel.need_encoding().unwrap().decode(CHAPTER);
+        let document = visdom::Vis::load(html).unwrap();
+        let url = novel.get_next_page(&document).unwrap();
+        assert_eq!(url, None);
+    }
+}
@@ -0,0 +1,358 @@
+//! 大地讀書 <https://www.tadu.com/>
+use super::{Book, Chapter, NovelError, Noveler, TextProcessor};
+use reqwest::{Client, RequestBuilder};
+use serde::Deserialize;
+use std::fmt::{self, Display};
+use url::Url;
+use visdom::types::Elements;
+
+/// the chapter list and the chapters come from the api of the app
+const API_BASE: &str = "https://api.tadu.com/";
+
+pub(crate) struct Tadu {
+    base: Url,
+    api_base: Url,
+    book_id: String,
+    /// sent as a bearer token to the api, the paid chapters need a logged in one
+    token: Option<String>,
+    processor: TextProcessor,
+}
+
+/// response of `/api/book/{bookId}/chapters`
+#[derive(Debug, Deserialize)]
+struct ChaptersResponse {
+    data: ChaptersData,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChaptersData {
+    chapters: Vec<ChapterInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChapterInfo {
+    chapter_id: u64,
+}
+
+/// response of `/api/chapter/{chapterId}/content`
+#[derive(Debug, Deserialize)]
+struct ContentResponse {
+    data: ContentData,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ContentData {
+    chapter_name: String,
+    content: String,
+}
+
+impl Tadu {
+    pub(crate) fn new(url: &str, token: Option<String>) -> Result<Self, NovelError> {
+        let mut base = Url::parse(url)?;
+
+        // https://www.tadu.com/book/{bookId}/
+        let book_id = base
+            .path_segments()
+            .and_then(|mut segments| segments.rfind(|s| !s.is_empty()))
+            .ok_or(NovelError::NotFound("book id".to_string()))?
+            .to_string();
+
+        match base.path_segments_mut() {
+            Ok(mut path) => {
+                path.clear();
+            }
+            Err(()) => {
+                return Err(NovelError::CannotBeABase(url.to_string()));
+            }
+        }
+        base.set_query(None);
+
+        let processor = TextProcessor::new()
+            .add_regex_replacement(r"</?p[^>]*>|<br\s*/?>", "\n")?
+            .add_regex_replacement(
+                r"(?m)^.*(?:tadu\.com|塔[讀读]文[學学]|大地[讀读][書书]).*$",
+                "",
+            )?
+            .add_line_split_and_trim(&['\n', '\u{a0}', '\u{3000}', '\r'])
+            .add_filter_empty_lines();
+
+        Ok(Self {
+            base,
+            api_base: Url::parse(API_BASE)?,
+            book_id,
+            token,
+            processor,
+        })
+    }
+
+    fn chapters_url(&self) -> Result<Url, NovelError> {
+        Ok(self
+            .api_base
+            .join(&format!("/api/book/{}/chapters", self.book_id))?)
+    }
+
+    fn api_get(&self, client: &Client, url: Url) -> RequestBuilder {
+        let request = client.get(url);
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    fn parse_chapters(&self, json: &str) -> Result<Vec<Url>, NovelError> {
+        let response: ChaptersResponse = serde_json::from_str(json)?;
+        response
+            .data
+            .chapters
+            .into_iter()
+            .map(|chapter| {
+                self.api_base
+                    .join(&format!("/api/chapter/{}/content", chapter.chapter_id))
+                    .map_err(NovelError::ParseError)
+            })
+            .collect()
+    }
+
+    fn parse_content(json: &str, order: &str) -> Result<Chapter, NovelError> {
+        let response: ContentResponse = serde_json::from_str(json)?;
+        Ok(Chapter {
+            order: order.to_string(),
+            title: response.data.chapter_name.trim().to_string(),
+            text: response.data.content,
+        })
+    }
+
+    async fn fetch_chapters(&self, client: Client) -> Result<Vec<Url>, NovelError> {
+        let json = self
+            .api_get(&client, self.chapters_url()?)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        self.parse_chapters(&json)
+    }
+}
+
+impl Display for Tadu {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "大地讀書")
+    }
+}
+
+impl Noveler for Tadu {
+    fn base_url(&self) -> Option<&Url> {
+        Some(&self.base)
+    }
+
+    // the chapters are json from the api, not html pages
+    async fn process_url(
+        &self,
+        client: Client,
+        order: &str,
+        url: Url,
+        _encoding: Option<&'static encoding_rs::Encoding>,
+    ) -> Result<(Chapter, Option<Url>), NovelError> {
+        let json = self
+            .api_get(&client, url)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        let chapter = Self::parse_content(&json, order)?;
+        Ok((self.process_chapter(chapter), None))
+    }
+
+    fn get_book_info(&self, document: &Elements) -> Result<Book, NovelError> {
+        let selector = r#"meta[property="og:novel:book_name"]"#;
+        let name = document
+            .find(selector)
+            .attr("content")
+            .ok_or(NovelError::NotFound("book_name content".to_string()))?
+            .to_string();
+
+        let selector = r#"meta[property="og:novel:author"]"#;
+        let author = document
+            .find(selector)
+            .attr("content")
+            .ok_or(NovelError::NotFound("author content".to_string()))?
+            .to_string();
+        Ok(Book {
+            name,
+            author,
+            ..Book::default()
+        })
+    }
+
+    fn get_synopsis(&self, document: &Elements) -> Option<String> {
+        let selector = r#"meta[property="og:description"]"#;
+        Some(document.find(selector).attr("content")?.to_string())
+    }
+
+    fn get_cover_url(&self, document: &Elements) -> Result<Option<Url>, NovelError> {
+        let selector = r#"meta[property="og:image"]"#;
+        let Some(src) = document.find(selector).attr("content") else {
+            return Ok(None);
+        };
+        Ok(Some(self.base.join(&src.to_string())?))
+    }
+
+    // the book is on www.tadu.com, the chapters on api.tadu.com
+    fn chapter_url_is_valid(&self, url: &Url) -> bool {
+        url.host_str()
+            .is_some_and(|host| host == "tadu.com" || host.ends_with(".tadu.com"))
+            && url.path() != "/"
+    }
+
+    fn get_chapter_urls_sorted(&self, document: &Elements) -> Result<Vec<Url>, NovelError> {
+        let selector = r"div.chapter_list a";
+        document
+            .find(selector)
+            .into_iter()
+            .map(|x| {
+                x.get_attribute("href")
+                    .map(|attr| attr.to_string())
+                    .ok_or(NovelError::NotFound("href".to_string()))
+            })
+            .map(|x| x.and_then(|url_str| self.base.join(&url_str).map_err(NovelError::ParseError)))
+            .collect()
+    }
+
+    async fn fetch_chapter_urls_sorted(
+        &self,
+        client: Client,
+        document: &Elements<'_>,
+    ) -> Result<Vec<Url>, NovelError> {
+        // the contents page only shows the latest chapters
+        match self.fetch_chapters(client).await {
+            Ok(urls) if !urls.is_empty() => Ok(urls),
+            _ => self.get_chapter_urls_sorted(document),
+        }
+    }
+
+    fn get_chapter(&self, document: &Elements, order: &str) -> Result<Chapter, NovelError> {
+        let selector = r"div.chapter_title h4";
+        let title = document.find(selector).text().trim().to_string();
+
+        let selector = r"div#partContent p";
+        let text: String = document
+            .find(selector)
+            .into_iter()
+            .map(|x| x.text().trim().to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let order = order.to_string();
+        Ok(Chapter { order, title, text })
+    }
+
+    fn get_next_page(&self, _document: &Elements) -> Result<Option<Url>, NovelError> {
+        Ok(None)
+    }
+
+    fn process_chapter_text(&self, text: &str) -> String {
+        self.processor.process(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static CONTENTS: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/tadu/contents.html"
+    ));
+    static CHAPTERS: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/tadu/chapters.json"
+    ));
+    static CONTENT: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/tadu/content.json"
+    ));
+
+    #[test]
+    fn test_new() {
+        let novel = Tadu::new("https://www.tadu.com/book/892406/", None).unwrap();
+        assert_eq!(novel.book_id, "892406");
+        assert_eq!(
+            novel.chapters_url().unwrap(),
+            Url::parse("https://api.tadu.com/api/book/892406/chapters").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_get_book_info() {
+        let html = CONTENTS;
+        let document = visdom::Vis::load(html).unwrap();
+        let novel = Tadu::new("https://www.tadu.com/book/892406/", None).unwrap();
+        let book = novel.get_book_info(&document).unwrap();
+        assert_eq!(
+            book,
+            Book {
+                name: "山河社稷圖".to_string(),
+                author: "青衫取醉".to_string(),
+                ..Book::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_chapters() {
+        let novel = Tadu::new("https://www.tadu.com/book/892406/", None).unwrap();
+        let urls = novel.parse_chapters(CHAPTERS).unwrap();
+        assert_eq!(urls.len(), 3);
+        assert_eq!(
+            urls.first().unwrap(),
+            &Url::parse("https://api.tadu.com/api/chapter/59310001/content").unwrap()
+        );
+        assert_eq!(
+            urls.last().unwrap(),
+            &Url::parse("https://api.tadu.com/api/chapter/59310003/content").unwrap()
+        );
+        assert!(urls.iter().all(|url| novel.chapter_url_is_valid(url)));
+    }
+
+    #[test]
+    fn test_parse_content() {
+        let novel = Tadu::new("https://www.tadu.com/book/892406/", None).unwrap();
+        let chapter = Tadu::parse_content(CONTENT, "1").unwrap();
+        assert_eq!(chapter.order, "1".to_string());
+        assert_eq!(chapter.title, "第一章 少年".to_string());
+        let chapter = novel.process_chapter(chapter);
+        dbg!(&chapter.text);
+        assert!(chapter.text.starts_with("清晨的霧還沒有散。"));
+        assert!(chapter.text.ends_with("少年背起了竹簍。"));
+        assert!(!chapter.text.contains("<p>"));
+        assert!(!chapter.text.contains("tadu.com"));
+    }
+
+    #[tokio::test]
+    async fn test_process_url_with_token() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/api/chapter/59310001/content")
+            .match_header("authorization", "Bearer token")
+            .with_body(CONTENT)
+            .create_async()
+            .await;
+
+        let novel = Tadu::new(
+            "https://www.tadu.com/book/892406/",
+            Some("token".to_string()),
+        )
+        .unwrap();
+        let url = Url::parse(&format!("{}/api/chapter/59310001/content", server.url())).unwrap();
+        let (chapter, next_page) = novel
+            .process_url(Client::new(), "1", url, None)
+            .await
+            .unwrap();
+        assert_eq!(chapter.title, "第一章 少年".to_string());
+        assert_eq!(next_page, None);
+        mock.assert_async().await;
+    }
+}
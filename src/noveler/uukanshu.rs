@@ -1,13 +1,13 @@
-/// UU看書 <https://www.uukanshu.com/>
-use super::{Book, Chapter, NovelError, Noveler};
-use regex::Regex;
+//! UU看書 <https://www.uukanshu.com/>
+use super::{get_html_and_fix_encoding, Book, NovelError, Noveler, PageEncoding, TextProcessor};
+use reqwest::Client;
 use std::fmt::{self, Display};
 use url::Url;
 use visdom::types::Elements;
 
 pub(crate) struct UUkanshu {
     base: Url,
-    replacer: (Vec<Regex>, Vec<String>),
+    processor: TextProcessor,
 }
 
 impl UUkanshu {
@@ -27,25 +27,61 @@ impl UUkanshu {
         let patterns = [
             r"(?s)如果喜歡.*，請把網址發給您的朋友。.*",
             r"(?s)如果喜欢.*，请把网址发给您的朋友。.*",
-            r"[wｗ]{3}[．\.][ｕu][ｕu][ｋk][ａa][ｎn][ｓs][ｈh][ｕu][．\.][ｃc][ｏo][ｍm]",
-            r"[ｕuＵU]{2}看书[ ]*",
-            r"[ｕuＵU]{2}看書[ ]*",
-            r"請記住本書首發域名：。：",
-            r"请记住本书首发域名：。：",
+            r"www\.uukanshu\.com",
+            r"[uU]{2}看[书書] *",
+            r"[請请][記记]住本[書书]首[發发]域名：。：",
         ];
-        let replace_with = ["", "", "", "", "", "", ""]
+        // the chinese punctuation and the ideographic space between paragraphs stay full-width
+        let processor = patterns
             .into_iter()
-            .map(std::string::ToString::to_string)
-            .collect();
-        let regexes = patterns
-            .into_iter()
-            .map(Regex::new)
-            .collect::<Result<Vec<_>, _>>()?;
+            .try_fold(
+                TextProcessor::new().add_fullwidth_normalization(&[
+                    '，', '！', '？', '：', '；', '（', '）', '\u{3000}',
+                ]),
+                |processor, pattern| processor.add_regex_replacement(pattern, ""),
+            )?
+            .add_line_split_and_trim(&['\n', '\u{3000}', '\u{a0}', '\r'])
+            .add_filter_empty_lines()
+            .add_step(|text| {
+                text.split("  ")
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .collect::<Vec<&str>>()
+                    .join("\n")
+            });
 
-        Ok(Self {
-            base,
-            replacer: (regexes, replace_with),
-        })
+        Ok(Self { base, processor })
+    }
+}
+
+impl UUkanshu {
+    /// every book of the author, `(name, contents url)`, from the page the author link goes to
+    pub(crate) async fn get_series_list(
+        client: Client,
+        author_url: Url,
+    ) -> Result<Vec<(String, Url)>, NovelError> {
+        let document =
+            get_html_and_fix_encoding(client, author_url.clone(), PageEncoding::Response).await?;
+        let document = visdom::Vis::load(document)?;
+        Self::parse_series_list(&document, &author_url)
+    }
+
+    fn parse_series_list(
+        document: &Elements,
+        author_url: &Url,
+    ) -> Result<Vec<(String, Url)>, NovelError> {
+        let selector = r"span.bookname a";
+        document
+            .find(selector)
+            .into_iter()
+            .map(|x| {
+                let href = x
+                    .get_attribute("href")
+                    .ok_or(NovelError::NotFound("href".to_string()))?
+                    .to_string();
+                Ok((x.text().trim().to_string(), author_url.join(&href)?))
+            })
+            .collect()
     }
 }
 
@@ -56,6 +92,10 @@ impl Display for UUkanshu {
 }
 
 impl Noveler for UUkanshu {
+    fn base_url(&self) -> Option<&Url> {
+        Some(&self.base)
+    }
+
     fn get_book_info(&self, document: &Elements) -> Result<Book, NovelError> {
         let selector = r"dd.jieshao_content > h1 > a";
         let name = document
@@ -66,12 +106,48 @@ impl Noveler for UUkanshu {
 
         let selector = r"dd.jieshao_content > h2 > a";
         let author = document.find(selector).text();
-        Ok(Book { name, author })
+        Ok(Book {
+            name,
+            author,
+            ..Book::default()
+        })
+    }
+
+    fn get_synopsis(&self, document: &Elements) -> Option<String> {
+        let selector = r"dd.jieshao_content > h3";
+        let text = document.find(selector).text();
+        let synopsis = text
+            .split_once("簡介：")
+            .or_else(|| text.split_once("简介："))
+            .map_or(text.as_str(), |(_, synopsis)| synopsis)
+            .replace("http://www.uukanshu.com", "")
+            .replace("www.uukanshu.com", "")
+            .split(['\n', '\u{3000}'])
+            .map(str::trim)
+            .filter(|s| !s.is_empty() && !s.starts_with('－'))
+            .collect::<Vec<_>>()
+            .join("\n");
+        (!synopsis.is_empty()).then_some(synopsis)
+    }
+
+    fn get_cover_url(&self, document: &Elements) -> Result<Option<Url>, NovelError> {
+        let selector = r"dt.jieshao-img img";
+        let Some(src) = document.find(selector).attr("src") else {
+            return Ok(None);
+        };
+        Ok(Some(self.base.join(&src.to_string())?))
     }
 
     fn get_chapter_urls_sorted(&self, document: &Elements) -> Result<Vec<Url>, NovelError> {
+        let mut urls = self.get_chapter_urls_sorted_reverse(document)?;
+        urls.reverse();
+        Ok(urls)
+    }
+
+    // the newest chapter is listed first
+    fn get_chapter_urls_sorted_reverse(&self, document: &Elements) -> Result<Vec<Url>, NovelError> {
         let selector = r"ul#chapterList a";
-        let urls = document
+        document
             .find(selector)
             .into_iter()
             .map(|x| {
@@ -80,46 +156,23 @@ impl Noveler for UUkanshu {
                     .ok_or(NovelError::NotFound("href".to_string()))
             })
             .map(|x| x.and_then(|url_str| self.base.join(&url_str).map_err(NovelError::ParseError)))
-            .collect::<Result<Vec<Url>, NovelError>>()?;
-        Ok(urls.into_iter().rev().collect())
+            .collect()
     }
 
-    fn get_chapter(&self, document: &Elements, order: &str) -> Result<Chapter, NovelError> {
-        let selector = r"h1#timu";
-        let title = document.find(selector).text().trim().to_string();
-
-        let selector = r"div#contentbox.uu_cont";
-        let text: String = document.find(selector).text();
+    fn chapter_title_selector(&self) -> &'static str {
+        r"h1#timu"
+    }
 
-        let order = order.to_string();
-        Ok(Chapter { order, title, text })
+    fn chapter_content_selector(&self) -> &'static str {
+        r"div#contentbox.uu_cont"
     }
 
     fn get_next_page(&self, _document: &Elements) -> Result<Option<Url>, NovelError> {
         Ok(None)
     }
 
-    fn process_chapter(&self, chapter: Chapter) -> Chapter {
-        let mut text = chapter.text;
-
-        for (re, s) in self.replacer.0.iter().zip(self.replacer.1.iter()) {
-            text = re.replace_all(&text, s).to_string();
-        }
-
-        text = text
-            .split(['\n', '\u{3000}', '\u{a0}', '\r'])
-            .map(str::trim)
-            .filter(|s| !s.is_empty())
-            .collect::<Vec<&str>>()
-            .join("\n");
-        text = text
-            .split("  ")
-            .map(str::trim)
-            .filter(|s| !s.is_empty())
-            .collect::<Vec<&str>>()
-            .join("\n");
-
-        Chapter { text, ..chapter }
+    fn process_chapter_text(&self, text: &str) -> String {
+        self.processor.process(text)
     }
 }
 
@@ -162,11 +215,30 @@ mod tests {
             book,
             Book {
                 name: "射手兇猛".to_string(),
-                author: "初四兮".to_string()
+                author: "初四兮".to_string(),
+                ..Book::default()
             }
         );
     }
 
+    #[test]
+    fn test_get_book_details() {
+        let html = CONTENTS;
+        let document = visdom::Vis::load(html).unwrap();
+        let novel = UUkanshu::new("https://tw.uukanshu.com/b/239329/").unwrap();
+        let book = novel.get_book(&document).unwrap();
+        let synopsis = book.synopsis.unwrap();
+        assert!(synopsis.starts_with("你希望自己能成為一名什么風格的adc？"));
+        assert!(synopsis.ends_with("《這個選手罪孽深重》"));
+        assert_eq!(
+            book.cover_url,
+            Some(
+                Url::parse("https://img.uukanshu.com/fengmian/2023/6/638218075421345617.jpg")
+                    .unwrap()
+            )
+        );
+    }
+
     #[test]
     fn test_get_book_info2() {
         let novel = UUkanshu::new("https://www.uukanshu.com/b/239329/").unwrap();
@@ -177,11 +249,41 @@ mod tests {
             book,
             Book {
                 name: "射手凶猛".to_string(),
-                author: "初四兮".to_string()
+                author: "初四兮".to_string(),
+                ..Book::default()
             }
         );
     }
 
+    #[test]
+    fn test_parse_series_list() {
+        let html = include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/uukanshu/author.html"
+        ));
+        let document = visdom::Vis::load(html).unwrap();
+        let author_url =
+            Url::parse("https://tw.uukanshu.com/search.aspx?t=1&key=%b3%f5%cb%c4%d9%e2").unwrap();
+        let books = UUkanshu::parse_series_list(&document, &author_url).unwrap();
+        assert_eq!(
+            books,
+            [
+                (
+                    "射手兇猛".to_string(),
+                    Url::parse("https://tw.uukanshu.com/b/239329/").unwrap()
+                ),
+                (
+                    "這個選手罪孽深重".to_string(),
+                    Url::parse("https://tw.uukanshu.com/b/152847/").unwrap()
+                ),
+                (
+                    "聯盟之傭兵系統".to_string(),
+                    Url::parse("https://tw.uukanshu.com/b/62381/").unwrap()
+                ),
+            ]
+        );
+    }
+
     #[test]
     fn test_get_chapter_urls_sorted() {
         let html = CONTENTS;
@@ -198,6 +300,22 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_get_chapter_urls_sorted_reverse() {
+        let html = CONTENTS;
+        let document = visdom::Vis::load(html).unwrap();
+        let novel = UUkanshu::new("https://tw.uukanshu.com/b/239329/").unwrap();
+        let urls = novel.get_chapter_urls_sorted_reverse(&document).unwrap();
+        assert_eq!(
+            urls.first().unwrap(),
+            &Url::parse("https://tw.uukanshu.com/b/239329/374018.html").unwrap()
+        );
+        assert_eq!(
+            urls.last().unwrap(),
+            &Url::parse("https://tw.uukanshu.com/b/239329/176659.html").unwrap()
+        );
+    }
+
     #[test]
     fn test_get_chapter_urls_sorted2() {
         let novel = UUkanshu::new("https://www.uukanshu.com/b/239329/").unwrap();
@@ -0,0 +1,168 @@
+//! 烽火中文網 <https://www.wfxs.cc/>
+use super::{Book, NovelError, Noveler, TextProcessor};
+use std::fmt::{self, Display};
+use url::Url;
+use visdom::types::Elements;
+
+pub(crate) struct Wfxs {
+    base: Url,
+    processor: TextProcessor,
+}
+
+impl Wfxs {
+    pub(crate) fn new(url: &str) -> Result<Self, NovelError> {
+        let mut base = Url::parse(url)?;
+
+        match base.path_segments_mut() {
+            Ok(mut path) => {
+                path.clear();
+            }
+            Err(()) => {
+                return Err(NovelError::CannotBeABase(url.to_string()));
+            }
+        }
+        base.set_query(None);
+
+        let processor = TextProcessor::new()
+            .add_regex_replacement(r"(?m)^.*(?:wfxs\.cc|烽火中文网).*$", "")?
+            .add_line_split_and_trim(&['\n', '\u{a0}', '\u{3000}', '\r'])
+            .add_filter_empty_lines();
+
+        Ok(Self { base, processor })
+    }
+}
+
+impl Display for Wfxs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "烽火中文網")
+    }
+}
+
+impl Noveler for Wfxs {
+    fn base_url(&self) -> Option<&Url> {
+        Some(&self.base)
+    }
+
+    fn need_encoding(&self) -> Option<&'static encoding_rs::Encoding> {
+        Some(encoding_rs::GBK)
+    }
+
+    fn get_book_info(&self, document: &Elements) -> Result<Book, NovelError> {
+        let selector = r"h1.bookTitle";
+        let name = document.find(selector).text().trim().to_string();
+
+        let selector = r"div.bookinfo p.p2";
+        let author = document
+            .find(selector)
+            .text()
+            .replace("作者：", "")
+            .trim()
+            .to_string();
+        Ok(Book {
+            name,
+            author,
+            ..Book::default()
+        })
+    }
+
+    fn get_chapter_urls_sorted(&self, document: &Elements) -> Result<Vec<Url>, NovelError> {
+        let selector = r"div#chapterList a";
+        document
+            .find(selector)
+            .into_iter()
+            .map(|x| {
+                x.get_attribute("href")
+                    .map(|attr| attr.to_string())
+                    .ok_or(NovelError::NotFound("href".to_string()))
+            })
+            .map(|x| x.and_then(|url_str| self.base.join(&url_str).map_err(NovelError::ParseError)))
+            .collect()
+    }
+
+    fn chapter_title_selector(&self) -> &'static str {
+        r"div.chapterinfo h1"
+    }
+
+    fn chapter_content_selector(&self) -> &'static str {
+        r"div#chapter_content"
+    }
+
+    fn get_next_page(&self, _document: &Elements) -> Result<Option<Url>, NovelError> {
+        Ok(None)
+    }
+
+    fn process_chapter_text(&self, text: &str) -> String {
+        self.processor.process(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static CONTENTS: &[u8] = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/wfxs/contents.html"
+    ));
+    static CHAPTER: &[u8] = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/wfxs/chapter.html"
+    ));
+
+    #[test]
+    fn test_get_book_info() {
+        let novel = Wfxs::new("https://www.wfxs.cc/book/2716/").unwrap();
+        let (html, _, _) = novel.need_encoding().unwrap().decode(CONTENTS);
+        let document = visdom::Vis::load(html).unwrap();
+        let book = novel.get_book_info(&document).unwrap();
+        assert_eq!(
+            book,
+            Book {
+                name: "雪鹰领主".to_string(),
+                author: "我吃西红柿".to_string(),
+                ..Book::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_get_chapter_urls_sorted() {
+        let novel = Wfxs::new("https://www.wfxs.cc/book/2716/").unwrap();
+        let (html, _, _) = novel.need_encoding().unwrap().decode(CONTENTS);
+        let document = visdom::Vis::load(html).unwrap();
+        let urls = novel.get_chapter_urls_sorted(&document).unwrap();
+        assert_eq!(urls.len(), 3);
+        assert_eq!(
+            urls.first().unwrap(),
+            &Url::parse("https://www.wfxs.cc/book/2716/1001.html").unwrap()
+        );
+        assert_eq!(
+            urls.last().unwrap(),
+            &Url::parse("https://www.wfxs.cc/book/2716/1003.html").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_get_chapter_content() {
+        let novel = Wfxs::new("https://www.wfxs.cc/book/2716/").unwrap();
+        let (html, _, _) = novel.need_encoding().unwrap().decode(CHAPTER);
+        let document = visdom::Vis::load(html).unwrap();
+        let chapter = novel.get_chapter(&document, "1").unwrap();
+        assert_eq!(chapter.order, "1".to_string());
+        assert_eq!(chapter.title, "第一章 雪鹰".to_string());
+        let chapter = novel.process_chapter(chapter);
+        dbg!(&chapter.text);
+        assert!(chapter.text.starts_with("夏族，安阳行省，雪鹰领。"));
+        assert!(chapter.text.ends_with("东伯雪鹰站在城墙上，眺望远方。"));
+        assert!(!chapter.text.contains("烽火中文网"));
+    }
+
+    #[test]
+    fn test_get_next_page() {
+        let novel = Wfxs::new("https://www.wfxs.cc/book/2716/").unwrap();
+        let (html, _, _) = novel.need_encoding().unwrap().decode(CHAPTER);
+        let document = visdom::Vis::load(html).unwrap();
+        let url = novel.get_next_page(&document).unwrap();
+        assert_eq!(url, None);
+    }
+}
@@ -0,0 +1,242 @@
+//! WNMTL <https://www.wnmtl.org/>
+use super::{Book, NovelError, Noveler, TextProcessor};
+use reqwest::Client;
+use serde::Deserialize;
+use std::fmt::{self, Display};
+use url::Url;
+use visdom::types::Elements;
+
+pub(crate) struct Wnmtl {
+    base: Url,
+    novel_id: String,
+    processor: TextProcessor,
+}
+
+/// response of `/api/novel/<id>/chapters`
+#[derive(Debug, Deserialize)]
+struct ChaptersResponse {
+    data: Vec<ChapterInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChapterInfo {
+    id: u64,
+    chapter_order: u64,
+}
+
+impl Wnmtl {
+    pub(crate) fn new(url: &str) -> Result<Self, NovelError> {
+        let mut base = Url::parse(url)?;
+
+        // https://www.wnmtl.org/book/{novelId}-{slug}
+        let novel_id = base
+            .path_segments()
+            .and_then(|mut segments| segments.nth(1))
+            .map(|segment| {
+                segment
+                    .chars()
+                    .take_while(char::is_ascii_digit)
+                    .collect::<String>()
+            })
+            .filter(|id| !id.is_empty())
+            .ok_or(NovelError::NotFound("novel id".to_string()))?;
+
+        match base.path_segments_mut() {
+            Ok(mut path) => {
+                path.clear();
+            }
+            Err(()) => {
+                return Err(NovelError::CannotBeABase(url.to_string()));
+            }
+        }
+        base.set_query(None);
+
+        let processor = TextProcessor::new()
+            .add_regex_replacement(r"(?mi)^.*wnmtl\.org.*$", "")?
+            .add_line_split_and_trim(&['\n', '\u{a0}', '\r'])
+            .add_filter_empty_lines();
+
+        Ok(Self {
+            base,
+            novel_id,
+            processor,
+        })
+    }
+
+    fn chapters_url(&self) -> Result<Url, NovelError> {
+        Ok(self
+            .base
+            .join(&format!("/api/novel/{}/chapters", self.novel_id))?)
+    }
+
+    /// the chapter pages in `chapterOrder`, the api does not promise any order
+    fn parse_chapters(&self, json: &str) -> Result<Vec<Url>, NovelError> {
+        let mut chapters = serde_json::from_str::<ChaptersResponse>(json)?.data;
+        chapters.sort_by_key(|chapter| chapter.chapter_order);
+        chapters
+            .into_iter()
+            .map(|chapter| Ok(self.base.join(&format!("/chapter/{}", chapter.id))?))
+            .collect()
+    }
+}
+
+impl Display for Wnmtl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "WNMTL")
+    }
+}
+
+impl Noveler for Wnmtl {
+    fn base_url(&self) -> Option<&Url> {
+        Some(&self.base)
+    }
+
+    fn get_book_info(&self, document: &Elements) -> Result<Book, NovelError> {
+        let selector = r"h1.novel-title";
+        let name = document.find(selector).text().trim().to_string();
+
+        let selector = r"div.author-detail";
+        let author = document.find(selector).text();
+        let author = author.trim();
+        let author = author.strip_prefix("Author:").unwrap_or(author).trim();
+        Ok(Book {
+            name,
+            author: author.to_string(),
+            ..Book::default()
+        })
+    }
+
+    // the contents page fills its list by script, only the api has the chapters
+    fn get_chapter_urls_sorted(&self, _document: &Elements) -> Result<Vec<Url>, NovelError> {
+        Ok(Vec::new())
+    }
+
+    async fn fetch_chapter_urls_sorted(
+        &self,
+        client: Client,
+        _document: &Elements<'_>,
+    ) -> Result<Vec<Url>, NovelError> {
+        let json = client
+            .get(self.chapters_url()?)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        self.parse_chapters(&json)
+    }
+
+    fn chapter_title_selector(&self) -> &'static str {
+        r"h1.chapter-title"
+    }
+
+    fn chapter_content_selector(&self) -> &'static str {
+        r"div.chapter-content"
+    }
+
+    fn get_next_page(&self, _document: &Elements) -> Result<Option<Url>, NovelError> {
+        Ok(None)
+    }
+
+    fn process_chapter_text(&self, text: &str) -> String {
+        self.processor.process(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static CONTENTS: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/wnmtl/contents.html"
+    ));
+    static CHAPTERS: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/wnmtl/chapters.json"
+    ));
+    static CHAPTER: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/wnmtl/chapter.html"
+    ));
+
+    const URL: &str = "https://www.wnmtl.org/book/4139-reincarnation-of-the-strongest-sword-god";
+
+    #[test]
+    fn test_new() {
+        let novel = Wnmtl::new(URL).unwrap();
+        assert_eq!(novel.novel_id, "4139");
+        assert_eq!(
+            novel.chapters_url().unwrap(),
+            Url::parse("https://www.wnmtl.org/api/novel/4139/chapters").unwrap()
+        );
+
+        assert!(matches!(
+            Wnmtl::new("https://www.wnmtl.org/book/"),
+            Err(NovelError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_get_book_info() {
+        let document = visdom::Vis::load(CONTENTS).unwrap();
+        let novel = Wnmtl::new(URL).unwrap();
+        let book = novel.get_book_info(&document).unwrap();
+        assert_eq!(
+            book,
+            Book {
+                name: "Reincarnation Of The Strongest Sword God".to_string(),
+                author: "Lucky Cat".to_string(),
+                ..Book::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_chapters() {
+        let novel = Wnmtl::new(URL).unwrap();
+        let urls = novel.parse_chapters(CHAPTERS).unwrap();
+        assert_eq!(
+            urls,
+            ["1001", "1002", "1003"]
+                .map(|id| Url::parse(&format!("https://www.wnmtl.org/chapter/{id}")).unwrap())
+        );
+        assert!(urls.iter().all(|url| novel.chapter_url_is_valid(url)));
+    }
+
+    #[test]
+    fn test_get_chapter_content() {
+        let document = visdom::Vis::load(CHAPTER).unwrap();
+        let novel = Wnmtl::new(URL).unwrap();
+        let chapter = novel.get_chapter(&document, "1").unwrap();
+        assert_eq!(chapter.order, "1".to_string());
+        assert_eq!(chapter.title, "Chapter 1 - Starting Over".to_string());
+        let chapter = novel.process_chapter(chapter);
+        assert!(chapter.text.starts_with("Shi Feng opened his eyes."));
+        assert!(chapter
+            .text
+            .ends_with("ten years before everything went wrong."));
+        assert!(!chapter.text.contains("wnmtl"));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_chapter_urls_sorted() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/api/novel/4139/chapters")
+            .with_body(CHAPTERS)
+            .create_async()
+            .await;
+
+        let novel = Wnmtl::new(&format!("{}/book/4139", server.url())).unwrap();
+        let document = visdom::Vis::load(CONTENTS).unwrap();
+        let urls = novel
+            .fetch_chapter_urls_sorted(Client::new(), &document)
+            .await
+            .unwrap();
+        assert_eq!(urls.len(), 3);
+        assert!(urls[0].as_str().ends_with("/chapter/1001"));
+        mock.assert_async().await;
+    }
+}
@@ -0,0 +1,183 @@
+//! 新筆趣閣 <https://www.xbiquge.so/>
+use super::{Book, NovelError, Noveler, TextProcessor};
+use std::fmt::{self, Display};
+use url::Url;
+use visdom::types::Elements;
+
+pub(crate) struct XbiqugeSo {
+    base: Url,
+    processor: TextProcessor,
+}
+
+impl XbiqugeSo {
+    pub(crate) fn new(url: &str) -> Result<Self, NovelError> {
+        let mut base = Url::parse(url)?;
+
+        match base.path_segments_mut() {
+            Ok(mut path) => {
+                path.clear();
+            }
+            Err(()) => {
+                return Err(NovelError::CannotBeABase(url.to_string()));
+            }
+        }
+        base.set_query(None);
+
+        let processor = TextProcessor::new()
+            .add_regex_replacement(r"(?m)^.*(?:xbiquge\.so|新笔趣阁).*$", "")?
+            .add_line_split_and_trim(&['\n', '\u{a0}', '\u{3000}', '\r'])
+            .add_filter_empty_lines();
+
+        Ok(Self { base, processor })
+    }
+}
+
+impl Display for XbiqugeSo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "新筆趣閣")
+    }
+}
+
+impl Noveler for XbiqugeSo {
+    fn base_url(&self) -> Option<&Url> {
+        Some(&self.base)
+    }
+
+    fn need_encoding(&self) -> Option<&'static encoding_rs::Encoding> {
+        Some(encoding_rs::GBK)
+    }
+
+    fn get_book_info(&self, document: &Elements) -> Result<Book, NovelError> {
+        let selector = r"div#info h1";
+        let name = document.find(selector).text().trim().to_string();
+
+        let selector = r"div#info p:first-of-type";
+        let author = document.find(selector).text();
+        let author = author
+            .split_once('：')
+            .map(|(_, author)| author.trim().to_string())
+            .ok_or(NovelError::NotFound("author".to_string()))?;
+        Ok(Book {
+            name,
+            author,
+            ..Book::default()
+        })
+    }
+
+    fn get_synopsis(&self, document: &Elements) -> Option<String> {
+        let selector = r"div#intro";
+        let synopsis = document.find(selector).text().trim().to_string();
+        (!synopsis.is_empty()).then_some(synopsis)
+    }
+
+    fn get_chapter_urls_sorted(&self, document: &Elements) -> Result<Vec<Url>, NovelError> {
+        let selector = r"div#list dd a";
+        document
+            .find(selector)
+            .into_iter()
+            .map(|x| {
+                x.get_attribute("href")
+                    .map(|attr| attr.to_string())
+                    .ok_or(NovelError::NotFound("href".to_string()))
+            })
+            .map(|x| x.and_then(|url_str| self.base.join(&url_str).map_err(NovelError::ParseError)))
+            .collect()
+    }
+
+    fn chapter_title_selector(&self) -> &'static str {
+        r"div.bookname h1"
+    }
+
+    fn get_next_page(&self, _document: &Elements) -> Result<Option<Url>, NovelError> {
+        Ok(None)
+    }
+
+    fn process_chapter_text(&self, text: &str) -> String {
+        self.processor.process(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static CONTENTS: &[u8] = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/xbiquge_so/contents.html"
+    ));
+    static CHAPTER: &[u8] = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/xbiquge_so/chapter.html"
+    ));
+
+    #[test]
+    fn test_get_book_info() {
+        let novel = XbiqugeSo::new("https://www.xbiquge.so/book/53099/").unwrap();
+        let (html, _, _) = novel.need_encoding().unwrap().decode(CONTENTS);
+        let document = visdom::Vis::load(html).unwrap();
+        let book = novel.get_book_info(&document).unwrap();
+        assert_eq!(
+            book,
+            Book {
+                name: "大奉打更人".to_string(),
+                author: "卖报小郎君".to_string(),
+                ..Book::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_get_book_details() {
+        let novel = XbiqugeSo::new("https://www.xbiquge.so/book/53099/").unwrap();
+        let (html, _, _) = novel.need_encoding().unwrap().decode(CONTENTS);
+        let document = visdom::Vis::load(html).unwrap();
+        let book = novel.get_book(&document).unwrap();
+        assert_eq!(
+            book.synopsis.as_deref(),
+            Some("这个世界，有儒；有道；有佛；有妖；有术士。")
+        );
+    }
+
+    #[test]
+    fn test_get_chapter_urls_sorted() {
+        let novel = XbiqugeSo::new("https://www.xbiquge.so/book/53099/").unwrap();
+        let (html, _, _) = novel.need_encoding().unwrap().decode(CONTENTS);
+        let document = visdom::Vis::load(html).unwrap();
+        let urls = novel.get_chapter_urls_sorted(&document).unwrap();
+        assert_eq!(urls.len(), 3);
+        assert_eq!(
+            urls.first().unwrap(),
+            &Url::parse("https://www.xbiquge.so/book/53099/38190553.html").unwrap()
+        );
+        assert_eq!(
+            urls.last().unwrap(),
+            &Url::parse("https://www.xbiquge.so/book/53099/38190555.html").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_get_chapter_content() {
+        let novel = XbiqugeSo::new("https://www.xbiquge.so/book/53099/").unwrap();
+        let (html, _, _) = novel.need_encoding().unwrap().decode(CHAPTER);
+        let document = visdom::Vis::load(html).unwrap();
+        let chapter = novel.get_chapter(&document, "1").unwrap();
+        assert_eq!(chapter.order, "1".to_string());
+        assert_eq!(chapter.title, "第一章 牢狱之灾".to_string());
+        let chapter = novel.process_chapter(chapter);
+        dbg!(&chapter.text);
+        assert!(chapter.text.starts_with("“嘶……”"));
+        assert!(chapter
+            .text
+            .ends_with("他在牢房里坐了一夜，终于确认自己穿越了。"));
+        assert!(!chapter.text.contains("xbiquge"));
+    }
+
+    #[test]
+    fn test_get_next_page() {
+        let novel = XbiqugeSo::new("https://www.xbiquge.so/book/53099/").unwrap();
+        let (html, _, _) = novel.need_encoding().unwrap().decode(CHAPTER);
+        let document = visdom::Vis::load(html).unwrap();
+        let url = novel.get_next_page(&document).unwrap();
+        assert_eq!(url, None);
+    }
+}
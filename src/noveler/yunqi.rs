@@ -0,0 +1,335 @@
+//! 雲起書院 <https://www.yunqi.qq.com/>
+use super::{Book, Chapter, NovelError, Noveler, TextProcessor};
+use reqwest::Client;
+use serde::Deserialize;
+use std::fmt::{self, Display};
+use url::Url;
+use visdom::types::Elements;
+
+/// the pages are rendered by javascript, the chapter list and the chapters come from the api
+/// of the mobile site, the VIP chapters need the session cookie of a QQ login
+const API_BASE: &str = "https://m.yunqi.qq.com/";
+
+pub(crate) struct Yunqi {
+    base: Url,
+    api_base: Url,
+    book_id: String,
+    processor: TextProcessor,
+}
+
+/// response of `/api/book/{bookId}/chapters`
+#[derive(Debug, Deserialize)]
+struct ChaptersResponse {
+    data: ChaptersData,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChaptersData {
+    chapters: Vec<ChapterInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChapterInfo {
+    cid: u64,
+}
+
+/// response of `/api/book/{bookId}/chapter/{cid}`
+#[derive(Debug, Deserialize)]
+struct ContentResponse {
+    data: ContentData,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ContentData {
+    title: String,
+    #[serde(default)]
+    content: String,
+    #[serde(default)]
+    is_vip: bool,
+}
+
+impl Yunqi {
+    pub(crate) fn new(url: &str) -> Result<Self, NovelError> {
+        let mut base = Url::parse(url)?;
+
+        // https://www.yunqi.qq.com/books/{bookId} or .../{bookId}.html
+        let book_id = base
+            .path_segments()
+            .and_then(|mut segments| segments.rfind(|s| !s.is_empty()))
+            .map(|segment| segment.trim_end_matches(".html").to_string())
+            .ok_or(NovelError::NotFound("book id".to_string()))?;
+
+        match base.path_segments_mut() {
+            Ok(mut path) => {
+                path.clear();
+            }
+            Err(()) => {
+                return Err(NovelError::CannotBeABase(url.to_string()));
+            }
+        }
+        base.set_query(None);
+
+        let processor = TextProcessor::new()
+            .add_regex_replacement(r"</?p[^>]*>|<br\s*/?>", "\n")?
+            .add_regex_replacement(r"(?m)^.*(?:yunqi\.qq\.com|[雲云]起[書书]院).*$", "")?
+            .add_line_split_and_trim(&['\n', '\u{a0}', '\u{3000}', '\r'])
+            .add_filter_empty_lines();
+
+        Ok(Self {
+            base,
+            api_base: Url::parse(API_BASE)?,
+            book_id,
+            processor,
+        })
+    }
+
+    fn chapters_url(&self) -> Result<Url, NovelError> {
+        Ok(self
+            .api_base
+            .join(&format!("/api/book/{}/chapters", self.book_id))?)
+    }
+
+    fn parse_chapters(&self, json: &str) -> Result<Vec<Url>, NovelError> {
+        let response: ChaptersResponse = serde_json::from_str(json)?;
+        response
+            .data
+            .chapters
+            .into_iter()
+            .map(|chapter| {
+                self.api_base
+                    .join(&format!(
+                        "/api/book/{}/chapter/{}",
+                        self.book_id, chapter.cid
+                    ))
+                    .map_err(NovelError::ParseError)
+            })
+            .collect()
+    }
+
+    /// a VIP chapter without its text is one the session has not bought, or no session at all
+    fn parse_content(json: &str, order: &str) -> Result<Chapter, NovelError> {
+        let response: ContentResponse = serde_json::from_str(json)?;
+        let title = response.data.title.trim().to_string();
+        if response.data.is_vip && response.data.content.trim().is_empty() {
+            return Err(NovelError::VipChapter(format!("{order} {title}")));
+        }
+        Ok(Chapter {
+            order: order.to_string(),
+            title,
+            text: response.data.content,
+        })
+    }
+
+    async fn fetch_chapters(&self, client: Client) -> Result<Vec<Url>, NovelError> {
+        let json = client
+            .get(self.chapters_url()?)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        self.parse_chapters(&json)
+    }
+}
+
+impl Display for Yunqi {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "雲起書院")
+    }
+}
+
+impl Noveler for Yunqi {
+    fn base_url(&self) -> Option<&Url> {
+        Some(&self.base)
+    }
+
+    // the chapters are json from the api, not html pages
+    async fn process_url(
+        &self,
+        client: Client,
+        order: &str,
+        url: Url,
+        _encoding: Option<&'static encoding_rs::Encoding>,
+    ) -> Result<(Chapter, Option<Url>), NovelError> {
+        let json = client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        let chapter = Self::parse_content(&json, order)?;
+        Ok((self.process_chapter(chapter), None))
+    }
+
+    // the meta tags are in the page, the rest is rendered by javascript
+    fn get_book_info(&self, document: &Elements) -> Result<Book, NovelError> {
+        let selector = r#"meta[property="og:novel:book_name"]"#;
+        let name = document
+            .find(selector)
+            .attr("content")
+            .ok_or(NovelError::NotFound("book_name content".to_string()))?
+            .to_string();
+
+        let selector = r#"meta[property="og:novel:author"]"#;
+        let author = document
+            .find(selector)
+            .attr("content")
+            .ok_or(NovelError::NotFound("author content".to_string()))?
+            .to_string();
+        Ok(Book {
+            name,
+            author,
+            ..Book::default()
+        })
+    }
+
+    fn get_synopsis(&self, document: &Elements) -> Option<String> {
+        let selector = r#"meta[property="og:description"]"#;
+        Some(document.find(selector).attr("content")?.to_string())
+    }
+
+    fn get_cover_url(&self, document: &Elements) -> Result<Option<Url>, NovelError> {
+        let selector = r#"meta[property="og:image"]"#;
+        let Some(src) = document.find(selector).attr("content") else {
+            return Ok(None);
+        };
+        Ok(Some(self.base.join(&src.to_string())?))
+    }
+
+    fn get_tags(&self, document: &Elements) -> Vec<String> {
+        let selector = r#"meta[property="og:novel:category"]"#;
+        document
+            .find(selector)
+            .attr("content")
+            .map(|category| vec![category.to_string()])
+            .unwrap_or_default()
+    }
+
+    // the book is on www.yunqi.qq.com, the chapters on m.yunqi.qq.com
+    fn chapter_url_is_valid(&self, url: &Url) -> bool {
+        url.host_str()
+            .is_some_and(|host| host == "yunqi.qq.com" || host.ends_with(".yunqi.qq.com"))
+            && url.path() != "/"
+    }
+
+    // nothing to read from the page, the list is rendered by javascript
+    fn get_chapter_urls_sorted(&self, _document: &Elements) -> Result<Vec<Url>, NovelError> {
+        Ok(Vec::new())
+    }
+
+    async fn fetch_chapter_urls_sorted(
+        &self,
+        client: Client,
+        _document: &Elements<'_>,
+    ) -> Result<Vec<Url>, NovelError> {
+        self.fetch_chapters(client).await
+    }
+
+    fn get_next_page(&self, _document: &Elements) -> Result<Option<Url>, NovelError> {
+        Ok(None)
+    }
+
+    fn process_chapter_text(&self, text: &str) -> String {
+        self.processor.process(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static CONTENTS: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/yunqi/contents.html"
+    ));
+    static CHAPTERS: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/yunqi/chapters.json"
+    ));
+    static CONTENT: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/yunqi/content.json"
+    ));
+    static VIP: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/yunqi/vip.json"));
+
+    #[test]
+    fn test_new() {
+        let novel = Yunqi::new("https://www.yunqi.qq.com/books/25413.html").unwrap();
+        assert_eq!(novel.book_id, "25413");
+        assert_eq!(
+            novel.chapters_url().unwrap(),
+            Url::parse("https://m.yunqi.qq.com/api/book/25413/chapters").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_get_book_info() {
+        let html = CONTENTS;
+        let document = visdom::Vis::load(html).unwrap();
+        let novel = Yunqi::new("https://www.yunqi.qq.com/books/25413").unwrap();
+        let book = novel.get_book_info(&document).unwrap();
+        assert_eq!(
+            book,
+            Book {
+                name: "雪中悍刀行".to_string(),
+                author: "烽火戲諸侯".to_string(),
+                ..Book::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_get_book_details() {
+        let html = CONTENTS;
+        let document = visdom::Vis::load(html).unwrap();
+        let novel = Yunqi::new("https://www.yunqi.qq.com/books/25413").unwrap();
+        let book = novel.get_book(&document).unwrap();
+        assert_eq!(
+            book.synopsis.as_deref(),
+            Some("江湖是一張珠簾。大人物小人物，是珠子，大故事小故事，是串線。")
+        );
+        assert_eq!(
+            book.cover_url,
+            Some(Url::parse("https://bookcover.yuewen.com/qdbimg/25413/180").unwrap())
+        );
+        assert_eq!(book.tags, ["武俠"]);
+    }
+
+    #[test]
+    fn test_parse_chapters() {
+        let novel = Yunqi::new("https://www.yunqi.qq.com/books/25413").unwrap();
+        let urls = novel.parse_chapters(CHAPTERS).unwrap();
+        assert_eq!(urls.len(), 3);
+        assert_eq!(
+            urls.first().unwrap(),
+            &Url::parse("https://m.yunqi.qq.com/api/book/25413/chapter/1").unwrap()
+        );
+        assert_eq!(
+            urls.last().unwrap(),
+            &Url::parse("https://m.yunqi.qq.com/api/book/25413/chapter/3").unwrap()
+        );
+        assert!(urls.iter().all(|url| novel.chapter_url_is_valid(url)));
+    }
+
+    #[test]
+    fn test_parse_content() {
+        let novel = Yunqi::new("https://www.yunqi.qq.com/books/25413").unwrap();
+        let chapter = Yunqi::parse_content(CONTENT, "1").unwrap();
+        assert_eq!(chapter.order, "1".to_string());
+        assert_eq!(chapter.title, "第一章 小二上酒".to_string());
+        let chapter = novel.process_chapter(chapter);
+        dbg!(&chapter.text);
+        assert!(chapter.text.starts_with("北涼王府龐大如一座皇城。"));
+        assert!(chapter.text.ends_with("老黃牽著一匹瘦馬。"));
+        assert!(!chapter.text.contains("<p>"));
+        assert!(!chapter.text.contains("雲起"));
+    }
+
+    #[test]
+    fn test_parse_content_vip() {
+        let err = Yunqi::parse_content(VIP, "3").unwrap_err();
+        assert!(matches!(&err, NovelError::VipChapter(chapter) if chapter == "3 第三章 徐鳳年"));
+    }
+}
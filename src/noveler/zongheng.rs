@@ -0,0 +1,236 @@
+//! 縱橫中文網 <https://www.zongheng.com/>
+use super::{Book, Chapter, NovelError, Noveler, TextProcessor};
+use std::fmt::{self, Display};
+use url::Url;
+use visdom::types::Elements;
+
+/// the chapters are on another host than the book
+const READ_BASE: &str = "https://read.zongheng.com/";
+
+pub(crate) struct Zongheng {
+    base: Url,
+    read_base: Url,
+    processor: TextProcessor,
+}
+
+impl Zongheng {
+    pub(crate) fn new(url: &str) -> Result<Self, NovelError> {
+        let mut base = Url::parse(url)?;
+
+        match base.path_segments_mut() {
+            Ok(mut path) => {
+                path.clear();
+            }
+            Err(()) => {
+                return Err(NovelError::CannotBeABase(url.to_string()));
+            }
+        }
+        base.set_query(None);
+
+        let processor = TextProcessor::new()
+            .add_regex_replacement(r"(?m)^.*(?:zongheng\.com|[縱纵][橫横]中文[網网]).*$", "")?
+            .add_line_split_and_trim(&['\n', '\u{a0}', '\u{3000}', '\r'])
+            .add_filter_empty_lines();
+
+        Ok(Self {
+            base,
+            read_base: Url::parse(READ_BASE)?,
+            processor,
+        })
+    }
+}
+
+impl Display for Zongheng {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "縱橫中文網")
+    }
+}
+
+impl Noveler for Zongheng {
+    fn base_url(&self) -> Option<&Url> {
+        Some(&self.base)
+    }
+
+    fn get_book_info(&self, document: &Elements) -> Result<Book, NovelError> {
+        let selector = r"div.book-info div.book-name";
+        let name = document.find(selector).text().trim().to_string();
+
+        let selector = r"div.book-info div.book-author a";
+        let author = document.find(selector).text().trim().to_string();
+        Ok(Book {
+            name,
+            author,
+            ..Book::default()
+        })
+    }
+
+    // the book is on book.zongheng.com, the chapters on read.zongheng.com
+    fn chapter_url_is_valid(&self, url: &Url) -> bool {
+        url.host_str()
+            .is_some_and(|host| host == "zongheng.com" || host.ends_with(".zongheng.com"))
+            && url.path() != "/"
+    }
+
+    fn get_chapter_urls_sorted(&self, document: &Elements) -> Result<Vec<Url>, NovelError> {
+        let selector = r"div.chapter-list a";
+        document
+            .find(selector)
+            .into_iter()
+            .map(|x| {
+                x.get_attribute("href")
+                    .map(|attr| attr.to_string())
+                    .ok_or(NovelError::NotFound("href".to_string()))
+            })
+            .map(|x| {
+                x.and_then(|url_str| {
+                    self.read_base
+                        .join(&url_str)
+                        .map_err(NovelError::ParseError)
+                })
+            })
+            .collect()
+    }
+
+    fn get_chapter(&self, document: &Elements, order: &str) -> Result<Chapter, NovelError> {
+        let selector = r"div.title_txtbox";
+        let title = document.find(selector).text().trim().to_string();
+
+        // the reader page of the newer layout, div.content-body of the older one
+        let mut paragraphs = document.find(r"div#readerFull p");
+        if paragraphs.is_empty() {
+            paragraphs = document.find(r"div.content-body p");
+        }
+        let text: String = paragraphs
+            .into_iter()
+            .map(|x| x.text().trim().to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let order = order.to_string();
+        Ok(Chapter { order, title, text })
+    }
+
+    // long chapters go on at `?p=2` and so on
+    fn get_next_page(&self, document: &Elements) -> Result<Option<Url>, NovelError> {
+        let selector = r"div.chapter-pagination a";
+        let Some(href) = document
+            .find(selector)
+            .into_iter()
+            .find(|x| matches!(x.text().trim(), "下一頁" | "下一页"))
+            .and_then(|x| x.get_attribute("href"))
+        else {
+            return Ok(None);
+        };
+        Ok(Some(self.read_base.join(&href.to_string())?))
+    }
+
+    fn process_chapter_text(&self, text: &str) -> String {
+        self.processor.process(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static CONTENTS: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/zongheng/contents.html"
+    ));
+    static CHAPTER: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/zongheng/chapter.html"
+    ));
+    static CHAPTER2: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/zongheng/chapter2.html"
+    ));
+
+    #[test]
+    fn test_get_book_info() {
+        let html = CONTENTS;
+        let document = visdom::Vis::load(html).unwrap();
+        let novel = Zongheng::new("https://book.zongheng.com/showchapter/189169.html").unwrap();
+        let book = novel.get_book_info(&document).unwrap();
+        assert_eq!(
+            book,
+            Book {
+                name: "劍來".to_string(),
+                author: "烽火戲諸侯".to_string(),
+                ..Book::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_chapter_url_is_valid() {
+        let novel = Zongheng::new("https://book.zongheng.com/showchapter/189169.html").unwrap();
+        assert!(novel.chapter_url_is_valid(
+            &Url::parse("https://read.zongheng.com/chapter/189169/3431546.html").unwrap()
+        ));
+        assert!(!novel.chapter_url_is_valid(&Url::parse("https://www.zongheng.com/").unwrap()));
+        assert!(!novel.chapter_url_is_valid(&Url::parse("https://example.com/1.html").unwrap()));
+    }
+
+    #[test]
+    fn test_get_chapter_urls_sorted() {
+        let html = CONTENTS;
+        let document = visdom::Vis::load(html).unwrap();
+        let novel = Zongheng::new("https://book.zongheng.com/showchapter/189169.html").unwrap();
+        let urls = novel.get_chapter_urls_sorted(&document).unwrap();
+        assert_eq!(urls.len(), 3);
+        assert_eq!(
+            urls.first().unwrap(),
+            &Url::parse("https://read.zongheng.com/chapter/189169/3431546.html").unwrap()
+        );
+        assert_eq!(
+            urls.last().unwrap(),
+            &Url::parse("https://read.zongheng.com/chapter/189169/3431548.html").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_get_chapter_content() {
+        let html = CHAPTER;
+        let document = visdom::Vis::load(html).unwrap();
+        let novel = Zongheng::new("https://book.zongheng.com/showchapter/189169.html").unwrap();
+        let chapter = novel.get_chapter(&document, "1").unwrap();
+        assert_eq!(chapter.order, "1".to_string());
+        assert_eq!(chapter.title, "第一章 驚蟄".to_string());
+        let chapter = novel.process_chapter(chapter);
+        dbg!(&chapter.text);
+        assert!(chapter.text.starts_with("二月二，龍抬頭。"));
+        assert!(chapter.text.ends_with("少年陳平安站在泥瓶巷口。"));
+        assert!(!chapter.text.contains("縱橫中文網"));
+    }
+
+    #[test]
+    fn test_get_chapter_content2() {
+        let html = CHAPTER2;
+        let document = visdom::Vis::load(html).unwrap();
+        let novel = Zongheng::new("https://book.zongheng.com/showchapter/189169.html").unwrap();
+        let chapter = novel.get_chapter(&document, "1_n").unwrap();
+        assert_eq!(chapter.title, "第一章 驚蟄".to_string());
+        let chapter = novel.process_chapter(chapter);
+        dbg!(&chapter.text);
+        assert!(chapter.text.starts_with("暮色裡，小鎮名叫驪珠洞天。"));
+        assert!(chapter.text.ends_with("陳平安關上了院門。"));
+    }
+
+    #[test]
+    fn test_get_next_page() {
+        let novel = Zongheng::new("https://book.zongheng.com/showchapter/189169.html").unwrap();
+
+        let document = visdom::Vis::load(CHAPTER).unwrap();
+        let url = novel.get_next_page(&document).unwrap();
+        assert_eq!(
+            url,
+            Some(Url::parse("https://read.zongheng.com/chapter/189169/3431546.html?p=2").unwrap())
+        );
+
+        // the last page
+        let document = visdom::Vis::load(CHAPTER2).unwrap();
+        let url = novel.get_next_page(&document).unwrap();
+        assert_eq!(url, None);
+    }
+}
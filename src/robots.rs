@@ -0,0 +1,348 @@
+//! `--respect-robots-txt`, the `Allow` and `Disallow` rules of a site's `robots.txt`
+//!
+//! the group naming `get_novel` applies if there is one, otherwise the `*` group, the longest
+//! matching rule decides and `Allow` wins a tie, as in RFC 9309
+
+use crate::noveler::NovelError;
+use reqwest::Client;
+use std::collections::HashMap;
+use url::Url;
+
+/// the product token matched against `User-agent` lines
+const ROBOTS_USER_AGENT: &str = "get_novel";
+
+/// `(allow, pattern)`
+type Rule = (bool, String);
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct RobotsTxtChecker {
+    rules: Vec<Rule>,
+}
+
+impl RobotsTxtChecker {
+    /// `robots.txt` of the site of `url`, a missing one allows everything
+    pub(crate) async fn fetch(client: &Client, url: &Url) -> Result<Self, NovelError> {
+        let resp = client.get(url.join("/robots.txt")?).send().await?;
+        if resp.status().is_client_error() {
+            return Ok(Self::default());
+        }
+        Ok(Self::parse(&resp.error_for_status()?.text().await?))
+    }
+
+    pub(crate) fn parse(text: &str) -> Self {
+        // consecutive `User-agent` lines share the rules after them
+        let mut groups: Vec<(Vec<String>, Vec<Rule>)> = Vec::new();
+        let mut in_agents = false;
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or_default();
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            match key.trim().to_ascii_lowercase().as_str() {
+                "user-agent" => {
+                    if !in_agents {
+                        groups.push((Vec::new(), Vec::new()));
+                        in_agents = true;
+                    }
+                    if let Some((agents, _)) = groups.last_mut() {
+                        agents.push(value.to_ascii_lowercase());
+                    }
+                }
+                key @ ("allow" | "disallow") => {
+                    in_agents = false;
+                    // an empty `Disallow` allows everything, same as no rule
+                    if let (Some((_, rules)), false) = (groups.last_mut(), value.is_empty()) {
+                        rules.push((key == "allow", value.to_string()));
+                    }
+                }
+                _ => in_agents = false,
+            }
+        }
+
+        let rules_of = |agent: &str| -> Vec<Rule> {
+            groups
+                .iter()
+                .filter(|(agents, _)| agents.iter().any(|a| a == agent))
+                .flat_map(|(_, rules)| rules.iter().cloned())
+                .collect()
+        };
+        let named = groups
+            .iter()
+            .any(|(agents, _)| agents.iter().any(|a| a == ROBOTS_USER_AGENT));
+        Self {
+            rules: rules_of(if named { ROBOTS_USER_AGENT } else { "*" }),
+        }
+    }
+
+    pub(crate) fn is_allowed(&self, url: &Url) -> bool {
+        let path = match url.query() {
+            Some(query) => format!("{}?{query}", url.path()),
+            None => url.path().to_string(),
+        };
+        self.rules
+            .iter()
+            .filter(|(_, pattern)| rule_matches(pattern, &path))
+            .max_by_key(|(allow, pattern)| (pattern.len(), *allow))
+            .is_none_or(|(allow, _)| *allow)
+    }
+}
+
+/// `*` matches any run of chars and a trailing `$` the end of the path
+fn rule_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+    let mut parts = pattern.split('*');
+    let Some(mut rest) = path.strip_prefix(parts.next().unwrap_or_default()) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    for (i, part) in parts.iter().enumerate() {
+        if anchored && i + 1 == parts.len() {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(pos) => rest = &rest[pos + part.len()..],
+            None => return false,
+        }
+    }
+    !anchored || rest.is_empty()
+}
+
+/// refuse `url_contents` when the `robots.txt` of its site disallows it
+pub(crate) async fn check_contents(client: &Client, url_contents: &str) -> Result<(), NovelError> {
+    let url = Url::parse(url_contents)?;
+    if RobotsTxtChecker::fetch(client, &url)
+        .await?
+        .is_allowed(&url)
+    {
+        Ok(())
+    } else {
+        Err(NovelError::DisallowedByRobotsTxt(url_contents.to_string()))
+    }
+}
+
+/// leave out the chapters the `robots.txt` of their site disallows
+pub(crate) async fn allowed_chapters(
+    client: &Client,
+    urls: Vec<(String, Url)>,
+) -> Result<Vec<(String, Url)>, NovelError> {
+    // chapters may be on another host than the contents
+    let mut checkers: HashMap<String, RobotsTxtChecker> = HashMap::new();
+    let mut allowed = Vec::with_capacity(urls.len());
+    for (order, url) in urls {
+        let origin = url.origin().ascii_serialization();
+        if !checkers.contains_key(&origin) {
+            let checker = RobotsTxtChecker::fetch(client, &url).await?;
+            checkers.insert(origin.clone(), checker);
+        }
+        if checkers[&origin].is_allowed(&url) {
+            allowed.push((order, url));
+        } else {
+            tracing::warn!(order = %order, url = %url, "disallowed by robots.txt, skipping");
+        }
+    }
+    Ok(allowed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static ROBOTS: &str = "
+# comment
+User-agent: *
+Disallow: /admin/
+Disallow: /search
+Allow: /admin/public
+
+User-agent: Googlebot
+User-agent: get_novel
+Disallow: /book/*/vip
+Disallow: /*.php$
+Allow: /book/1/vip
+";
+
+    fn allowed(checker: &RobotsTxtChecker, path: &str) -> bool {
+        checker.is_allowed(
+            &Url::parse("https://example.com")
+                .unwrap()
+                .join(path)
+                .unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_rule_matches() {
+        assert!(rule_matches("/", "/book/1"));
+        assert!(rule_matches("/book", "/book/1"));
+        assert!(!rule_matches("/book/2", "/book/1"));
+        assert!(rule_matches("/book/*/vip", "/book/12/vip/3"));
+        assert!(!rule_matches("/book/*/vip", "/book/12/free"));
+        assert!(rule_matches("/*.php$", "/index.php"));
+        assert!(!rule_matches("/*.php$", "/index.php?id=1"));
+        assert!(rule_matches("/index$", "/index"));
+        assert!(!rule_matches("/index$", "/index.html"));
+    }
+
+    #[test]
+    fn test_rule_matches_wildcards() {
+        assert!(rule_matches("*", "/"));
+        assert!(rule_matches("/book*", "/book"));
+        assert!(rule_matches("/book/*", "/book/1"));
+        assert!(!rule_matches("/book/*", "/books/1"));
+        // every `*` in order, each may match nothing
+        assert!(rule_matches("/a*b*c", "/abc"));
+        assert!(rule_matches("/a*b*c", "/a1b2c3"));
+        assert!(!rule_matches("/a*b*c", "/a1c2b3"));
+        assert!(rule_matches("/*/vip/*.html$", "/12/vip/3.html"));
+        assert!(!rule_matches("/*/vip/*.html$", "/12/vip/3.html/4"));
+        // `$` only at the end of a pattern anchors it
+        assert!(rule_matches("/*$", "/any/path"));
+        // no regex, `.` and `?` are plain chars
+        assert!(!rule_matches("/a.c", "/abc"));
+        assert!(rule_matches("/search?", "/search?q=1"));
+    }
+
+    #[test]
+    fn test_rule_precedence() {
+        let checker = RobotsTxtChecker::parse(
+            "User-agent: *\nAllow: /book\nDisallow: /book/vip\nAllow: /book/vip/free\n",
+        );
+        // the longest match decides, whichever comes first in the file
+        assert!(allowed(&checker, "/book/1"));
+        assert!(!allowed(&checker, "/book/vip/1"));
+        assert!(allowed(&checker, "/book/vip/free/1"));
+
+        // a wildcard counts with its pattern length, not the length it matches
+        let checker = RobotsTxtChecker::parse("User-agent: *\nDisallow: /\nAllow: /*.html$\n");
+        assert!(allowed(&checker, "/book/1.html"));
+        assert!(!allowed(&checker, "/book/1"));
+        let checker = RobotsTxtChecker::parse("User-agent: *\nAllow: /*\nDisallow: /book/*/vip\n");
+        assert!(!allowed(&checker, "/book/1/vip"));
+        assert!(allowed(&checker, "/book/1/free"));
+
+        // `Disallow` wins only when longer, the order of the lines does not matter
+        let checker = RobotsTxtChecker::parse("User-agent: *\nAllow: /a\nDisallow: /a\n");
+        assert!(allowed(&checker, "/a"));
+    }
+
+    #[test]
+    fn test_parse_agent_matching() {
+        // the agent and the keys are matched case-insensitively
+        let checker = RobotsTxtChecker::parse("USER-AGENT: Get_Novel\nDISALLOW: /\n");
+        assert!(!allowed(&checker, "/book/1"));
+
+        // groups of the same agent are merged, wherever they are in the file
+        let checker = RobotsTxtChecker::parse(
+            "User-agent: get_novel\nDisallow: /a\n\nUser-agent: *\nDisallow: /b\n\nUser-agent: get_novel\nDisallow: /c\n",
+        );
+        assert!(!allowed(&checker, "/a"));
+        assert!(allowed(&checker, "/b"));
+        assert!(!allowed(&checker, "/c"));
+
+        // a `User-agent` after rules starts a new group
+        let checker = RobotsTxtChecker::parse(
+            "User-agent: get_novel\nDisallow: /a\nUser-agent: bingbot\nDisallow: /b\n",
+        );
+        assert!(!allowed(&checker, "/a"));
+        assert!(allowed(&checker, "/b"));
+
+        // rules before any `User-agent` belong to no group
+        let checker = RobotsTxtChecker::parse("Disallow: /\nUser-agent: *\nDisallow: /b\n");
+        assert!(allowed(&checker, "/a"));
+        assert!(!allowed(&checker, "/b"));
+
+        // another agent's group is not used when no group names `get_novel` or `*`
+        assert!(allowed(
+            &RobotsTxtChecker::parse("User-agent: bingbot\nDisallow: /\n"),
+            "/a"
+        ));
+    }
+
+    #[test]
+    fn test_parse_agent_group() {
+        let checker = RobotsTxtChecker::parse(ROBOTS);
+        assert_eq!(checker.rules.len(), 3);
+        // only the `get_novel` group applies, not the `*` one
+        assert!(allowed(&checker, "/admin/"));
+        assert!(!allowed(&checker, "/book/2/vip/1"));
+        assert!(allowed(&checker, "/book/1/vip/1"));
+        assert!(!allowed(&checker, "/index.php"));
+        assert!(allowed(&checker, "/index.php?id=1"));
+    }
+
+    #[test]
+    fn test_parse_any_agent() {
+        let checker = RobotsTxtChecker::parse(&ROBOTS.replace("get_novel", "bingbot"));
+        assert!(!allowed(&checker, "/admin/secret"));
+        // the longer `Allow` wins
+        assert!(allowed(&checker, "/admin/public/1"));
+        assert!(!allowed(&checker, "/search?q=1"));
+        assert!(allowed(&checker, "/book/2/vip/1"));
+
+        assert!(allowed(&RobotsTxtChecker::parse(""), "/admin/"));
+        assert!(allowed(
+            &RobotsTxtChecker::parse("User-agent: *\nDisallow:\n"),
+            "/admin/"
+        ));
+        // `Allow` wins a tie
+        assert!(allowed(
+            &RobotsTxtChecker::parse("User-agent: *\nDisallow: /a\nAllow: /a\n"),
+            "/a"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_fetch() {
+        let mut server = mockito::Server::new_async().await;
+        let url = Url::parse(&server.url()).unwrap().join("/book/1/").unwrap();
+        let client = Client::new();
+
+        let missing = server
+            .mock("GET", "/robots.txt")
+            .with_status(404)
+            .create_async()
+            .await;
+        assert_eq!(
+            RobotsTxtChecker::fetch(&client, &url).await.unwrap(),
+            RobotsTxtChecker::default()
+        );
+        missing.assert_async().await;
+        missing.remove_async().await;
+
+        server
+            .mock("GET", "/robots.txt")
+            .with_body("User-agent: *\nDisallow: /book/\n")
+            .create_async()
+            .await;
+        assert!(matches!(
+            check_contents(&client, url.as_str()).await,
+            Err(NovelError::DisallowedByRobotsTxt(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_allowed_chapters() {
+        let mut server = mockito::Server::new_async().await;
+        let base = Url::parse(&server.url()).unwrap();
+        let robots = server
+            .mock("GET", "/robots.txt")
+            .with_body("User-agent: *\nDisallow: /vip/\n")
+            .create_async()
+            .await;
+
+        let urls = vec![
+            ("00001".to_string(), base.join("/book/1").unwrap()),
+            ("00002".to_string(), base.join("/vip/2").unwrap()),
+            ("00003".to_string(), base.join("/book/3").unwrap()),
+        ];
+        let allowed = allowed_chapters(&Client::new(), urls).await.unwrap();
+        let orders: Vec<&str> = allowed.iter().map(|(order, _)| order.as_str()).collect();
+        assert_eq!(orders, ["00001", "00003"]);
+        // fetched once for the site
+        robots.assert_async().await;
+    }
+}